@@ -0,0 +1,38 @@
+//! Build script for forge-e2e.
+//!
+//! Records the commit the binary was built from as `FORGE_E2E_COMMIT`, so
+//! report metadata names the sources it came from wherever it runs (empty
+//! outside a git checkout).
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Runs `git` with `args` and returns its trimmed stdout, or `None` on any
+/// failure.
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!stdout.is_empty()).then_some(stdout)
+}
+
+fn main() {
+    let commit = git(&["rev-parse", "HEAD"]).unwrap_or_default();
+    println!("cargo:rustc-env=FORGE_E2E_COMMIT={commit}");
+
+    // Rebuild when HEAD moves: a checkout changes HEAD, a commit changes
+    // the branch it points to (loose or packed)
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]).map(PathBuf::from) {
+        let mut watched = vec![git_dir.join("HEAD"), git_dir.join("packed-refs")];
+        if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            watched.push(git_dir.join(branch));
+        }
+        for path in watched.iter().filter(|path| path.exists()) {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    } else {
+        println!("cargo:rerun-if-changed=build.rs");
+    }
+}
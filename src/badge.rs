@@ -0,0 +1,158 @@
+//! Status badges for READMEs.
+//!
+//! Renders a run's pass rate either as a self-contained flat SVG or as a
+//! shields.io endpoint JSON file, colored by [`BadgeColor::for_rate`].
+
+use crate::report::RunSummary;
+
+/// Left-hand text of every badge.
+const BADGE_LABEL: &str = "forge-e2e";
+
+/// Pass rate (percent) at or above which the badge is green.
+const GREEN_MIN_RATE: f64 = 100.0;
+/// Pass rate (percent) at or above which the badge is yellow.
+const YELLOW_MIN_RATE: f64 = 90.0;
+
+/// Approximate width of one character at the badge font size, in pixels.
+const CHAR_WIDTH: usize = 7;
+/// Horizontal padding on each side of a badge half, in pixels.
+const PADDING: usize = 6;
+
+/// Badge background color, chosen from the pass rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadgeColor {
+    /// Every executed test passed.
+    Green,
+    /// Mostly passing.
+    Yellow,
+    /// Too many failures.
+    Red,
+    /// Nothing was executed.
+    Grey,
+}
+
+impl BadgeColor {
+    /// Picks the color for a pass rate (`None` when nothing ran).
+    pub fn for_rate(rate: Option<f64>) -> Self {
+        match rate {
+            None => Self::Grey,
+            Some(r) if r >= GREEN_MIN_RATE => Self::Green,
+            Some(r) if r >= YELLOW_MIN_RATE => Self::Yellow,
+            Some(_) => Self::Red,
+        }
+    }
+
+    /// Returns the shields.io color name.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Green => "brightgreen",
+            Self::Yellow => "yellow",
+            Self::Red => "red",
+            Self::Grey => "lightgrey",
+        }
+    }
+
+    /// Returns the SVG fill color.
+    pub const fn hex(self) -> &'static str {
+        match self {
+            Self::Green => "#4c1",
+            Self::Yellow => "#dfb317",
+            Self::Red => "#e05d44",
+            Self::Grey => "#9f9f9f",
+        }
+    }
+}
+
+/// Returns the right-hand badge text, e.g. `97.5% passing`.
+///
+/// The rate is rounded down so a badge never claims 100% with failures.
+fn badge_message(summary: &RunSummary) -> String {
+    summary.pass_rate().map_or_else(
+        || "no tests".to_string(),
+        |rate| format!("{}% passing", (rate * 10.0).floor() / 10.0),
+    )
+}
+
+/// Renders a flat SVG badge for `summary`.
+pub fn badge_svg(summary: &RunSummary) -> String {
+    let message = badge_message(summary);
+    let color = BadgeColor::for_rate(summary.pass_rate()).hex();
+    let label_width = BADGE_LABEL.len() * CHAR_WIDTH + 2 * PADDING;
+    let message_width = message.chars().count() * CHAR_WIDTH + 2 * PADDING;
+    let width = label_width + message_width;
+    let label_x = label_width / 2;
+    let message_x = label_width + message_width / 2;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{BADGE_LABEL}: {message}">
+  <title>{BADGE_LABEL}: {message}</title>
+  <rect width="{label_width}" height="20" fill="#555"/>
+  <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{BADGE_LABEL}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##
+    )
+}
+
+/// Builds a shields.io endpoint document for `summary`.
+///
+/// See <https://shields.io/badges/endpoint-badge>.
+pub fn badge_endpoint_json(summary: &RunSummary) -> serde_json::Value {
+    serde_json::json!({
+        "schemaVersion": 1,
+        "label": BADGE_LABEL,
+        "message": badge_message(summary),
+        "color": BadgeColor::for_rate(summary.pass_rate()).name(),
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn summary(passed: usize, failed: usize, skipped: usize) -> RunSummary {
+        RunSummary {
+            passed,
+            failed,
+            skipped,
+        }
+    }
+
+    #[test]
+    fn color_follows_thresholds() {
+        assert_eq!(BadgeColor::for_rate(Some(100.0)), BadgeColor::Green);
+        assert_eq!(BadgeColor::for_rate(Some(95.0)), BadgeColor::Yellow);
+        assert_eq!(BadgeColor::for_rate(Some(50.0)), BadgeColor::Red);
+        assert_eq!(BadgeColor::for_rate(None), BadgeColor::Grey);
+    }
+
+    #[test]
+    fn message_rounds_down_and_ignores_skips() {
+        assert_eq!(badge_message(&summary(10, 0, 3)), "100% passing");
+        assert_eq!(badge_message(&summary(1999, 1, 0)), "99.9% passing");
+        assert_eq!(badge_message(&summary(0, 0, 4)), "no tests");
+    }
+
+    #[test]
+    fn svg_contains_message_and_color() {
+        let svg = badge_svg(&summary(39, 1, 0));
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(">97.5% passing</text>"));
+        assert!(svg.contains(r##"fill="#dfb317""##));
+    }
+
+    #[test]
+    fn endpoint_json_matches_shields_schema() {
+        let json = badge_endpoint_json(&summary(2, 2, 0));
+        assert_eq!(json["schemaVersion"], 1);
+        assert_eq!(json["label"], "forge-e2e");
+        assert_eq!(json["message"], "50% passing");
+        assert_eq!(json["color"], "red");
+    }
+}
@@ -0,0 +1,318 @@
+//! Formula calculation backends for perf mode.
+//!
+//! Perf mode only needs forge's calculation engine, not the XLSX → Gnumeric
+//! pipeline. [`Calculator`] abstracts how a formula gets evaluated so the
+//! per-test `forge calculate` subprocess can be swapped for an in-process
+//! backend once forge-demo exposes a library.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Output;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::runner::{build_test_yaml, test_yaml, RESULT_LABEL};
+use crate::transport::{ForgeArg, LocalTransport, Transport};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Calculator
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Evaluates a single formula with forge's calculation engine.
+///
+/// Implementations are shared across rayon workers, so they must be
+/// `Send + Sync`.
+pub trait Calculator: Send + Sync {
+    /// Evaluates `formula` as a scalar in `config.section` and returns its value.
+    fn calculate(&self, config: &Config, formula: &str) -> Result<f64, String>;
+
+    /// Returns whether forge rejects `formula`, for tests expecting an
+    /// export error.
+    ///
+    /// Errors mean the answer is unknown (e.g. forge could not be run),
+    /// not that the formula was rejected. The default knows no way to
+    /// reject, so any error from [`Self::calculate`] is passed on.
+    fn rejects(&self, config: &Config, formula: &str) -> Result<bool, String> {
+        self.calculate(config, formula).map(|_| false)
+    }
+
+    /// Evaluates every formula in `formulas`, returning their values in
+    /// order (`--perf-batch`).
+    ///
+    /// The default evaluates them one by one; backends that can evaluate
+    /// many formulas at once should override it.
+    fn calculate_all(&self, config: &Config, formulas: &[&str]) -> Vec<Result<f64, String>> {
+        formulas
+            .iter()
+            .map(|formula| self.calculate(config, formula))
+            .collect()
+    }
+}
+
+/// Runs `forge calculate --dry-run` on a generated spec for every formula.
+pub struct SubprocessCalculator {
+    /// Path to the forge-demo binary.
+    forge_binary: PathBuf,
+    /// How forge-demo is invoked.
+    transport: Arc<dyn Transport>,
+}
+
+impl SubprocessCalculator {
+    /// Creates a calculator that shells out to a local `forge_binary`.
+    pub fn new(forge_binary: PathBuf) -> Self {
+        Self::with_transport(forge_binary, Arc::new(LocalTransport))
+    }
+
+    /// Creates a calculator that runs `forge_binary` through `transport`.
+    pub fn with_transport(forge_binary: PathBuf, transport: Arc<dyn Transport>) -> Self {
+        Self {
+            forge_binary,
+            transport,
+        }
+    }
+
+    /// Runs `forge calculate --dry-run` on the spec `yaml`, whatever its
+    /// exit status.
+    fn run_forge(&self, config: &Config, yaml: &str) -> Result<Output, String> {
+        let temp_dir =
+            tempfile::tempdir().map_err(|e| format!("Failed to create temp dir: {e}"))?;
+        let yaml_path = temp_dir.path().join("test.yaml");
+        fs::write(&yaml_path, yaml).map_err(|e| format!("Failed to write YAML: {e}"))?;
+
+        self.transport
+            .run(
+                &self.forge_binary,
+                config,
+                &[
+                    ForgeArg::Plain("calculate"),
+                    ForgeArg::Plain("--dry-run"),
+                    ForgeArg::Input(&yaml_path),
+                ],
+            )
+            .map_err(|e| format!("Failed to run forge calculate: {e}"))
+    }
+
+    /// Runs `forge calculate --dry-run` on the spec `yaml` and returns its
+    /// stdout.
+    fn run_calculate(&self, config: &Config, yaml: &str) -> Result<String, String> {
+        let output = self.run_forge(config, yaml)?;
+        if !output.status.success() {
+            return Err(format!(
+                "forge calculate failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl Calculator for SubprocessCalculator {
+    fn calculate(&self, config: &Config, formula: &str) -> Result<f64, String> {
+        let section = &config.section;
+        let stdout = self.run_calculate(config, &test_yaml(section, formula))?;
+
+        // Parse output: "<section>.test_result = <value>"
+        parse_calculate_output(&stdout, section, RESULT_LABEL)
+    }
+
+    /// A non-zero exit is a rejection; a signal is an error.
+    fn rejects(&self, config: &Config, formula: &str) -> Result<bool, String> {
+        let output = self.run_forge(config, &test_yaml(&config.section, formula))?;
+        output
+            .status
+            .code()
+            .map(|code| code != 0)
+            .ok_or_else(|| "forge calculate was killed by a signal".to_string())
+    }
+
+    /// Evaluates all formulas with a single `forge calculate` run, as
+    /// scalars `test_0`, `test_1`, ... of one spec.
+    ///
+    /// If that run fails, one bad formula may have sunk the rest, so each
+    /// formula is evaluated on its own instead.
+    fn calculate_all(&self, config: &Config, formulas: &[&str]) -> Vec<Result<f64, String>> {
+        if formulas.is_empty() {
+            return Vec::new();
+        }
+        let section = &config.section;
+        let names: Vec<String> = (0..formulas.len()).map(|i| format!("test_{i}")).collect();
+        let cases: Vec<(&str, &str)> = names
+            .iter()
+            .map(String::as_str)
+            .zip(formulas.iter().copied())
+            .collect();
+        let Ok(stdout) = self.run_calculate(config, &build_test_yaml(section, &cases)) else {
+            return formulas
+                .iter()
+                .map(|formula| self.calculate(config, formula))
+                .collect();
+        };
+        let mut values = parse_calculate_outputs(&stdout, section);
+        names
+            .iter()
+            .map(|name| {
+                values
+                    .remove(name.as_str())
+                    .unwrap_or_else(|| Err(format!("Could not find {name} in output")))
+            })
+            .collect()
+    }
+}
+
+/// Parses `forge calculate` output to extract a value.
+///
+/// Output format: `<section>.<name> = <value>`
+fn parse_calculate_output(output: &str, section: &str, var_name: &str) -> Result<f64, String> {
+    let pattern = format!("{section}.{var_name} = ");
+    for line in output.lines() {
+        if let Some(rest) = line.trim().strip_prefix(&pattern) {
+            return rest
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| format!("Failed to parse value: {e}"));
+        }
+    }
+    Err(format!("Could not find {var_name} in output"))
+}
+
+/// Parses `forge calculate` output to extract every value in `section`,
+/// keyed by scalar name.
+///
+/// Output format: one `<section>.<name> = <value>` line per scalar. A
+/// scalar printed twice keeps its first value, as in
+/// [`parse_calculate_output`].
+fn parse_calculate_outputs<'a>(
+    output: &'a str,
+    section: &str,
+) -> HashMap<&'a str, Result<f64, String>> {
+    let mut values = HashMap::new();
+    let lines = output.lines().filter_map(|line| {
+        let (name, value) = line
+            .trim()
+            .strip_prefix(section)?
+            .strip_prefix('.')?
+            .split_once(" = ")?;
+        let value = value
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("Failed to parse value of {name}: {e}"));
+        Some((name, value))
+    });
+    for (name, value) in lines {
+        values.entry(name).or_insert(value);
+    }
+    values
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
+
+    #[test]
+    fn parse_calculate_output_uses_section() {
+        let output = "assumptions.test_result = 1\ninputs.test_result = 2\n";
+        let value = parse_calculate_output(output, "inputs", RESULT_LABEL).unwrap();
+        assert!((value - 2.0).abs() < f64::EPSILON);
+        assert!(parse_calculate_output(output, "outputs", RESULT_LABEL).is_err());
+    }
+
+    #[test]
+    fn parse_calculate_outputs_reads_every_scalar() {
+        let output =
+            "inputs.test_0 = 1.5\noutputs.test_1 = 9\n  inputs.test_1 = -2\ninputs.test_2 = oops\n";
+        let values = parse_calculate_outputs(output, "inputs");
+        assert_eq!(values.len(), 3);
+        assert_eq!(values["test_0"], Ok(1.5));
+        assert_eq!(values["test_1"], Ok(-2.0));
+        assert!(values["test_2"].as_ref().unwrap_err().contains("test_2"));
+
+        let values = parse_calculate_outputs("inputs.test_0 = 1\ninputs.test_0 = 2\n", "inputs");
+        assert_eq!(values["test_0"], Ok(1.0));
+    }
+
+    /// Writes an executable fake forge-demo that runs `script`.
+    #[cfg(unix)]
+    fn fake_forge(dir: &std::path::Path, script: &str) -> PathBuf {
+        fake_binary(dir, "forge-demo", script)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn subprocess_calculator_reads_forge_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let forge = fake_forge(
+            dir.path(),
+            r#"[ "$1" = calculate ] && grep -q '=6\*7' "$3" && echo "inputs.test_result = 42""#,
+        );
+        let config = Config {
+            section: "inputs".to_string(),
+            ..Config::default()
+        };
+        let calc = SubprocessCalculator::new(forge);
+        let value = calc.calculate(&config, "=6*7").unwrap();
+        assert!((value - 42.0).abs() < f64::EPSILON);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn subprocess_calculator_reports_forge_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let forge = fake_forge(dir.path(), "echo boom >&2; exit 1");
+        let err = SubprocessCalculator::new(forge)
+            .calculate(&Config::default(), "=1")
+            .unwrap_err();
+        assert!(err.contains("forge calculate failed: boom"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn subprocess_calculator_evaluates_all_formulas_in_one_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls = dir.path().join("calls");
+        let forge = fake_forge(
+            dir.path(),
+            &format!(
+                r#"echo >> "{}"
+grep -q '=6\*7' "$3" && grep -q '=1+1' "$3" && printf 'assumptions.test_1 = 2\nassumptions.test_0 = 42\n'"#,
+                calls.display()
+            ),
+        );
+        let values = SubprocessCalculator::new(forge)
+            .calculate_all(&Config::default(), &["=6*7", "=1+1", "=3"]);
+        assert_eq!(values[..2], [Ok(42.0), Ok(2.0)]);
+        assert_eq!(
+            values[2],
+            Err("Could not find test_2 in output".to_string())
+        );
+        assert_eq!(fs::read_to_string(calls).unwrap().lines().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn subprocess_calculator_falls_back_to_each_formula_when_the_run_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls = dir.path().join("calls");
+        let forge = fake_forge(
+            dir.path(),
+            &format!(
+                r#"echo >> "{}"
+grep -q BAD "$3" && {{ echo bad formula >&2; exit 1; }}
+echo "assumptions.test_result = 7""#,
+                calls.display()
+            ),
+        );
+        let values =
+            SubprocessCalculator::new(forge).calculate_all(&Config::default(), &["=7", "=BAD()"]);
+        assert_eq!(values[0], Ok(7.0));
+        assert!(values[1].as_ref().unwrap_err().contains("bad formula"));
+        assert_eq!(fs::read_to_string(calls).unwrap().lines().count(), 3);
+    }
+}
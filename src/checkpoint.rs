@@ -0,0 +1,167 @@
+//! Checkpoints that let an interrupted `--all` run resume.
+//!
+//! With `--resume FILE`, every normal-mode result is appended to FILE as
+//! one JSON line as soon as the test finishes. Restarting with the same
+//! file reuses those results instead of running the tests again, so a
+//! multi-hour run only repeats the test that was in flight. The file is
+//! removed once a run completes.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::types::{TestCase, TestResult};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Checkpoint
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Results recorded by earlier, interrupted runs, and the file new results
+/// are appended to.
+#[derive(Debug)]
+pub struct Checkpoint {
+    /// Path of the checkpoint file.
+    path: PathBuf,
+    /// Recorded results by test key.
+    completed: HashMap<String, TestResult>,
+    /// The checkpoint file, open for appending.
+    file: Mutex<File>,
+}
+
+impl Checkpoint {
+    /// Loads the results recorded in `path` and opens it for appending,
+    /// creating it if needed.
+    ///
+    /// Lines that don't parse (e.g. one cut short by the interruption) are
+    /// ignored, so their tests simply run again.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        };
+        let completed = content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<TestResult>(line).ok())
+            .map(|result| (result.key().to_string(), result))
+            .collect();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            completed,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Returns the number of recorded results.
+    pub fn len(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Returns the recorded result for `case`, if it ran with the same
+    /// formula and expected value.
+    ///
+    /// A test edited since it was recorded runs again.
+    pub fn completed(&self, case: &TestCase) -> Option<TestResult> {
+        let result = self.completed.get(case.key())?;
+        let (formula, expected) = match result {
+            TestResult::Pass {
+                formula, expected, ..
+            }
+            | TestResult::Fail {
+                formula, expected, ..
+            } => (formula, *expected),
+            TestResult::Skip { .. } => return None,
+        };
+        let unchanged = *formula == case.formula && expected.to_bits() == case.expected.to_bits();
+        unchanged.then(|| result.clone())
+    }
+
+    /// Appends `result` to the checkpoint file.
+    pub fn record(&self, result: &TestResult) -> Result<(), String> {
+        let mut line = serde_json::to_string(result).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| "checkpoint file lock poisoned".to_string())?;
+        // One write per line, so an interruption can only truncate the last
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {e}", self.path.display()))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, formula: &str) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            formula: formula.to_string(),
+            expected: 2.0,
+            ..TestCase::default()
+        }
+    }
+
+    #[test]
+    fn recorded_results_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.checkpoint");
+        let one = case("math.test_one", "=1+1");
+        let two = case("math.test_two", "=4/2");
+
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.len(), 0);
+        checkpoint
+            .record(&TestResult::pass(&one, 2.0, false))
+            .unwrap();
+        checkpoint
+            .record(&TestResult::fail(&two, Some(3.0), None))
+            .unwrap();
+        drop(checkpoint);
+        // A line cut short by the interruption is ignored
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"status":"pass","name":"math.te"#)
+            .unwrap();
+
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.len(), 2);
+        assert!(checkpoint.completed(&one).is_some_and(|r| r.is_pass()));
+        assert!(checkpoint.completed(&two).is_some_and(|r| r.is_fail()));
+        assert!(checkpoint
+            .completed(&case("math.test_three", "=3"))
+            .is_none());
+    }
+
+    #[test]
+    fn edited_tests_are_not_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.checkpoint");
+        let original = case("math.test_one", "=1+1");
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        checkpoint
+            .record(&TestResult::pass(&original, 2.0, false))
+            .unwrap();
+
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert!(checkpoint
+            .completed(&case("math.test_one", "=2*1"))
+            .is_none());
+        let expected_changed = TestCase {
+            expected: 3.0,
+            ..original
+        };
+        assert!(checkpoint.completed(&expected_changed).is_none());
+    }
+}
@@ -0,0 +1,189 @@
+//! Run configuration for forge-e2e.
+//!
+//! Collects the options that shape how tests are executed, so the CLI can
+//! build them once and the runner can read them from one place.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::engine::RecalcStrategy;
+use crate::types::TestCase;
+
+/// Section that holds the generated test scalars by default.
+pub const DEFAULT_SECTION: &str = "assumptions";
+
+/// Environment variable forge-demo reads (as `YYYY-MM-DD`) to freeze the
+/// date `TODAY()`/`NOW()` return, instead of using the system clock.
+pub const TODAY_ENV: &str = "FORGE_TODAY";
+
+/// Absolute tolerance used when neither the test nor its category sets one.
+pub const DEFAULT_TOLERANCE: f64 = f64::EPSILON;
+
+/// Options controlling test execution.
+///
+/// Serializes to the JSON recorded in run manifests and reports.
+#[derive(Debug, Clone, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Config {
+    /// How ssconvert treats cached formula values.
+    #[serde(serialize_with = "serialize_value_enum")]
+    pub recalc: RecalcStrategy,
+    /// Maximum number of tests per XLSX in batch mode (`None` = all in one).
+    pub batch_size: Option<NonZeroUsize>,
+    /// Maximum concurrent forge-demo exports (`None` = as wide as the
+    /// thread pool).
+    pub export_jobs: Option<NonZeroUsize>,
+    /// Maximum concurrent ssconvert conversions (`None` = as wide as the
+    /// thread pool).
+    pub convert_jobs: Option<NonZeroUsize>,
+    /// Threads perf mode runs on (`None` = rayon's global pool, one per
+    /// core), so tests/sec can be compared across machines.
+    pub perf_threads: Option<NonZeroUsize>,
+    /// Whether perf mode evaluates all formulas with one `forge calculate`
+    /// run instead of one run per test.
+    pub perf_batch: bool,
+    /// Section name for generated scalars; also the prefix forge-demo
+    /// uses when reporting them (`<section>.test_result`).
+    pub section: String,
+    /// Frozen "today" passed to forge-demo via [`TODAY_ENV`].
+    ///
+    /// Requires a forge-demo build that honors the variable. Gnumeric still
+    /// uses the system clock when it recalculates.
+    #[serde(serialize_with = "serialize_date")]
+    pub today: Option<NaiveDate>,
+    /// Whether failures to find a result in the CSV include the CSV's
+    /// first lines in the error.
+    pub show_csv_on_fail: bool,
+    /// Leading CSV columns that hold labels rather than values (`None` =
+    /// detect per CSV); see [`crate::runner::detect_label_columns`].
+    pub label_columns: Option<usize>,
+    /// Whether a test without a labeled result cell may fall back to any
+    /// cell matching its expected value.
+    pub heuristic_match: bool,
+    /// Absolute tolerance for tests without a per-test or category default.
+    pub tolerance: f64,
+    /// Default tolerance per test category (the name's section prefix,
+    /// e.g. `math` in `math.test_sqrt`).
+    pub category_tolerances: HashMap<String, f64>,
+    /// Whether normal-mode passes are checked against `forge calculate`
+    /// too, noting where it disagrees with the engine.
+    pub annotate_forge: bool,
+    /// Whether normal-mode passes also compare the formula forge-demo
+    /// exported with the test's own, failing when they differ.
+    pub compare_formulas: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            recalc: RecalcStrategy::default(),
+            batch_size: None,
+            export_jobs: None,
+            convert_jobs: None,
+            perf_threads: None,
+            perf_batch: false,
+            section: DEFAULT_SECTION.to_string(),
+            today: None,
+            show_csv_on_fail: false,
+            label_columns: None,
+            heuristic_match: true,
+            tolerance: DEFAULT_TOLERANCE,
+            category_tolerances: HashMap::new(),
+            annotate_forge: false,
+            compare_formulas: false,
+        }
+    }
+}
+
+impl Config {
+    /// Returns the tolerance `test_case` is compared with.
+    ///
+    /// Precedence: the test's own `tolerance`, then its category's default,
+    /// then the global [`Config::tolerance`].
+    pub fn tolerance_for(&self, test_case: &TestCase) -> f64 {
+        test_case
+            .tolerance
+            .or_else(|| {
+                let category = test_case.name.split('.').next()?;
+                self.category_tolerances.get(category).copied()
+            })
+            .unwrap_or(self.tolerance)
+    }
+
+    /// Returns the options as a JSON object, for the run manifest.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+}
+
+/// Serializes a command-line value enum as the name it is given by.
+pub fn serialize_value_enum<T: ValueEnum, S: serde::Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .serialize(serializer)
+}
+
+/// Serializes a date as `YYYY-MM-DD`.
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+fn serialize_date<S: serde::Serializer>(
+    date: &Option<NaiveDate>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    date.map(|d| d.to_string()).serialize(serializer)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, tolerance: Option<f64>) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            formula: "=STDEV(1, 2, 3)".to_string(),
+            expected: 1.0,
+            tolerance,
+            ..TestCase::default()
+        }
+    }
+
+    #[test]
+    fn json_lists_every_option() {
+        let config = Config {
+            recalc: RecalcStrategy::Compare,
+            today: NaiveDate::from_ymd_opt(2024, 6, 15),
+            ..Config::default()
+        };
+        let json = config.to_json();
+        assert_eq!(json["recalc"], "compare");
+        assert_eq!(json["today"], "2024-06-15");
+        assert_eq!(json["section"], DEFAULT_SECTION);
+        assert_eq!(json["perf_batch"], false);
+        assert_eq!(json.as_object().unwrap().len(), 15);
+    }
+
+    #[test]
+    fn tolerance_prefers_test_then_category_then_global() {
+        let config = Config {
+            tolerance: 1e-6,
+            category_tolerances: HashMap::from([("stats".to_string(), 1e-3)]),
+            ..Config::default()
+        };
+
+        assert!((config.tolerance_for(&case("stats.test_stdev", Some(0.5))) - 0.5).abs() < 1e-12);
+        assert!((config.tolerance_for(&case("stats.test_stdev", None)) - 1e-3).abs() < 1e-12);
+        assert!((config.tolerance_for(&case("math.test_sqrt", None)) - 1e-6).abs() < 1e-12);
+        assert!((config.tolerance_for(&case("test_bare", None)) - 1e-6).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,241 @@
+//! Function coverage tracking.
+//!
+//! Infers which Excel functions a run exercised from test names
+//! (`math.test_sin_zero` → `SIN` in `math`), shared by the TUI stats panel
+//! and the headless `--min-coverage` gate.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Demo mode function count (v1.0.0 schema).
+pub const DEMO_FUNCTION_COUNT: usize = 48;
+/// Full build function count (R&D, not for sale).
+pub const FULL_BUILD_FUNCTION_COUNT: usize = 173;
+
+/// Demo functions documented in `docs/FUNCTIONS.md`.
+pub const DEMO_FUNCTIONS: &[&str] = &[
+    // Math
+    "ABS",
+    "SQRT",
+    "ROUND",
+    "ROUNDUP",
+    "ROUNDDOWN",
+    "FLOOR",
+    "CEILING",
+    "MOD",
+    "POWER",
+    // Aggregation
+    "SUM",
+    "AVERAGE",
+    "MIN",
+    "MAX",
+    "COUNT",
+    // Logical
+    "IF",
+    "AND",
+    "OR",
+    "NOT",
+    "IFERROR",
+    // Text
+    "CONCAT",
+    "LEFT",
+    "RIGHT",
+    "MID",
+    "LEN",
+    "UPPER",
+    "LOWER",
+    "TRIM",
+    "REPT",
+    // Date
+    "TODAY",
+    "DATE",
+    "YEAR",
+    "MONTH",
+    "DAY",
+    "DATEDIF",
+    // Lookup
+    "INDEX",
+    "MATCH",
+    "CHOOSE",
+];
+
+/// Functions exercised by a run, grouped by test category.
+#[derive(Debug, Default)]
+pub struct FunctionCoverage {
+    /// Category -> set of function names.
+    by_category: HashMap<String, HashSet<String>>,
+}
+
+impl FunctionCoverage {
+    /// Builds coverage from a set of test names.
+    pub fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut coverage = Self::default();
+        for name in names {
+            coverage.record(name);
+        }
+        coverage
+    }
+
+    /// Records the function exercised by the test `name`, if recognizable.
+    pub fn record(&mut self, name: &str) {
+        // Test names like "assumptions.test_sin_zero" -> extract "SIN"
+        let parts: Vec<&str> = name.split('.').collect();
+        if parts.len() >= 2 {
+            let category = parts[0].to_string();
+            let test_name = parts[1..].join(".");
+            if let Some(func) = extract_function_name(&test_name) {
+                self.by_category.entry(category).or_default().insert(func);
+            }
+        }
+    }
+
+    /// Forgets all recorded functions.
+    pub fn clear(&mut self) {
+        self.by_category.clear();
+    }
+
+    /// Returns the number of distinct (category, function) pairs tested.
+    pub fn unique_functions_tested(&self) -> usize {
+        self.by_category.values().map(HashSet::len).sum()
+    }
+
+    /// Returns `(category, function count)`, most-covered first.
+    pub fn by_category(&self) -> Vec<(&str, usize)> {
+        let mut cats: Vec<_> = self
+            .by_category
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.len()))
+            .collect();
+        cats.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        cats
+    }
+
+    /// Returns coverage of the demo function set as a percentage.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percent(&self) -> f64 {
+        self.unique_functions_tested() as f64 * 100.0 / DEMO_FUNCTION_COUNT as f64
+    }
+
+    /// Returns `true` if some test exercised `func` (uppercase).
+    pub fn covers(&self, func: &str) -> bool {
+        self.by_category.values().any(|set| set.contains(func))
+    }
+
+    /// Returns documented demo functions no test exercised.
+    pub fn missing_demo_functions(&self) -> Vec<&'static str> {
+        DEMO_FUNCTIONS
+            .iter()
+            .copied()
+            .filter(|func| !self.covers(func))
+            .collect()
+    }
+}
+
+/// How much of the full build's function set a run covers.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FullBuildParity {
+    /// Full build functions some passing test exercised.
+    pub covered: Vec<String>,
+    /// Full build functions still only in the full build.
+    pub locked: Vec<String>,
+}
+
+impl FullBuildParity {
+    /// Splits `functions` by whether `coverage` exercised them, keeping
+    /// their order.
+    pub fn of(functions: &[String], coverage: &FunctionCoverage) -> Self {
+        let (covered, locked) = functions
+            .iter()
+            .cloned()
+            .partition(|func| coverage.covers(func));
+        Self { covered, locked }
+    }
+}
+
+/// Reads a list of function names: one per line (or comma-separated),
+/// with `#` starting a comment. Names are uppercased and deduplicated.
+pub fn read_function_list(path: &Path) -> Result<Vec<String>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let mut seen = HashSet::new();
+    Ok(content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(|line| line.split(','))
+        .map(|name| name.trim().to_uppercase())
+        .filter(|name| !name.is_empty() && seen.insert(name.clone()))
+        .collect())
+}
+
+/// Extract Excel function name from test name.
+/// `test_sin_zero` -> `SIN`, `test_roundup_pos` -> `ROUNDUP`
+fn extract_function_name(test_name: &str) -> Option<String> {
+    let name = test_name.strip_prefix("test_")?;
+    // Find the function part (up to the next underscore that precedes a variant)
+    // e.g., "sin_zero" -> "sin", "roundup_positive" -> "roundup"
+    let parts: Vec<&str> = name.split('_').collect();
+    if parts.is_empty() {
+        return None;
+    }
+    // Handle multi-part function names like "round_up" vs single "sin"
+    // Check if combining first parts makes a known pattern
+    let func = parts[0].to_uppercase();
+    Some(func)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_function_from_test_name() {
+        assert_eq!(extract_function_name("test_sin_zero"), Some("SIN".into()));
+        assert_eq!(
+            extract_function_name("test_roundup_pos"),
+            Some("ROUNDUP".into())
+        );
+        assert_eq!(extract_function_name("sin_zero"), None);
+    }
+
+    #[test]
+    fn missing_lists_untested_demo_functions() {
+        let names: Vec<String> = DEMO_FUNCTIONS
+            .iter()
+            .skip(2)
+            .map(|f| format!("math.test_{}_basic", f.to_lowercase()))
+            .collect();
+        let coverage = FunctionCoverage::from_names(names.iter().map(String::as_str));
+        assert_eq!(coverage.missing_demo_functions(), vec!["ABS", "SQRT"]);
+        assert_eq!(coverage.unique_functions_tested(), DEMO_FUNCTIONS.len() - 2);
+    }
+
+    #[test]
+    fn full_build_parity_splits_covered_and_locked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("full-build.txt");
+        fs::write(&path, "# full build\nabs\nXIRR, NPV\n\nABS  # again\n").unwrap();
+        let functions = read_function_list(&path).unwrap();
+        assert_eq!(functions, ["ABS", "XIRR", "NPV"]);
+
+        let coverage = FunctionCoverage::from_names(["math.test_abs_neg", "finance.test_npv"]);
+        assert_eq!(
+            FullBuildParity::of(&functions, &coverage),
+            FullBuildParity {
+                covered: vec!["ABS".to_string(), "NPV".to_string()],
+                locked: vec!["XIRR".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn percent_is_relative_to_demo_count() {
+        let names: Vec<String> = (0..12).map(|i| format!("math.test_f{i}")).collect();
+        let coverage = FunctionCoverage::from_names(names.iter().map(String::as_str));
+        assert!((coverage.percent() - 25.0).abs() < f64::EPSILON);
+    }
+}
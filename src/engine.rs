@@ -2,30 +2,82 @@
 //!
 //! Uses Gnumeric's ssconvert for XLSX to CSV conversion with formula recalculation.
 
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::excel;
+
+/// Formula converted at startup to check that `--recalc` works.
+const RECALC_PROBE_FORMULA: &str = "=1+1";
+/// Correct value of [`RECALC_PROBE_FORMULA`].
+const RECALC_PROBE_EXPECTED: f64 = 2.0;
+/// Deliberately wrong value cached in the probe XLSX.
+const RECALC_PROBE_CACHED: &str = "0";
+
+/// Conversion attempts before a transient ssconvert failure is reported.
+const MAX_CONVERT_ATTEMPTS: u32 = 4;
+/// Delay before the first retry; doubles after each further failure.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+/// Error fragments of ssconvert failures caused by temp file contention
+/// under parallel load, which are worth retrying.
+const TRANSIENT_ERRORS: &[&str] = &[
+    "Resource temporarily unavailable",
+    "Device or resource busy",
+    "Text file busy",
+    "temporary file",
+    "CSV file not created",
+];
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Spreadsheet Engine
 // ─────────────────────────────────────────────────────────────────────────────
 
+/// How ssconvert treats the formula values cached in the exported XLSX.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum RecalcStrategy {
+    /// Recalculate every formula (`--recalc`).
+    #[default]
+    Always,
+    /// Read the values forge-demo cached in the XLSX as-is.
+    Never,
+    /// Recalculate, and also flag tests whose cached value differs.
+    Compare,
+}
+
 /// Gnumeric spreadsheet engine for formula recalculation.
+#[derive(Clone)]
 pub struct SpreadsheetEngine {
     /// Path to the ssconvert binary.
     path: PathBuf,
     /// Version string from ssconvert.
     version: String,
+    /// Whether to pass `--recalc` to ssconvert.
+    recalc: bool,
 }
 
 impl SpreadsheetEngine {
     /// Engine name constant.
     const NAME: &'static str = "Gnumeric (ssconvert)";
 
-    /// Detects Gnumeric (ssconvert) installation.
+    /// Detects a Gnumeric (ssconvert) installation that can recalculate.
     ///
-    /// Returns `Some(engine)` if ssconvert is found and working,
-    /// `None` otherwise.
-    pub fn detect() -> Option<Self> {
+    /// Fails if ssconvert is missing, or if it exports cached formula
+    /// values even with `--recalc` (see [`Self::verify_recalc`]).
+    pub fn detect() -> Result<Self, String> {
+        let engine = Self::find().ok_or(
+            "Gnumeric not found. Install with: brew install gnumeric (macOS) or apt install gnumeric (Linux)",
+        )?;
+        engine.verify_recalc()?;
+        Ok(engine)
+    }
+
+    /// Locates a working ssconvert binary on the `PATH`.
+    fn find() -> Option<Self> {
         let output = Command::new("ssconvert").arg("--version").output().ok()?;
 
         if output.status.success() {
@@ -33,12 +85,33 @@ impl SpreadsheetEngine {
             Some(Self {
                 path: PathBuf::from("ssconvert"),
                 version,
+                recalc: true,
             })
         } else {
             None
         }
     }
 
+    /// Creates an engine backed by an arbitrary ssconvert-compatible binary.
+    #[cfg(test)]
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            path,
+            version: "test".to_string(),
+            recalc: true,
+        }
+    }
+
+    /// Sets whether formulas are recalculated on conversion.
+    ///
+    /// With recalculation off, the CSV contains the values forge-demo
+    /// cached in the XLSX, which is useful for catching stale caches.
+    #[must_use]
+    pub const fn with_recalc(mut self, recalc: bool) -> Self {
+        self.recalc = recalc;
+        self
+    }
+
     /// Returns the engine version string.
     pub fn version(&self) -> &str {
         &self.version
@@ -49,10 +122,51 @@ impl SpreadsheetEngine {
         Self::NAME
     }
 
+    /// Checks that ssconvert really recalculates formulas.
+    ///
+    /// Some Gnumeric builds accept `--recalc` but ignore it, so every test
+    /// would read forge-demo's cached values instead. Converts a workbook
+    /// whose cached result for `=1+1` is wrong and expects the correct one.
+    pub fn verify_recalc(&self) -> Result<(), String> {
+        let temp_dir =
+            tempfile::tempdir().map_err(|e| format!("Failed to create temp dir: {e}"))?;
+        let xlsx_path = temp_dir.path().join("recalc_probe.xlsx");
+        excel::create_formula_xlsx(&xlsx_path, RECALC_PROBE_FORMULA, RECALC_PROBE_CACHED)
+            .map_err(|e| format!("Failed to write recalc probe: {e}"))?;
+
+        let csv_path = self
+            .clone()
+            .with_recalc(true)
+            .xlsx_to_csv(&xlsx_path, temp_dir.path())?;
+        let csv = fs::read_to_string(&csv_path)
+            .map_err(|e| format!("Failed to read recalc probe CSV: {e}"))?;
+        let cell = csv.split([',', '\n']).next().unwrap_or("").trim();
+        match cell.trim_matches('"').parse::<f64>() {
+            Ok(v) if (v - RECALC_PROBE_EXPECTED).abs() < f64::EPSILON => Ok(()),
+            _ => Err(format!(
+                "{} does not recalculate formulas: {RECALC_PROBE_FORMULA} exported as {cell:?} \
+                 instead of {RECALC_PROBE_EXPECTED}. This Gnumeric build likely lacks \
+                 recalculation support; install a full build (e.g. your distribution's \
+                 gnumeric package or Homebrew's) and try again.",
+                self.path.display()
+            )),
+        }
+    }
+
+    /// Builds the base ssconvert command with the recalc flag applied.
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.path);
+        if self.recalc {
+            command.arg("--recalc");
+        }
+        command
+    }
+
     /// Converts XLSX to CSV with formula recalculation.
     ///
-    /// Uses ssconvert with the `--recalc` flag to ensure all formulas
-    /// are recalculated before export.
+    /// Uses ssconvert with the `--recalc` flag (unless disabled via
+    /// [`Self::with_recalc`]) to ensure all formulas are recalculated
+    /// before export.
     pub fn xlsx_to_csv(&self, xlsx_path: &Path, output_dir: &Path) -> Result<PathBuf, String> {
         let csv_name = xlsx_path
             .file_stem()
@@ -62,10 +176,51 @@ impl SpreadsheetEngine {
             + ".csv";
         let csv_path = output_dir.join(&csv_name);
 
-        let output = Command::new(&self.path)
-            .arg("--recalc")
+        retry_transient(|| {
+            let output = self
+                .command()
+                .arg(xlsx_path)
+                .arg(&csv_path)
+                .output()
+                .map_err(|e| format!("Failed to run ssconvert: {e}"))?;
+
+            if !output.status.success() {
+                return Err(format!(
+                    "ssconvert failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            if csv_path.exists() {
+                Ok(csv_path.clone())
+            } else {
+                Err(format!("CSV file not created: {}", csv_path.display()))
+            }
+        })
+    }
+
+    /// Converts XLSX to one CSV per worksheet with formula recalculation.
+    ///
+    /// Uses ssconvert's `--export-file-per-sheet` so every sheet is exported,
+    /// not just the first one. Output files are named `<stem>.<sheet>.csv`
+    /// and returned sorted by path.
+    pub fn xlsx_to_csv_per_sheet(
+        &self,
+        xlsx_path: &Path,
+        output_dir: &Path,
+    ) -> Result<Vec<PathBuf>, String> {
+        let stem = xlsx_path
+            .file_stem()
+            .ok_or("Invalid xlsx path: no file stem")?
+            .to_string_lossy()
+            .to_string();
+        let csv_template = output_dir.join(format!("{stem}.%s.csv"));
+
+        let output = self
+            .command()
+            .arg("--export-file-per-sheet")
             .arg(xlsx_path)
-            .arg(&csv_path)
+            .arg(&csv_template)
             .output()
             .map_err(|e| format!("Failed to run ssconvert: {e}"))?;
 
@@ -76,14 +231,57 @@ impl SpreadsheetEngine {
             ));
         }
 
-        if csv_path.exists() {
-            Ok(csv_path)
+        let csv_paths = collect_sheet_csvs(output_dir, &stem);
+        if csv_paths.is_empty() {
+            Err(format!("No CSV files created in {}", output_dir.display()))
         } else {
-            Err(format!("CSV file not created: {}", csv_path.display()))
+            Ok(csv_paths)
+        }
+    }
+}
+
+/// Runs `convert`, retrying with exponential backoff while it fails with
+/// one of the [`TRANSIENT_ERRORS`]. Other errors are returned immediately.
+fn retry_transient<T>(mut convert: impl FnMut() -> Result<T, String>) -> Result<T, String> {
+    let mut delay = RETRY_BASE_DELAY;
+    let mut attempt = 1;
+    loop {
+        match convert() {
+            Err(e) if attempt < MAX_CONVERT_ATTEMPTS && is_transient(&e) => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
         }
     }
 }
 
+/// Returns `true` if an ssconvert error looks like temp file contention.
+fn is_transient(error: &str) -> bool {
+    TRANSIENT_ERRORS.iter().any(|sig| error.contains(sig))
+}
+
+/// Collects the per-sheet CSV files written for `stem` in `output_dir`.
+fn collect_sheet_csvs(output_dir: &Path, stem: &str) -> Vec<PathBuf> {
+    let prefix = format!("{stem}.");
+    let Ok(entries) = fs::read_dir(output_dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|e| e == "csv")
+                && path
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with(&prefix))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────────────────
@@ -91,6 +289,8 @@ impl SpreadsheetEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
 
     #[test]
     fn engine_name_is_constant() {
@@ -98,10 +298,108 @@ mod tests {
     }
 
     #[test]
-    fn engine_detection_returns_valid_engine_or_none() {
+    fn collect_sheet_csvs_finds_only_matching_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("test.Scalars.csv"), "").unwrap();
+        fs::write(temp_dir.path().join("test.assumptions.csv"), "").unwrap();
+        fs::write(temp_dir.path().join("test.xlsx"), "").unwrap();
+        fs::write(temp_dir.path().join("other.Scalars.csv"), "").unwrap();
+
+        let paths = collect_sheet_csvs(temp_dir.path(), "test");
+        let names: Vec<_> = paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["test.Scalars.csv", "test.assumptions.csv"]);
+    }
+
+    #[test]
+    fn engine_detection_returns_valid_engine_or_error() {
         // This test may skip if Gnumeric is not installed
-        // Just verify detect() returns Some when ssconvert exists
+        // Just verify detect() returns Ok when a working ssconvert exists
         let _ = SpreadsheetEngine::detect();
         // No assertion - we just verify it doesn't panic
     }
+
+    /// Writes a fake ssconvert that outputs `recalc_value` with `--recalc`
+    /// and the cached value (0) otherwise.
+    #[cfg(unix)]
+    fn fake_ssconvert(dir: &Path, recalc_value: &str) -> SpreadsheetEngine {
+        let script = format!(
+            r#"v=0; for arg; do [ "$arg" = --recalc ] && v={recalc_value}; out="$arg"; done
+echo "$v" > "$out""#
+        );
+        SpreadsheetEngine::with_path(fake_binary(dir, "ssconvert", &script))
+    }
+
+    #[test]
+    fn retry_transient_stops_on_success_or_permanent_error() {
+        let mut calls = 0;
+        let result = retry_transient(|| {
+            calls += 1;
+            if calls < 3 {
+                Err("ssconvert failed: Resource temporarily unavailable".to_string())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result, Ok(3));
+
+        let mut calls = 0;
+        let result: Result<(), String> = retry_transient(|| {
+            calls += 1;
+            Err("ssconvert failed: Unsupported file format".to_string())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let _: Result<(), String> = retry_transient(|| {
+            calls += 1;
+            Err("CSV file not created: out.csv".to_string())
+        });
+        assert_eq!(calls, MAX_CONVERT_ATTEMPTS);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn xlsx_to_csv_retries_after_transient_failure() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let marker = temp_dir.path().join("failed_once");
+        let script = format!(
+            r#"if [ ! -e "{marker}" ]; then
+  touch "{marker}"
+  echo "Could not create temporary file: Resource temporarily unavailable" >&2
+  exit 1
+fi
+for arg; do out="$arg"; done
+echo 2 > "$out""#,
+            marker = marker.display()
+        );
+
+        let engine =
+            SpreadsheetEngine::with_path(fake_binary(temp_dir.path(), "ssconvert", &script));
+        let xlsx = temp_dir.path().join("model.xlsx");
+        let csv = engine.xlsx_to_csv(&xlsx, temp_dir.path()).unwrap();
+        assert!(marker.exists());
+        assert_eq!(fs::read_to_string(csv).unwrap(), "2\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verify_recalc_accepts_recalculated_value() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let engine = fake_ssconvert(temp_dir.path(), "2").with_recalc(false);
+        assert_eq!(engine.verify_recalc(), Ok(()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verify_recalc_rejects_cached_value() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let engine = fake_ssconvert(temp_dir.path(), "0");
+        let err = engine.verify_recalc().unwrap_err();
+        assert!(err.contains("does not recalculate formulas"), "{err}");
+        assert!(err.contains("\"0\""), "{err}");
+    }
 }
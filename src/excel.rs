@@ -3,16 +3,23 @@
 //! Provides:
 //! - Create test Excel files with data and formulas
 //! - Read Excel files to verify exports
-//! - Compare Excel contents for round-trip validation
+//! - Compare Excel contents for round-trip validation (`compare_xlsx`)
+//! - Validate the structure of `forge-demo import` output (`validate_import`)
+//! - Diff `forge-demo import` output against a golden YAML (`golden_differences`)
+//! - Resolve `file.xlsx!Sheet!A1` references to expected values
+//! - Read formulas back from exported XLSX files (`read_formula_at`)
 
-// Allow unused code - these helpers are only used in tests
+// Allow unused code - most of these helpers are only used in tests
 #![allow(dead_code)]
 
+use std::fmt::{self, Write as _};
 use std::path::Path;
 
 use calamine::{open_workbook, Data, Reader, Xlsx};
 use rust_xlsxwriter::{Formula, Workbook, XlsxError};
 
+use crate::types::{InputFormat, Scalar, Section, TableColumn, TestSpec};
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Test Excel Creation
 // ─────────────────────────────────────────────────────────────────────────────
@@ -119,6 +126,18 @@ pub fn create_multi_sheet_xlsx(path: &Path) -> Result<(), XlsxError> {
     Ok(())
 }
 
+/// Creates a single-cell workbook holding `formula` with a cached `result`.
+///
+/// A wrong cached result shows whether a reader recalculates or trusts
+/// the cache.
+pub fn create_formula_xlsx(path: &Path, formula: &str, result: &str) -> Result<(), XlsxError> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.write_formula(0, 0, Formula::new(formula).set_result(result))?;
+    workbook.save(path)?;
+    Ok(())
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Excel Reading
 // ─────────────────────────────────────────────────────────────────────────────
@@ -205,6 +224,582 @@ pub fn get_sheet_names(path: &Path) -> Result<Vec<String>, String> {
     Ok(workbook.sheet_names())
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Golden Comparison
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Maximum number of mismatches [`compare_xlsx`] reports.
+pub const MAX_REPORTED_MISMATCHES: usize = 10;
+
+/// A difference between a workbook and its golden copy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XlsxMismatch {
+    /// Sheet present in the golden workbook only.
+    MissingSheet(String),
+    /// Sheet present in the actual workbook only.
+    ExtraSheet(String),
+    /// Cell whose value differs.
+    Cell {
+        /// Sheet name.
+        sheet: String,
+        /// A1-style address, relative to the sheet's used range.
+        cell: String,
+        /// Value in the actual workbook.
+        actual: CellValue,
+        /// Value in the golden workbook.
+        expected: CellValue,
+    },
+}
+
+impl fmt::Display for XlsxMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSheet(sheet) => write!(f, "missing sheet {sheet}"),
+            Self::ExtraSheet(sheet) => write!(f, "unexpected sheet {sheet}"),
+            Self::Cell {
+                sheet,
+                cell,
+                actual,
+                expected,
+            } => write!(f, "{sheet}!{cell}: expected {expected:?}, got {actual:?}"),
+        }
+    }
+}
+
+/// Compares `actual` against `golden` sheet by sheet, cell by cell.
+///
+/// Numbers match within `tolerance`; other values must be equal, and
+/// cells past the end of a shorter row count as empty. Returns up to
+/// [`MAX_REPORTED_MISMATCHES`] differences (empty when the workbooks match).
+pub fn compare_xlsx(
+    actual: &Path,
+    golden: &Path,
+    tolerance: f64,
+) -> Result<Vec<XlsxMismatch>, String> {
+    Ok(compare_sheets(
+        &read_xlsx(actual)?,
+        &read_xlsx(golden)?,
+        tolerance,
+        MAX_REPORTED_MISMATCHES,
+    ))
+}
+
+/// Diffs two workbooks' sheet data, stopping after `limit` mismatches.
+fn compare_sheets(
+    actual: &SheetData,
+    golden: &SheetData,
+    tolerance: f64,
+    limit: usize,
+) -> Vec<XlsxMismatch> {
+    let mut mismatches = Vec::new();
+    let find = |sheets: &SheetData, name: &str| {
+        sheets
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, rows)| rows.clone())
+    };
+
+    for (name, golden_rows) in golden {
+        let Some(actual_rows) = find(actual, name) else {
+            mismatches.push(XlsxMismatch::MissingSheet(name.clone()));
+            continue;
+        };
+        let height = actual_rows.len().max(golden_rows.len());
+        for row in 0..height {
+            let actual_row = actual_rows.get(row).map_or(&[][..], Vec::as_slice);
+            let golden_row = golden_rows.get(row).map_or(&[][..], Vec::as_slice);
+            for col in 0..actual_row.len().max(golden_row.len()) {
+                let a = actual_row.get(col).unwrap_or(&CellValue::Empty);
+                let g = golden_row.get(col).unwrap_or(&CellValue::Empty);
+                if !cells_match(a, g, tolerance) {
+                    mismatches.push(XlsxMismatch::Cell {
+                        sheet: name.clone(),
+                        cell: format_a1(row, col),
+                        actual: a.clone(),
+                        expected: g.clone(),
+                    });
+                }
+            }
+        }
+    }
+    for (name, _) in actual {
+        if find(golden, name).is_none() {
+            mismatches.push(XlsxMismatch::ExtraSheet(name.clone()));
+        }
+    }
+
+    mismatches.truncate(limit);
+    mismatches
+}
+
+/// Returns `true` if two cells are equal, comparing numbers within `tolerance`.
+fn cells_match(actual: &CellValue, expected: &CellValue, tolerance: f64) -> bool {
+    match (actual, expected) {
+        (CellValue::Number(a), CellValue::Number(e)) => (a - e).abs() <= tolerance,
+        _ => actual == expected,
+    }
+}
+
+/// Formats zero-based `(row, col)` as an A1-style address.
+fn format_a1(row: usize, col: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = col + 1;
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        letters.push(b'A' + u8::try_from(rem).unwrap_or(0));
+        n = (n - 1) / 26;
+    }
+    letters.reverse();
+    format!("{}{}", String::from_utf8_lossy(&letters), row + 1)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Import Validation
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Relative tolerance for imported numbers (they pass through text).
+const IMPORT_TOLERANCE: f64 = 1e-9;
+
+/// What one entry of an imported spec should hold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportedEntry {
+    /// Scalar holding this literal value (and no formula).
+    Value(f64),
+    /// Scalar defined by a formula.
+    Formula,
+    /// Table column of these numbers.
+    Numbers(Vec<f64>),
+    /// Table column of these strings.
+    Strings(Vec<String>),
+}
+
+impl fmt::Display for ImportedEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value(v) => write!(f, "value {v}"),
+            Self::Formula => write!(f, "a formula"),
+            Self::Numbers(values) => write!(f, "numbers {values:?}"),
+            Self::Strings(values) => write!(f, "strings {values:?}"),
+        }
+    }
+}
+
+/// A difference between imported YAML and its expected structure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportMismatch {
+    /// Expected section absent from the import.
+    MissingSection(String),
+    /// Expected key absent from its section.
+    MissingKey {
+        /// Section name.
+        section: String,
+        /// Scalar or column name.
+        key: String,
+    },
+    /// Entry present but of the wrong kind or value.
+    Entry {
+        /// Section name.
+        section: String,
+        /// Scalar or column name.
+        key: String,
+        /// What the entry should hold.
+        expected: ImportedEntry,
+        /// Description of what it holds.
+        actual: String,
+    },
+}
+
+impl fmt::Display for ImportMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSection(section) => write!(f, "missing section {section}"),
+            Self::MissingKey { section, key } => write!(f, "missing {section}.{key}"),
+            Self::Entry {
+                section,
+                key,
+                expected,
+                actual,
+            } => write!(f, "{section}.{key}: expected {expected}, got {actual}"),
+        }
+    }
+}
+
+/// Parses `forge-demo import` output and checks it against `expected`.
+///
+/// Each expectation is `(section, key, entry)`. Sections are matched
+/// ignoring ASCII case, since they come from sheet names; keys must match
+/// exactly. Scalars written at the top level belong to the spec's
+/// `_default_section`. Entries not listed are not checked. Returns every
+/// mismatch (empty when the import matches), or `Err` if the YAML isn't a
+/// spec.
+pub fn validate_import(
+    yaml: &str,
+    expected: &[(&str, &str, ImportedEntry)],
+) -> Result<Vec<ImportMismatch>, String> {
+    let spec = TestSpec::parse(yaml, InputFormat::Yaml)
+        .map_err(|e| format!("Imported YAML is not a valid spec: {e}"))?;
+
+    let mut mismatches = Vec::new();
+    for (section_name, key, entry) in expected {
+        let section = spec
+            .sections
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(section_name))
+            .map(|(_, section)| section);
+        let is_default = spec
+            .default_section
+            .as_deref()
+            .is_some_and(|default| default.eq_ignore_ascii_case(section_name));
+        if section.is_none() && !is_default {
+            let missing = ImportMismatch::MissingSection((*section_name).to_string());
+            if !mismatches.contains(&missing) {
+                mismatches.push(missing);
+            }
+            continue;
+        }
+        let actual = match section {
+            Some(Section::ScalarGroup(scalars)) => {
+                scalars.get(*key).map(|scalar| check_scalar(scalar, entry))
+            }
+            Some(Section::Table(columns)) => {
+                columns.get(*key).map(|column| check_column(column, entry))
+            }
+            Some(Section::Test(_)) | None => None,
+        }
+        .or_else(|| {
+            let Some(Section::Test(scalar)) = spec.sections.get(*key) else {
+                return None;
+            };
+            is_default.then(|| check_scalar(scalar, entry))
+        });
+        match actual {
+            None => mismatches.push(ImportMismatch::MissingKey {
+                section: (*section_name).to_string(),
+                key: (*key).to_string(),
+            }),
+            Some(Ok(())) => {}
+            Some(Err(actual)) => mismatches.push(ImportMismatch::Entry {
+                section: (*section_name).to_string(),
+                key: (*key).to_string(),
+                expected: entry.clone(),
+                actual,
+            }),
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Checks an imported scalar, describing it on mismatch.
+fn check_scalar(scalar: &Scalar, expected: &ImportedEntry) -> Result<(), String> {
+    let matches = match expected {
+        ImportedEntry::Value(v) => {
+            scalar.formula.is_none() && scalar.value.is_some_and(|a| numbers_match(a, *v))
+        }
+        ImportedEntry::Formula => scalar.formula.is_some(),
+        ImportedEntry::Numbers(_) | ImportedEntry::Strings(_) => false,
+    };
+    if matches {
+        return Ok(());
+    }
+    Err(match (&scalar.formula, scalar.value) {
+        (Some(formula), _) => format!("formula {formula}"),
+        (None, Some(value)) => format!("value {value}"),
+        (None, None) => "an empty scalar".to_string(),
+    })
+}
+
+/// Checks an imported table column, describing it on mismatch.
+fn check_column(column: &TableColumn, expected: &ImportedEntry) -> Result<(), String> {
+    let matches = match (column, expected) {
+        (TableColumn::Numbers(actual), ImportedEntry::Numbers(expected)) => {
+            actual.len() == expected.len()
+                && actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(a, e)| numbers_match(*a, *e))
+        }
+        (TableColumn::Strings(actual), ImportedEntry::Strings(expected)) => actual == expected,
+        (TableColumn::Formula(_), ImportedEntry::Formula) => true,
+        _ => false,
+    };
+    if matches {
+        return Ok(());
+    }
+    Err(match column {
+        TableColumn::Numbers(values) => format!("column of numbers {values:?}"),
+        TableColumn::Strings(values) => format!("column of strings {values:?}"),
+        TableColumn::Formula(formula) => format!("column formula {formula}"),
+    })
+}
+
+/// Returns `true` if `a` equals `b` within [`IMPORT_TOLERANCE`] (relative).
+fn numbers_match(a: f64, b: f64) -> bool {
+    (a - b).abs() <= IMPORT_TOLERANCE * b.abs().max(1.0)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Golden Comparison
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Parses YAML into a canonical form: mapping keys sorted as text and every
+/// number an `f64`, so key order and `1` vs `1.0` don't show up as
+/// differences.
+pub fn normalize_yaml(yaml: &str) -> Result<serde_json::Value, String> {
+    let value: serde_yaml_ng::Value =
+        serde_yaml_ng::from_str(yaml).map_err(|e| format!("Invalid YAML: {e}"))?;
+    Ok(canonical(&value))
+}
+
+/// Converts one YAML value to its canonical form; see [`normalize_yaml`].
+fn canonical(value: &serde_yaml_ng::Value) -> serde_json::Value {
+    use serde_json::Value as Json;
+    use serde_yaml_ng::Value as Yaml;
+
+    match value {
+        Yaml::Null => Json::Null,
+        Yaml::Bool(b) => Json::Bool(*b),
+        // NaN and infinities have no JSON number; keep their YAML spelling
+        Yaml::Number(n) => n
+            .as_f64()
+            .and_then(serde_json::Number::from_f64)
+            .map_or_else(|| Json::String(n.to_string()), Json::Number),
+        Yaml::String(s) => Json::String(s.clone()),
+        Yaml::Sequence(items) => Json::Array(items.iter().map(canonical).collect()),
+        Yaml::Mapping(map) => {
+            let mut entries: Vec<(String, Json)> = map
+                .iter()
+                .map(|(key, value)| (key_text(key), canonical(value)))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Json::Object(entries.into_iter().collect())
+        }
+        Yaml::Tagged(tagged) => canonical(&tagged.value),
+    }
+}
+
+/// Returns a mapping key as text (non-string keys as their YAML).
+fn key_text(key: &serde_yaml_ng::Value) -> String {
+    match key {
+        serde_yaml_ng::Value::String(s) => s.clone(),
+        other => serde_yaml_ng::to_string(other)
+            .map_or_else(|_| format!("{other:?}"), |s| s.trim_end().to_string()),
+    }
+}
+
+/// Compares `forge-demo import` output with a golden YAML.
+///
+/// Both are normalized first (see [`normalize_yaml`]) and numbers match
+/// within [`IMPORT_TOLERANCE`]. Returns one line per difference, as
+/// `path: description` in key order (empty when they match).
+pub fn golden_differences(actual: &str, golden: &str) -> Result<Vec<String>, String> {
+    let actual = normalize_yaml(actual).map_err(|e| format!("Import output: {e}"))?;
+    let golden = normalize_yaml(golden).map_err(|e| format!("Golden: {e}"))?;
+    let mut differences = Vec::new();
+    diff_values("", &golden, &actual, &mut differences);
+    Ok(differences)
+}
+
+/// Appends the differences between `golden` and `actual` at `path`.
+fn diff_values(
+    path: &str,
+    golden: &serde_json::Value,
+    actual: &serde_json::Value,
+    out: &mut Vec<String>,
+) {
+    use serde_json::Value as Json;
+
+    let at = if path.is_empty() { "(root)" } else { path };
+    match (golden, actual) {
+        (Json::Object(golden), Json::Object(actual)) => {
+            let mut keys: Vec<&String> = golden.keys().chain(actual.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match (golden.get(key), actual.get(key)) {
+                    (Some(g), Some(a)) => diff_values(&child, g, a, out),
+                    (Some(g), None) => out.push(format!("{child}: missing (golden has {g})")),
+                    (None, Some(a)) => out.push(format!("{child}: unexpected {a}")),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Json::Array(golden), Json::Array(actual)) => {
+            if golden.len() != actual.len() {
+                out.push(format!(
+                    "{at}: expected {} items, got {}",
+                    golden.len(),
+                    actual.len()
+                ));
+            }
+            for (i, (g, a)) in golden.iter().zip(actual).enumerate() {
+                let mut child = at.to_string();
+                let _ = write!(child, "[{i}]");
+                diff_values(&child, g, a, out);
+            }
+        }
+        (Json::Number(g), Json::Number(a))
+            if g.as_f64()
+                .zip(a.as_f64())
+                .is_some_and(|(g, a)| numbers_match(a, g)) => {}
+        (g, a) if g == a => {}
+        (g, a) => out.push(format!("{at}: expected {g}, got {a}")),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Cell References
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Parses an A1-style cell address into zero-based `(row, col)`.
+///
+/// Accepts optional `$` anchors (`$B$2`). Returns `None` for malformed input.
+pub fn parse_a1(cell: &str) -> Option<(u32, u32)> {
+    let cell = cell.trim().replace('$', "");
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut col: u32 = 0;
+    for c in letters.chars() {
+        let digit = u32::from(c.to_ascii_uppercase()) - u32::from('A') + 1;
+        col = col.checked_mul(26)?.checked_add(digit)?;
+    }
+    let row: u32 = digits.parse().ok()?;
+    if row == 0 {
+        return None;
+    }
+    Some((row - 1, col - 1))
+}
+
+/// Reads a single cell from a worksheet.
+pub fn read_cell(path: &Path, sheet: &str, row: u32, col: u32) -> Result<CellValue, String> {
+    let mut workbook: Xlsx<_> =
+        open_workbook(path).map_err(|e| format!("Failed to open Excel file: {e}"))?;
+    let range = workbook
+        .worksheet_range(sheet)
+        .map_err(|e| format!("Failed to read sheet {sheet}: {e}"))?;
+    Ok(range
+        .get_value((row, col))
+        .map_or(CellValue::Empty, CellValue::from))
+}
+
+/// Resolves a `file.xlsx!Sheet!A1` reference to a numeric value.
+///
+/// The file path is relative to `base_dir` (usually the spec's directory).
+pub fn read_cell_ref(base_dir: &Path, reference: &str) -> Result<f64, String> {
+    let mut parts = reference.rsplitn(3, '!');
+    let (Some(cell), Some(sheet), Some(file)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!(
+            "Invalid cell reference '{reference}': expected file.xlsx!Sheet!A1"
+        ));
+    };
+    let (row, col) =
+        parse_a1(cell).ok_or_else(|| format!("Invalid cell address '{cell}' in '{reference}'"))?;
+
+    match read_cell(&base_dir.join(file), sheet, row, col)? {
+        CellValue::Number(n) => Ok(n),
+        other => Err(format!("Cell {reference} is not numeric: {other:?}")),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Formula Reading
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Reads the formula in `cell` (A1-style) of `sheet`, as written in the
+/// file (without a leading `=`). Returns `None` if the cell holds a plain
+/// value or nothing.
+pub fn read_formula_at(path: &Path, sheet: &str, cell: &str) -> Result<Option<String>, String> {
+    let (row, col) = parse_a1(cell).ok_or_else(|| format!("Invalid cell address '{cell}'"))?;
+    let mut workbook: Xlsx<_> =
+        open_workbook(path).map_err(|e| format!("Failed to open Excel file: {e}"))?;
+    let formulas = workbook
+        .worksheet_formula(sheet)
+        .map_err(|e| format!("Failed to read formulas of sheet {sheet}: {e}"))?;
+    Ok(formulas
+        .get_value((row, col))
+        .filter(|formula| !formula.is_empty())
+        .cloned())
+}
+
+/// Returns the `(sheet, cell)` just right of the first text cell `is_label`
+/// accepts, searching sheets in order and row-major within a sheet.
+pub fn find_labeled_cell(
+    path: &Path,
+    is_label: impl Fn(&str) -> bool,
+) -> Result<Option<(String, String)>, String> {
+    let mut workbook: Xlsx<_> =
+        open_workbook(path).map_err(|e| format!("Failed to open Excel file: {e}"))?;
+    for sheet in workbook.sheet_names() {
+        let range = workbook
+            .worksheet_range(&sheet)
+            .map_err(|e| format!("Failed to read sheet {sheet}: {e}"))?;
+        let (top, left) = range.start().unwrap_or_default();
+        let labeled = range.used_cells().find_map(|(row, col, data)| match data {
+            Data::String(text) if is_label(text.trim()) => {
+                Some(format_a1(top as usize + row, left as usize + col + 1))
+            }
+            _ => None,
+        });
+        if let Some(cell) = labeled {
+            return Ok(Some((sheet, cell)));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads every formula in the workbook as `(sheet, cell, formula)`, in
+/// sheet order and row-major within a sheet.
+pub fn read_formulas(path: &Path) -> Result<Vec<(String, String, String)>, String> {
+    let mut workbook: Xlsx<_> =
+        open_workbook(path).map_err(|e| format!("Failed to open Excel file: {e}"))?;
+    let mut found = Vec::new();
+    for sheet in workbook.sheet_names() {
+        let formulas = workbook
+            .worksheet_formula(&sheet)
+            .map_err(|e| format!("Failed to read formulas of sheet {sheet}: {e}"))?;
+        let (top, left) = formulas.start().unwrap_or_default();
+        for (row, col, formula) in formulas.used_cells() {
+            if !formula.is_empty() {
+                let cell = format_a1(top as usize + row, left as usize + col);
+                found.push((sheet.clone(), cell, formula.clone()));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Returns `formula` in a canonical form, so that a formula read back from
+/// an XLSX compares equal to the one it was written from.
+///
+/// Drops the leading `=`, the `_xlfn.` prefix Excel stores newer functions
+/// under, and whitespace outside string literals, and uppercases everything
+/// outside string literals.
+pub fn normalize_formula(formula: &str) -> String {
+    let formula = formula.trim();
+    let formula = formula.strip_prefix('=').unwrap_or(formula);
+    let mut normalized = String::with_capacity(formula.len());
+    let mut in_string = false;
+    for c in formula.chars() {
+        if c == '"' {
+            in_string = !in_string;
+        }
+        if in_string || c == '"' {
+            normalized.push(c);
+        } else if !c.is_whitespace() {
+            normalized.push(c.to_ascii_uppercase());
+        }
+    }
+    normalized.replace("_XLFN.", "")
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────────────────
@@ -362,4 +957,313 @@ mod tests {
         let result = get_sheet_names(Path::new("/nonexistent/file.xlsx"));
         assert!(result.is_err());
     }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Cell Reference Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn parse_a1_handles_columns_and_anchors() {
+        assert_eq!(parse_a1("A1"), Some((0, 0)));
+        assert_eq!(parse_a1("B2"), Some((1, 1)));
+        assert_eq!(parse_a1("z10"), Some((9, 25)));
+        assert_eq!(parse_a1("AA3"), Some((2, 26)));
+        assert_eq!(parse_a1("$C$4"), Some((3, 2)));
+    }
+
+    #[test]
+    fn parse_a1_rejects_malformed() {
+        assert_eq!(parse_a1(""), None);
+        assert_eq!(parse_a1("12"), None);
+        assert_eq!(parse_a1("B"), None);
+        assert_eq!(parse_a1("B0"), None);
+        assert_eq!(parse_a1("B2C"), None);
+    }
+
+    #[test]
+    fn read_cell_ref_reads_numeric_cell() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_test_scalars_xlsx(&temp_dir.path().join("model.xlsx")).unwrap();
+
+        let value = read_cell_ref(temp_dir.path(), "model.xlsx!Scalars!B2").unwrap();
+        assert!((value - 100_000.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn read_cell_ref_rejects_text_and_bad_refs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_test_scalars_xlsx(&temp_dir.path().join("model.xlsx")).unwrap();
+
+        assert!(read_cell_ref(temp_dir.path(), "model.xlsx!Scalars!A2").is_err());
+        assert!(read_cell_ref(temp_dir.path(), "model.xlsx!B2").is_err());
+        assert!(read_cell_ref(temp_dir.path(), "model.xlsx!Missing!B2").is_err());
+    }
+
+    #[test]
+    fn read_formula_at_returns_formula_text() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("model.xlsx");
+        create_test_scalars_xlsx(&path).unwrap();
+
+        assert_eq!(
+            read_formula_at(&path, "Scalars", "B4").unwrap().as_deref(),
+            Some("B2-B3")
+        );
+        assert_eq!(read_formula_at(&path, "Scalars", "B2").unwrap(), None);
+        assert_eq!(read_formula_at(&path, "Scalars", "Z99").unwrap(), None);
+        assert!(read_formula_at(&path, "Scalars", "4B").is_err());
+        assert!(read_formula_at(&path, "Missing", "B4").is_err());
+
+        let formulas: Vec<_> = read_formulas(&path)
+            .unwrap()
+            .into_iter()
+            .map(|(sheet, cell, formula)| format!("{sheet}!{cell}={formula}"))
+            .collect();
+        assert_eq!(formulas, ["Scalars!B4=B2-B3", "Scalars!B5=B4/B2"]);
+    }
+
+    #[test]
+    fn find_labeled_cell_returns_the_cell_after_the_label() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("model.xlsx");
+        create_test_scalars_xlsx(&path).unwrap();
+
+        assert_eq!(
+            find_labeled_cell(&path, |cell| cell == "margin").unwrap(),
+            Some(("Scalars".to_string(), "B5".to_string()))
+        );
+        assert_eq!(
+            find_labeled_cell(&path, |cell| cell == "tax").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn normalize_formula_ignores_case_spacing_and_prefixes() {
+        assert_eq!(normalize_formula("= sum( a1, 2 )"), "SUM(A1,2)");
+        assert_eq!(normalize_formula("_xlfn.STDEV.S(1,2)"), "STDEV.S(1,2)");
+        assert_eq!(normalize_formula(r#"=LEN("a b")"#), r#"LEN("a b")"#);
+        assert_ne!(
+            normalize_formula(r#"=LEN("a b")"#),
+            normalize_formula(r#"=LEN("A B")"#)
+        );
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Golden Comparison Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn format_a1_inverts_parse_a1() {
+        for (row, col) in [(0u32, 0u32), (1, 25), (9, 26), (0, 701), (4, 702)] {
+            let a1 = format_a1(row as usize, col as usize);
+            assert_eq!(parse_a1(&a1), Some((row, col)), "{a1}");
+        }
+        assert_eq!(format_a1(1, 27), "AB2");
+    }
+
+    #[test]
+    fn compare_xlsx_matches_identical_workbooks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let a = temp_dir.path().join("a.xlsx");
+        let b = temp_dir.path().join("b.xlsx");
+        create_multi_sheet_xlsx(&a).unwrap();
+        create_multi_sheet_xlsx(&b).unwrap();
+        assert_eq!(compare_xlsx(&a, &b, 0.0).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn compare_sheets_reports_cells_and_sheets() {
+        let golden: SheetData = vec![
+            (
+                "Scalars".to_string(),
+                vec![vec![
+                    CellValue::Text("rate".to_string()),
+                    CellValue::Number(0.25),
+                ]],
+            ),
+            ("Revenue".to_string(), vec![]),
+        ];
+        let actual: SheetData = vec![
+            (
+                "Scalars".to_string(),
+                vec![
+                    vec![
+                        CellValue::Text("rate".to_string()),
+                        CellValue::Number(0.250_000_1),
+                    ],
+                    vec![CellValue::Number(1.0)],
+                ],
+            ),
+            ("Extra".to_string(), vec![]),
+        ];
+
+        assert_eq!(
+            compare_sheets(&actual, &golden, 1e-3, 10),
+            vec![
+                XlsxMismatch::Cell {
+                    sheet: "Scalars".to_string(),
+                    cell: "A2".to_string(),
+                    actual: CellValue::Number(1.0),
+                    expected: CellValue::Empty,
+                },
+                XlsxMismatch::MissingSheet("Revenue".to_string()),
+                XlsxMismatch::ExtraSheet("Extra".to_string()),
+            ]
+        );
+
+        let strict = compare_sheets(&actual, &golden, 0.0, 1);
+        assert_eq!(strict.len(), 1);
+        assert_eq!(
+            strict[0].to_string(),
+            "Scalars!B1: expected Number(0.25), got Number(0.2500001)"
+        );
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Import Validation Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    const IMPORTED_YAML: &str = r#"_forge_version: "1.0.0"
+Scalars:
+  revenue:
+    value: 100000
+  profit:
+    value: null
+    formula: "=revenue - costs"
+quarterly:
+  quarter: [Q1, Q2]
+  revenue: [100000, 120000]
+"#;
+
+    #[test]
+    fn validate_import_accepts_matching_structure() {
+        let expected = [
+            ("scalars", "revenue", ImportedEntry::Value(100_000.0)),
+            ("scalars", "profit", ImportedEntry::Formula),
+            (
+                "quarterly",
+                "quarter",
+                ImportedEntry::Strings(vec!["Q1".to_string(), "Q2".to_string()]),
+            ),
+            (
+                "quarterly",
+                "revenue",
+                ImportedEntry::Numbers(vec![100_000.0, 120_000.0]),
+            ),
+        ];
+        assert_eq!(validate_import(IMPORTED_YAML, &expected).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn validate_import_reports_each_mismatch() {
+        let expected = [
+            ("scalars", "revenue", ImportedEntry::Formula),
+            ("scalars", "profit", ImportedEntry::Value(60_000.0)),
+            ("scalars", "costs", ImportedEntry::Value(40_000.0)),
+            ("quarterly", "revenue", ImportedEntry::Numbers(vec![1.0])),
+            ("revenue", "amount", ImportedEntry::Formula),
+            ("revenue", "month", ImportedEntry::Formula),
+        ];
+        let mismatches: Vec<String> = validate_import(IMPORTED_YAML, &expected)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            mismatches,
+            [
+                "scalars.revenue: expected a formula, got value 100000",
+                "scalars.profit: expected value 60000, got formula =revenue - costs",
+                "missing scalars.costs",
+                "quarterly.revenue: expected numbers [1.0], got column of numbers [100000.0, 120000.0]",
+                "missing section revenue",
+            ]
+        );
+
+        assert!(validate_import("not: [a, spec", &expected).is_err());
+    }
+
+    #[test]
+    fn validate_import_reads_top_level_scalars_as_the_default_section() {
+        let yaml = r#"_forge_version: "1.0.0"
+_default_section: Scalars
+revenue:
+  value: 100000
+profit:
+  formula: "=revenue - costs"
+"#;
+        let expected = [
+            ("scalars", "revenue", ImportedEntry::Value(100_000.0)),
+            ("scalars", "profit", ImportedEntry::Formula),
+        ];
+        assert_eq!(validate_import(yaml, &expected).unwrap(), vec![]);
+
+        let expected = [
+            ("scalars", "costs", ImportedEntry::Value(40_000.0)),
+            ("other", "revenue", ImportedEntry::Value(100_000.0)),
+        ];
+        let mismatches: Vec<String> = validate_import(yaml, &expected)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            mismatches,
+            ["missing scalars.costs", "missing section other"]
+        );
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Golden Comparison Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn golden_comparison_ignores_key_order_and_number_spelling() {
+        let golden = "scalars:\n  revenue:\n    value: 100000\n  costs:\n    value: 4.0e4\n";
+        let actual = "scalars:\n  costs:\n    value: 40000.0\n  revenue:\n    value: 100000.0\n";
+        assert_eq!(
+            golden_differences(actual, golden).unwrap(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            normalize_yaml(actual).unwrap(),
+            normalize_yaml(golden).unwrap()
+        );
+    }
+
+    #[test]
+    fn golden_comparison_reports_each_difference_by_path() {
+        let golden = "\
+scalars:
+  revenue:
+    value: 100000
+  profit:
+    formula: =revenue-costs
+data:
+  month: [Jan, Feb]
+";
+        let actual = "\
+scalars:
+  revenue:
+    value: 100001
+  extra:
+    value: 1
+data:
+  month: [Jan, Mar, Apr]
+";
+        assert_eq!(
+            golden_differences(actual, golden).unwrap(),
+            [
+                "data.month: expected 2 items, got 3",
+                r#"data.month[1]: expected "Feb", got "Mar""#,
+                "scalars.extra: unexpected {\"value\":1.0}",
+                r#"scalars.profit: missing (golden has {"formula":"=revenue-costs"})"#,
+                "scalars.revenue.value: expected 100000.0, got 100001.0",
+            ]
+        );
+        assert!(golden_differences("a: [", golden)
+            .unwrap_err()
+            .starts_with("Import output"));
+    }
 }
@@ -0,0 +1,281 @@
+//! Run history in a `SQLite` database (`--sqlite`).
+//!
+//! Each `--all` run appends one row to `runs` and one row per result to
+//! `results`, so flaky or regressing tests can be found with SQL across
+//! any number of runs:
+//!
+//! ```sql
+//! SELECT test, COUNT(DISTINCT status) FROM results GROUP BY test, mode
+//! HAVING COUNT(DISTINCT status) > 1;
+//! ```
+//!
+//! Rows are inserted with prepared statements, one transaction per run.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::report::{ModeRun, ReportMetadata};
+use crate::types::TestResult;
+
+/// Tables created in a new database; existing ones are left as they are.
+const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    forge_e2e_version TEXT NOT NULL,
+    forge_e2e_commit TEXT,
+    forge_demo_version TEXT
+);
+CREATE TABLE IF NOT EXISTS results (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    mode TEXT NOT NULL,
+    test TEXT NOT NULL,
+    test_id TEXT,
+    status TEXT NOT NULL,
+    expected REAL,
+    actual REAL,
+    error TEXT,
+    duration_secs REAL
+);
+CREATE INDEX IF NOT EXISTS results_by_test ON results(test, mode);
+";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Writing
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Appends `runs` to the database at `path` as one run, creating the
+/// database and its schema if needed.
+pub fn append_run(path: &Path, runs: &[ModeRun], metadata: &ReportMetadata) -> Result<(), String> {
+    let timestamp = chrono::Local::now().to_rfc3339();
+    let mut conn =
+        Connection::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    append(&mut conn, runs, metadata, &timestamp)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Creates the schema in `conn` if needed and inserts `runs` as one run,
+/// in a single transaction. Returns the new run's id.
+fn append(
+    conn: &mut Connection,
+    runs: &[ModeRun],
+    metadata: &ReportMetadata,
+    timestamp: &str,
+) -> rusqlite::Result<i64> {
+    conn.execute_batch(SCHEMA)?;
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO runs (timestamp, forge_e2e_version, forge_e2e_commit, forge_demo_version) \
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            timestamp,
+            env!("CARGO_PKG_VERSION"),
+            metadata.commit,
+            metadata.forge_version,
+        ],
+    )?;
+    let run_id = tx.last_insert_rowid();
+    {
+        let mut insert =
+            tx.prepare("INSERT INTO results VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?;
+        for run in runs {
+            for result in &run.results {
+                let (expected, actual, error) = match result {
+                    TestResult::Pass {
+                        expected, actual, ..
+                    } => (Some(*expected), Some(*actual), None),
+                    TestResult::Fail {
+                        expected,
+                        actual,
+                        error,
+                        ..
+                    } => (Some(*expected), *actual, error.as_deref()),
+                    TestResult::Skip { reason, .. } => (None, None, Some(reason.as_str())),
+                };
+                insert.execute(params![
+                    run_id,
+                    run.mode,
+                    result.name(),
+                    result.id(),
+                    status(result),
+                    real(expected),
+                    real(actual),
+                    error,
+                    result.duration().map(|d| d.as_secs_f64()),
+                ])?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(run_id)
+}
+
+/// Returns the `status` column value for `result`, as in JSON reports.
+const fn status(result: &TestResult) -> &'static str {
+    match result {
+        TestResult::Pass { .. } => "pass",
+        TestResult::Fail { .. } => "fail",
+        TestResult::Skip { .. } => "skip",
+    }
+}
+
+/// Returns `value` as stored in a REAL column: `SQLite` can't store NaN,
+/// so it becomes `NULL`; infinities are kept.
+fn real(value: Option<f64>) -> Option<f64> {
+    value.filter(|v| !v.is_nan())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// A `results` row, without its mode.
+    type Row = (
+        i64,
+        String,
+        Option<String>,
+        String,
+        Option<f64>,
+        Option<f64>,
+        Option<String>,
+        Option<f64>,
+    );
+
+    fn run() -> ModeRun {
+        ModeRun {
+            mode: "Normal".to_string(),
+            results: vec![
+                TestResult::Pass {
+                    name: "math.test_abs".to_string(),
+                    id: Some("abs-1".to_string()),
+                    formula: "=ABS(-1)".to_string(),
+                    expected: 1.0,
+                    actual: 1.0,
+                    heuristic: false,
+                    forge: None,
+                    duration: Some(Duration::from_millis(250)),
+                },
+                TestResult::Fail {
+                    name: "math.test_div".to_string(),
+                    id: None,
+                    formula: "=1/0".to_string(),
+                    expected: f64::INFINITY,
+                    actual: None,
+                    error: Some("Result 'x' not found".to_string()),
+                    duration: None,
+                },
+                TestResult::Skip {
+                    name: "dates.test_now".to_string(),
+                    id: None,
+                    reason: "volatile".to_string(),
+                },
+            ],
+            elapsed: Duration::from_secs(1),
+            threads: None,
+        }
+    }
+
+    #[test]
+    fn runs_accumulate_in_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let metadata = ReportMetadata {
+            commit: Some("abc123".to_string()),
+            forge_version: None,
+        };
+        let first = append(&mut conn, &[run()], &metadata, "2024-06-15T12:00:00Z").unwrap();
+        let second = append(&mut conn, &[run()], &metadata, "2024-06-16T12:00:00Z").unwrap();
+        assert_eq!((first, second), (1, 2));
+
+        let (timestamp, commit, forge_version): (String, String, Option<String>) = conn
+            .query_row(
+                "SELECT timestamp, forge_e2e_commit, forge_demo_version FROM runs WHERE id = 2",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(timestamp, "2024-06-16T12:00:00Z");
+        assert_eq!(commit, "abc123");
+        assert_eq!(forge_version, None);
+
+        let mut query = conn
+            .prepare(
+                "SELECT run_id, test, test_id, status, expected, actual, error, duration_secs \
+                 FROM results WHERE mode = 'Normal' ORDER BY run_id, rowid",
+            )
+            .unwrap();
+        let rows: Vec<Row> = query
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get(7)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(
+            rows[..3],
+            [
+                (
+                    1,
+                    "math.test_abs".to_string(),
+                    Some("abs-1".to_string()),
+                    "pass".to_string(),
+                    Some(1.0),
+                    Some(1.0),
+                    None,
+                    Some(0.25)
+                ),
+                (
+                    1,
+                    "math.test_div".to_string(),
+                    None,
+                    "fail".to_string(),
+                    Some(f64::INFINITY),
+                    None,
+                    Some("Result 'x' not found".to_string()),
+                    None
+                ),
+                (
+                    1,
+                    "dates.test_now".to_string(),
+                    None,
+                    "skip".to_string(),
+                    None,
+                    None,
+                    Some("volatile".to_string()),
+                    None
+                ),
+            ]
+        );
+        assert!(rows[3..].iter().all(|row| row.0 == 2));
+    }
+
+    #[test]
+    fn database_file_is_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        append_run(&path, &[run()], &ReportMetadata::default()).unwrap();
+        append_run(&path, &[run()], &ReportMetadata::default()).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM results", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 6);
+    }
+}
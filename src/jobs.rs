@@ -0,0 +1,101 @@
+//! Concurrency limits for the steps of the export pipeline.
+//!
+//! Batch chunks run on rayon's pool, so forge-demo exports and ssconvert
+//! conversions run as wide as the pool by default. ssconvert is much
+//! heavier on memory than forge-demo, so `--export-jobs` and
+//! `--convert-jobs` cap each step separately: a [`JobLimit`] is held for
+//! the duration of one step.
+
+use std::num::NonZeroUsize;
+use std::sync::{Condvar, Mutex, PoisonError};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Job Limit
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A counting semaphore bounding how many callers run a step at once.
+#[derive(Debug, Default)]
+pub struct JobLimit {
+    /// Maximum concurrent jobs (`None` = unlimited).
+    max: Option<NonZeroUsize>,
+    /// Jobs currently running.
+    running: Mutex<usize>,
+    /// Signalled whenever a job finishes.
+    finished: Condvar,
+}
+
+/// A running job; frees its slot when dropped.
+#[derive(Debug)]
+pub struct JobSlot<'a> {
+    /// The limit the slot was taken from (`None` when unlimited).
+    limit: Option<&'a JobLimit>,
+}
+
+impl JobLimit {
+    /// Creates a limit of `max` concurrent jobs (`None` = unlimited).
+    pub fn new(max: Option<NonZeroUsize>) -> Self {
+        Self {
+            max,
+            ..Self::default()
+        }
+    }
+
+    /// Waits for a free slot and takes it.
+    pub fn acquire(&self) -> JobSlot<'_> {
+        let Some(max) = self.max else {
+            return JobSlot { limit: None };
+        };
+        let running = self.running.lock().unwrap_or_else(PoisonError::into_inner);
+        *self
+            .finished
+            .wait_while(running, |running| *running >= max.get())
+            .unwrap_or_else(PoisonError::into_inner) += 1;
+        JobSlot { limit: Some(self) }
+    }
+
+    /// Runs `job` once a slot is free, holding the slot until it returns.
+    pub fn run<T>(&self, job: impl FnOnce() -> T) -> T {
+        let _slot = self.acquire();
+        job()
+    }
+}
+
+impl Drop for JobSlot<'_> {
+    fn drop(&mut self) {
+        if let Some(limit) = self.limit {
+            *limit.running.lock().unwrap_or_else(PoisonError::into_inner) -= 1;
+            limit.finished.notify_one();
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn limit_caps_concurrent_jobs() {
+        let limit = JobLimit::new(NonZeroUsize::new(2));
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        thread::scope(|s| {
+            for _ in 0..6 {
+                s.spawn(|| {
+                    let _slot = limit.acquire();
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}
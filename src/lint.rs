@@ -0,0 +1,252 @@
+//! Checks for tests that could pass with a broken formula (`--lint`).
+//!
+//! A test only proves something if a wrong result would fail it. These
+//! heuristics flag tests whose expected value is already on hand (a number
+//! written in the formula, or the result of a test it depends on, which
+//! heuristic matching may find in the same CSV) and formulas that are just
+//! a constant. Findings are suggestions, not errors.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::Config;
+use crate::types::TestCase;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Findings
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Why a test looks like it would pass whatever its formula computes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// The formula is a single literal, so only the pipeline is tested.
+    ConstantFormula,
+    /// `expected` equals this number written in the formula, so a function
+    /// that returns its argument unchanged would pass.
+    ExpectedIsLiteral(String),
+    /// `expected` equals the expected value of this dependency, whose
+    /// result heuristic matching may find instead of the test's own.
+    ExpectedIsDependency(String),
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConstantFormula => write!(f, "formula is a constant"),
+            Self::ExpectedIsLiteral(literal) => {
+                write!(f, "expected equals the literal {literal} in the formula")
+            }
+            Self::ExpectedIsDependency(name) => {
+                write!(f, "expected equals the result of dependency {name}")
+            }
+        }
+    }
+}
+
+/// A suspicious test found by [`lint_test_cases`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// Fully qualified test name.
+    pub name: String,
+    /// What makes the test suspicious.
+    pub kind: LintKind,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Checks
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Returns the suspicious tests among `cases`, in order.
+///
+/// Values are compared the way the run would compare a result, with the
+/// test's tolerance under `config`. Dependencies are only checked while
+/// heuristic matching is on and the test has no `result_cell`, since
+/// otherwise their results can't be mistaken for the test's.
+pub fn lint_test_cases(cases: &[TestCase], config: &Config) -> Vec<LintFinding> {
+    let by_name: HashMap<&str, &TestCase> = cases
+        .iter()
+        .map(|case| (case.name.as_str(), case))
+        .collect();
+    let mut findings = Vec::new();
+    for case in cases {
+        let tolerance = config.tolerance_for(case);
+        let mut found = |kind| {
+            findings.push(LintFinding {
+                name: case.name.clone(),
+                kind,
+            });
+        };
+        if is_constant(&case.formula) {
+            found(LintKind::ConstantFormula);
+        } else if let Some((literal, _)) = numeric_literals(&case.formula)
+            .into_iter()
+            .find(|(_, value)| case.accepts(*value, tolerance))
+        {
+            found(LintKind::ExpectedIsLiteral(literal));
+        }
+        if config.heuristic_match && case.result_cell.is_none() {
+            let dependency = case.depends_on.iter().find(|name| {
+                by_name
+                    .get(name.as_str())
+                    .is_some_and(|dep| case.accepts(dep.expected, tolerance))
+            });
+            if let Some(name) = dependency {
+                found(LintKind::ExpectedIsDependency(name.clone()));
+            }
+        }
+    }
+    findings
+}
+
+/// Returns `true` if `formula` is nothing but a number or a string, maybe
+/// signed and parenthesized (`=42`, `=(-1.5)`, `="abc"`).
+fn is_constant(formula: &str) -> bool {
+    let mut body = formula.trim().trim_start_matches('=').trim();
+    while let Some(inner) = body.strip_prefix('(').and_then(|b| b.strip_suffix(')')) {
+        body = inner.trim();
+    }
+    let is_string = body.len() >= 2
+        && body.starts_with('"')
+        && body.ends_with('"')
+        && !body[1..body.len() - 1].replace("\"\"", "").contains('"');
+    let is_number = body.chars().any(|c| c.is_ascii_digit()) && body.parse::<f64>().is_ok();
+    is_string || is_number
+}
+
+/// Returns the numbers written in `formula`, as text and value, in order.
+///
+/// A minus sign in front of an operand (`ABS(-42)`) belongs to the number.
+/// Digits inside string literals and names (`LOG10`, `A1`) are not numbers.
+fn numeric_literals(formula: &str) -> Vec<(String, f64)> {
+    let chars: Vec<char> = formula.chars().collect();
+    let mut literals = Vec::new();
+    let mut in_string = false;
+    // The last character outside whitespace, to tell unary minus from
+    // subtraction and digits in names from numbers
+    let mut previous = '=';
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' {
+            in_string = !in_string;
+        } else if !in_string && !previous.is_ascii_alphanumeric() && previous != '_' {
+            let negative = c == '-' && "=(,;+-*/^<>&".contains(previous);
+            let start = if negative { i + 1 } else { i };
+            let end = number_end(&chars, start);
+            if end > start {
+                let text: String = chars[if negative { i } else { start }..end]
+                    .iter()
+                    .collect();
+                if let Ok(value) = text.parse() {
+                    literals.push((text, value));
+                    previous = chars[end - 1];
+                    i = end;
+                    continue;
+                }
+            }
+        }
+        if !c.is_whitespace() {
+            previous = c;
+        }
+        i += 1;
+    }
+    literals
+}
+
+/// Returns the index just past the number starting at `start` (digits, a
+/// decimal point and an exponent), or `start` if there isn't one.
+fn number_end(chars: &[char], start: usize) -> usize {
+    let digits = |from: usize| {
+        (from..chars.len())
+            .find(|&i| !chars[i].is_ascii_digit())
+            .unwrap_or(chars.len())
+    };
+    let mut end = digits(start);
+    if chars.get(end) == Some(&'.') {
+        end = digits(end + 1);
+    }
+    if end == start || chars[start..end] == ['.'] {
+        return start;
+    }
+    if matches!(chars.get(end), Some('e' | 'E')) {
+        let sign = usize::from(matches!(chars.get(end + 1), Some('+' | '-')));
+        let exponent_end = digits(end + 1 + sign);
+        if exponent_end > end + 1 + sign {
+            end = exponent_end;
+        }
+    }
+    end
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, formula: &str, expected: f64, depends_on: &[&str]) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            formula: formula.to_string(),
+            expected,
+            depends_on: depends_on.iter().map(ToString::to_string).collect(),
+            ..TestCase::default()
+        }
+    }
+
+    #[test]
+    fn numeric_literals_keep_signs_and_skip_names() {
+        let literals: Vec<String> = numeric_literals(r#"=LOG10(A1)+ABS(-42)-3.5e2*MAX(1,.5)&"7""#)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect();
+        assert_eq!(literals, ["-42", "3.5e2", "1", ".5"]);
+    }
+
+    #[test]
+    fn constant_formulas_are_recognized() {
+        assert!(is_constant("=42"));
+        assert!(is_constant("=(-1.5)"));
+        assert!(is_constant(r#"="say ""hi""""#));
+        assert!(!is_constant("=1+1"));
+        assert!(!is_constant("=inf"));
+        assert!(!is_constant(r#"="a"&"b""#));
+    }
+
+    #[test]
+    fn lint_flags_tests_that_cannot_fail() {
+        let cases = vec![
+            case("math.test_abs", "=ABS(-42)", 42.0, &[]),
+            case("math.test_max", "=MAX(5, 3)", 5.0, &[]),
+            case("math.test_const", "=7", 7.0, &[]),
+            case("math.test_base", "=2*3", 6.0, &[]),
+            case("math.test_same", "=SQRT(36)", 6.0, &["math.test_base"]),
+        ];
+        let findings = lint_test_cases(&cases, &Config::default());
+        assert_eq!(
+            findings,
+            [
+                LintFinding {
+                    name: "math.test_max".to_string(),
+                    kind: LintKind::ExpectedIsLiteral("5".to_string()),
+                },
+                LintFinding {
+                    name: "math.test_const".to_string(),
+                    kind: LintKind::ConstantFormula,
+                },
+                LintFinding {
+                    name: "math.test_same".to_string(),
+                    kind: LintKind::ExpectedIsDependency("math.test_base".to_string()),
+                },
+            ]
+        );
+
+        let config = Config {
+            heuristic_match: false,
+            ..Config::default()
+        };
+        assert_eq!(lint_test_cases(&cases, &config).len(), 2);
+    }
+}
@@ -0,0 +1,148 @@
+//! Timing breakdown of the harness itself (`--profile`).
+//!
+//! Time spent in each phase of the pipeline is summed across all tests,
+//! showing whether forge-demo, Gnumeric or forge-e2e's own parsing
+//! dominates a run. Phases on rayon's threads overlap, so the totals can
+//! add up to more than the wall-clock time.
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Phases
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A step of the pipeline whose time is accumulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Loading and parsing spec files.
+    Load,
+    /// `forge-demo export` runs.
+    Export,
+    /// ssconvert conversions to CSV.
+    Convert,
+    /// Reading results out of the CSV files.
+    Parse,
+    /// Comparing results with their expectations.
+    Compare,
+    /// `forge calculate` runs (perf mode, `--annotate-forge`).
+    Calculate,
+}
+
+impl Phase {
+    /// Every phase, in pipeline order.
+    pub const ALL: [Self; 6] = [
+        Self::Load,
+        Self::Export,
+        Self::Convert,
+        Self::Parse,
+        Self::Compare,
+        Self::Calculate,
+    ];
+
+    /// Returns the phase's name in reports and folded stacks.
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Load => "load",
+            Self::Export => "export",
+            Self::Convert => "convert",
+            Self::Parse => "parse",
+            Self::Compare => "compare",
+            Self::Calculate => "calculate",
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Profile
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Time and call counts per [`Phase`], safe to update from any thread.
+#[derive(Debug, Default)]
+pub struct Profile {
+    /// Nanoseconds spent per phase, indexed by `Phase as usize`.
+    nanos: [AtomicU64; Phase::ALL.len()],
+    /// Times each phase ran.
+    calls: [AtomicU64; Phase::ALL.len()],
+}
+
+impl Profile {
+    /// Adds one run of `phase` that took `elapsed`.
+    pub fn record(&self, phase: Phase, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.nanos[phase as usize].fetch_add(nanos, Ordering::Relaxed);
+        self.calls[phase as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Runs `f`, recording its time under `phase`.
+    pub fn time<T>(&self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let value = f();
+        self.record(phase, start.elapsed());
+        value
+    }
+
+    /// Clears every phase but [`Phase::Load`], e.g. after a discarded
+    /// warmup pass.
+    pub fn reset_run(&self) {
+        for phase in Phase::ALL.into_iter().filter(|&p| p != Phase::Load) {
+            self.nanos[phase as usize].store(0, Ordering::Relaxed);
+            self.calls[phase as usize].store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns `(phase, total time, calls)` for every phase that ran, in
+    /// pipeline order.
+    pub fn breakdown(&self) -> Vec<(Phase, Duration, u64)> {
+        Phase::ALL
+            .into_iter()
+            .map(|phase| {
+                let nanos = self.nanos[phase as usize].load(Ordering::Relaxed);
+                let calls = self.calls[phase as usize].load(Ordering::Relaxed);
+                (phase, Duration::from_nanos(nanos), calls)
+            })
+            .filter(|&(_, _, calls)| calls > 0)
+            .collect()
+    }
+
+    /// Returns the breakdown in folded-stack format (`forge-e2e;export
+    /// 1234`, in microseconds), for `flamegraph.pl` or inferno.
+    pub fn folded(&self) -> String {
+        let mut folded = String::new();
+        for (phase, total, _) in self.breakdown() {
+            let _ = writeln!(folded, "forge-e2e;{} {}", phase.label(), total.as_micros());
+        }
+        folded
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_accumulates_per_phase() {
+        let profile = Profile::default();
+        profile.record(Phase::Load, Duration::from_millis(5));
+        profile.record(Phase::Convert, Duration::from_millis(30));
+        profile.record(Phase::Convert, Duration::from_millis(12));
+        assert_eq!(profile.time(Phase::Parse, || 7), 7);
+
+        let breakdown = profile.breakdown();
+        let phases: Vec<_> = breakdown.iter().map(|(phase, _, _)| *phase).collect();
+        assert_eq!(phases, [Phase::Load, Phase::Convert, Phase::Parse]);
+        assert_eq!(breakdown[1].1, Duration::from_millis(42));
+        assert_eq!(breakdown[1].2, 2);
+        assert!(profile
+            .folded()
+            .starts_with("forge-e2e;load 5000\nforge-e2e;convert 42000\nforge-e2e;parse "));
+
+        profile.reset_run();
+        assert_eq!(profile.folded(), "forge-e2e;load 5000\n");
+    }
+}
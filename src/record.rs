@@ -0,0 +1,291 @@
+//! Bootstrapping expected values from forge-demo (`--record`).
+//!
+//! Writing `expected` by hand for a large new function set is slow. Record
+//! mode evaluates every test that has a formula but no expected value with
+//! `forge calculate` and writes the value into its spec. Specs are edited as
+//! text, so comments, key order and quoting survive; only YAML specs can be
+//! recorded. The values are only as right as forge-demo, so review the diff.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::runner::SpecFile;
+use crate::selection::NameFilter;
+use crate::types::{extract_unrecorded_tests, source_line, InputFormat, TestSpec};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Finding Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A test without an expected value, waiting to be recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unrecorded {
+    /// YAML spec the test is defined in.
+    pub path: PathBuf,
+    /// Fully qualified test name.
+    pub name: String,
+    /// The test's formula.
+    pub formula: String,
+    /// The test's `scale`, applied to forge's value before it is written.
+    pub scale: Option<f64>,
+    /// The test's `base`, whose text expected values can't be recorded.
+    pub base: Option<u32>,
+}
+
+impl Unrecorded {
+    /// Returns the `expected` to write for the value forge calculated, in
+    /// the units the test compares in (see [`crate::types::TestCase::scaled`]).
+    ///
+    /// Tests with a `base` expect text, so they are refused.
+    pub fn expected(&self, calculated: f64) -> Result<f64, String> {
+        if let Some(base) = self.base {
+            return Err(format!(
+                "expects base-{base} text; add its expected value by hand"
+            ));
+        }
+        Ok(self.scale.map_or(calculated, |scale| calculated * scale))
+    }
+}
+
+/// Returns the tests without an expected value in the YAML specs among
+/// `spec_files` that `filter` selects, in file order.
+pub fn find_unrecorded(
+    spec_files: &[SpecFile],
+    filter: &NameFilter,
+) -> Result<Vec<Unrecorded>, String> {
+    let mut unrecorded = Vec::new();
+    for file in spec_files {
+        if InputFormat::Yaml.format_of(&file.path).is_none() {
+            continue;
+        }
+        let content = fs::read_to_string(&file.path)
+            .map_err(|e| format!("Failed to read {}: {e}", file.path.display()))?;
+        let spec = TestSpec::parse(&content, InputFormat::Yaml)
+            .map_err(|e| format!("Failed to parse {}: {e}", file.path.display()))?;
+        unrecorded.extend(
+            extract_unrecorded_tests(&spec)
+                .into_iter()
+                .filter(|(name, _)| filter.matches(name))
+                .map(|(name, scalar)| Unrecorded {
+                    path: file.path.clone(),
+                    name,
+                    formula: scalar.formula.clone().unwrap_or_default(),
+                    scale: scalar.scale,
+                    base: scalar.base,
+                }),
+        );
+    }
+    Ok(unrecorded)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Writing Values
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Writes `expected` for each `(name, value)` of `values` into the YAML
+/// spec at `path`.
+///
+/// Nothing is written if any test can't be edited.
+pub fn record_file(path: &Path, values: &[(&str, f64)]) -> Result<(), String> {
+    let mut content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    for &(name, value) in values {
+        content = record_expected(&content, name, value)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Returns the YAML spec `content` with `expected: <value>` added to the
+/// test `name` (`section.test`), after the test's last line.
+///
+/// The new key is indented like the test's other keys, and every other
+/// line is kept as is. Tests written as flow mappings (`test: {...}`) are
+/// rejected rather than rewritten.
+pub fn record_expected(content: &str, name: &str, value: f64) -> Result<String, String> {
+    let line = source_line(content, name).ok_or_else(|| format!("{name} not found"))?;
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let is_blank = |line: &str| {
+        let trimmed = line.trim();
+        trimmed.is_empty() || trimmed.starts_with('#')
+    };
+
+    let key_line = lines[line - 1];
+    let (_, rest) = key_line
+        .split_once(':')
+        .ok_or_else(|| format!("{name} not found"))?;
+    if !is_blank(rest) {
+        return Err(format!(
+            "{name} is written on one line; add its expected value by hand"
+        ));
+    }
+
+    // The test's keys run until the next line indented no deeper than it
+    let end = (line..lines.len())
+        .find(|&i| !lines[i].trim().is_empty() && indent(lines[i]) <= indent(key_line))
+        .unwrap_or(lines.len());
+    let body = &lines[line..end];
+    let key_indent = body
+        .iter()
+        .find(|l| !is_blank(l))
+        .map(|l| indent(l))
+        .ok_or_else(|| format!("{name} has no keys"))?;
+    let last = line + body.iter().rposition(|l| !is_blank(l)).unwrap_or(0);
+
+    let mut recorded = String::with_capacity(content.len() + 32);
+    for (i, l) in lines.iter().enumerate() {
+        recorded.push_str(l);
+        if i == last {
+            if !l.ends_with('\n') {
+                recorded.push('\n');
+            }
+            let _ = writeln!(
+                recorded,
+                "{:key_indent$}expected: {}",
+                "",
+                yaml_number(value)
+            );
+        }
+    }
+    Ok(recorded)
+}
+
+/// Formats `value` as a YAML number that parses back to it exactly.
+fn yaml_number(value: f64) -> String {
+    if value.is_nan() {
+        ".nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { ".inf" } else { "-.inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::extract_test_cases;
+
+    const SPEC: &str = r#"# New text functions
+_forge_version: "1.0.0"
+
+text:
+  # LEN
+  test_len:
+    value: null
+    formula: "=LEN(\"abc\")"
+
+  test_upper:
+    formula: |
+      =CODE(UPPER("a"))
+  # Already known
+  test_known:
+    formula: "=1+1"
+    expected: 2
+math:
+  test_ratio:
+    formula: "=1/3"
+"#;
+
+    #[test]
+    fn recorded_spec_round_trips() {
+        let mut content = SPEC.to_string();
+        for (name, value) in [
+            ("text.test_len", 3.0),
+            ("text.test_upper", 65.0),
+            ("math.test_ratio", 1.0 / 3.0),
+        ] {
+            content = record_expected(&content, name, value).unwrap();
+        }
+        assert_eq!(
+            content,
+            SPEC.replace(
+                "=LEN(\\\"abc\\\")\"\n",
+                "=LEN(\\\"abc\\\")\"\n    expected: 3\n"
+            )
+            .replace("UPPER(\"a\"))\n", "UPPER(\"a\"))\n    expected: 65\n")
+            .replace("\"=1/3\"\n", "\"=1/3\"\n    expected: 0.3333333333333333\n")
+        );
+
+        let spec = TestSpec::parse(&content, InputFormat::Yaml).unwrap();
+        assert!(extract_unrecorded_tests(&spec).is_empty());
+        let cases = extract_test_cases(&spec);
+        let ratio = cases.iter().find(|c| c.name == "math.test_ratio").unwrap();
+        assert_eq!(ratio.expected.to_bits(), (1.0_f64 / 3.0).to_bits());
+        assert_eq!(cases.len(), 4);
+    }
+
+    #[test]
+    fn record_handles_last_line_and_non_finite_values() {
+        let content = record_expected(
+            "math:\n  test_inf:\n    formula: \"=1/0\"",
+            "math.test_inf",
+            f64::INFINITY,
+        )
+        .unwrap();
+        assert_eq!(
+            content,
+            "math:\n  test_inf:\n    formula: \"=1/0\"\n    expected: .inf\n"
+        );
+        let err = record_expected("math:\n  test_a: {formula: \"=1\"}\n", "math.test_a", 1.0)
+            .unwrap_err();
+        assert!(err.contains("by hand"), "{err}");
+        assert!(record_expected(SPEC, "math.test_missing", 1.0).is_err());
+    }
+
+    #[test]
+    fn find_unrecorded_lists_tests_without_expected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("text.yaml");
+        fs::write(&path, SPEC).unwrap();
+        let spec_files = [SpecFile {
+            path: path.clone(),
+            tests: Vec::new(),
+            skips: Vec::new(),
+            lines: std::collections::BTreeMap::new(),
+        }];
+        let filter = NameFilter::new(&["text.*".to_string()], &[]).unwrap();
+        let mut names: Vec<String> = find_unrecorded(&spec_files, &filter)
+            .unwrap()
+            .into_iter()
+            .map(|u| u.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["text.test_len", "text.test_upper"]);
+
+        record_file(&path, &[("text.test_len", 3.0), ("text.test_upper", 65.0)]).unwrap();
+        let remaining = find_unrecorded(&spec_files, &filter).unwrap();
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn recorded_value_is_scaled_and_base_tests_are_refused() {
+        let unrecorded = Unrecorded {
+            path: PathBuf::from("spec.yaml"),
+            name: "finance.test_revenue".to_string(),
+            formula: "=1.5".to_string(),
+            scale: Some(1000.0),
+            base: None,
+        };
+        assert_eq!(unrecorded.expected(1.5), Ok(1500.0));
+        assert_eq!(
+            Unrecorded {
+                scale: None,
+                ..unrecorded.clone()
+            }
+            .expected(1.5),
+            Ok(1.5)
+        );
+        let hex = Unrecorded {
+            base: Some(16),
+            ..unrecorded
+        };
+        assert!(hex.expected(255.0).unwrap_err().contains("by hand"));
+    }
+}
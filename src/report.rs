@@ -0,0 +1,1629 @@
+//! Machine-readable run reports (JSON, `JUnit` XML, status badge, `SQLite`
+//! history).
+//!
+//! Reports cover every mode of a headless `--all` run; each mode becomes a
+//! JSON entry and a `JUnit` `<testsuite>`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::fs;
+use std::io::{Read as _, Seek as _, SeekFrom, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::badge;
+use crate::config::{Config, DEFAULT_TOLERANCE};
+use crate::engine::SpreadsheetEngine;
+use crate::history;
+use crate::runner::SpecFile;
+use crate::types::{ulp_distance, TestCase, TestResult};
+
+/// File name of the JSON report inside `--output-dir`.
+pub const JSON_REPORT_NAME: &str = "report.json";
+
+/// File name of the `JUnit` report inside `--output-dir`.
+pub const JUNIT_REPORT_NAME: &str = "junit.xml";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Mode Runs
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Results of running the suite in one mode (Normal, Perf, Batch).
+#[derive(Debug)]
+pub struct ModeRun {
+    /// Mode name, e.g. `Normal`.
+    pub mode: String,
+    /// Results in execution order.
+    pub results: Vec<TestResult>,
+    /// Wall-clock time for the whole mode.
+    pub elapsed: Duration,
+    /// Threads the mode ran on, for modes pinned to a thread count (Perf).
+    pub threads: Option<usize>,
+}
+
+impl ModeRun {
+    /// Returns (passed, failed, skipped) counts.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        count_results(&self.results)
+    }
+
+    /// Returns per-test latency percentiles, if durations were recorded.
+    pub fn latency(&self) -> Option<Percentiles> {
+        Percentiles::of(&self.results)
+    }
+}
+
+/// Pass/fail/skip totals across every mode of a run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Passed tests.
+    pub passed: usize,
+    /// Failed tests.
+    pub failed: usize,
+    /// Skipped tests.
+    pub skipped: usize,
+}
+
+impl RunSummary {
+    /// Sums the counts of every mode in `runs`.
+    pub fn of(runs: &[ModeRun]) -> Self {
+        runs.iter().fold(Self::default(), |acc, run| {
+            let (passed, failed, skipped) = run.counts();
+            Self {
+                passed: acc.passed + passed,
+                failed: acc.failed + failed,
+                skipped: acc.skipped + skipped,
+            }
+        })
+    }
+
+    /// Returns the percentage of executed (non-skipped) tests that passed.
+    ///
+    /// `None` if no test was executed.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn pass_rate(&self) -> Option<f64> {
+        let executed = self.passed + self.failed;
+        (executed > 0).then(|| self.passed as f64 * 100.0 / executed as f64)
+    }
+}
+
+/// Returns (passed, failed, skipped) counts for `results`.
+pub fn count_results(results: &[TestResult]) -> (usize, usize, usize) {
+    results
+        .iter()
+        .fold((0, 0, 0), |(p, f, s), result| match result {
+            TestResult::Pass { .. } => (p + 1, f, s),
+            TestResult::Fail { .. } => (p, f + 1, s),
+            TestResult::Skip { .. } => (p, f, s + 1),
+        })
+}
+
+/// Returns the review note for heuristic passes in `results`, or `None`
+/// when every pass came from a labeled result cell.
+///
+/// A heuristic pass matched the expected value somewhere in the output
+/// without a label pointing at it, so it may have matched the wrong cell.
+pub fn heuristic_note(results: &[TestResult]) -> Option<String> {
+    let count = results.iter().filter(|r| r.is_heuristic_pass()).count();
+    let noun = if count == 1 { "pass" } else { "passes" };
+    (count > 0).then(|| format!("{count} {noun} via heuristic — review recommended"))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Comparison Methods
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// How a result was judged against its expected value, from strictest to
+/// loosest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ComparisonMethod {
+    /// Equal up to [`DEFAULT_TOLERANCE`], or a non-finite value.
+    Exact,
+    /// Within a few representable doubles (`tolerance_ulps`).
+    Ulps,
+    /// An integer parsed from text in the test's `base`.
+    Text,
+    /// Within a looser absolute tolerance (per test, category or global).
+    Tolerance,
+    /// Matched by value, with no labeled result cell.
+    Heuristic,
+}
+
+impl ComparisonMethod {
+    /// Returns the method `case` is compared with under `config`, ignoring
+    /// how its result was found.
+    pub fn of(case: &TestCase, config: &Config) -> Self {
+        if case.base.is_some() {
+            Self::Text
+        } else if case.tolerance_ulps.is_some() {
+            Self::Ulps
+        } else if !case.expected.is_finite() || config.tolerance_for(case) <= DEFAULT_TOLERANCE {
+            Self::Exact
+        } else {
+            Self::Tolerance
+        }
+    }
+
+    /// Returns the method's name in summaries.
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Ulps => "ULPs",
+            Self::Text => "text",
+            Self::Tolerance => "absolute tolerance",
+            Self::Heuristic => "heuristic fallback",
+        }
+    }
+}
+
+/// Returns `(method, passed, failed)` counts across every mode of `runs`,
+/// strictest method first; methods no result used are left out.
+///
+/// Heuristic passes count as [`ComparisonMethod::Heuristic`] whatever
+/// their tolerance. Skips, and results whose test isn't in `cases`, are
+/// not counted.
+pub fn comparison_methods(
+    runs: &[ModeRun],
+    cases: &[TestCase],
+    config: &Config,
+) -> Vec<(ComparisonMethod, usize, usize)> {
+    let cases: HashMap<&str, &TestCase> = cases.iter().map(|c| (c.name.as_str(), c)).collect();
+    let mut counts: BTreeMap<ComparisonMethod, (usize, usize)> = BTreeMap::new();
+    for result in runs.iter().flat_map(|run| &run.results) {
+        let Some(case) = cases.get(result.name()) else {
+            continue;
+        };
+        let method = if result.is_heuristic_pass() {
+            ComparisonMethod::Heuristic
+        } else {
+            ComparisonMethod::of(case, config)
+        };
+        let entry = counts.entry(method).or_default();
+        match result {
+            TestResult::Pass { .. } => entry.0 += 1,
+            TestResult::Fail { .. } => entry.1 += 1,
+            TestResult::Skip { .. } => {}
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, (passed, failed))| passed + failed > 0)
+        .map(|(method, (passed, failed))| (method, passed, failed))
+        .collect()
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tolerance Headroom
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The tolerance a test is compared with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToleranceLimit {
+    /// Maximum absolute difference.
+    Absolute(f64),
+    /// Maximum distance in units in the last place.
+    Ulps(u64),
+}
+
+/// How much of its tolerance a passing test used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Headroom {
+    /// Test name.
+    pub name: String,
+    /// Absolute difference from the closest acceptable value.
+    pub abs_diff: f64,
+    /// `abs_diff` relative to that value (`None` when it is zero).
+    pub rel_diff: Option<f64>,
+    /// The tolerance the test passed within.
+    pub limit: ToleranceLimit,
+    /// Fraction of the tolerance used (0 = exact, 1 = at the limit).
+    pub used: f64,
+}
+
+/// Returns the headroom of every pass in `results`, closest to its limit
+/// first, so tolerances can be tightened from data.
+///
+/// Passes against non-finite values, and results whose test isn't in
+/// `cases`, are left out.
+#[allow(clippy::cast_precision_loss)] // ULP counts only set a ratio
+pub fn tolerance_headroom(
+    results: &[TestResult],
+    cases: &[TestCase],
+    config: &Config,
+) -> Vec<Headroom> {
+    let cases: HashMap<&str, &TestCase> = cases.iter().map(|c| (c.name.as_str(), c)).collect();
+    let mut headroom: Vec<Headroom> = results
+        .iter()
+        .filter_map(|result| {
+            let TestResult::Pass { name, actual, .. } = result else {
+                return None;
+            };
+            let case = cases.get(name.as_str())?;
+            if !actual.is_finite() {
+                return None;
+            }
+            let closest = case
+                .acceptable_values()
+                .filter(|v| v.is_finite())
+                .min_by(|a, b| (actual - a).abs().total_cmp(&(actual - b).abs()))?;
+            let abs_diff = (actual - closest).abs();
+            let limit = case.tolerance_ulps.map_or_else(
+                || ToleranceLimit::Absolute(config.tolerance_for(case)),
+                ToleranceLimit::Ulps,
+            );
+            let used = match limit {
+                // A zero-ULP pass is exact
+                ToleranceLimit::Ulps(ulps) => {
+                    ulp_distance(*actual, closest) as f64 / ulps.max(1) as f64
+                }
+                ToleranceLimit::Absolute(tolerance) if tolerance > 0.0 => abs_diff / tolerance,
+                ToleranceLimit::Absolute(_) => 0.0,
+            };
+            Some(Headroom {
+                name: name.clone(),
+                abs_diff,
+                rel_diff: (closest.abs() > 0.0).then(|| abs_diff / closest.abs()),
+                limit,
+                used,
+            })
+        })
+        .collect();
+    headroom.sort_by(|a, b| b.used.total_cmp(&a.used).then_with(|| a.name.cmp(&b.name)));
+    headroom
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Latency
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Per-test latency distribution for one mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    /// Median latency.
+    pub p50: Duration,
+    /// 90th percentile latency.
+    pub p90: Duration,
+    /// 99th percentile latency.
+    pub p99: Duration,
+}
+
+impl Percentiles {
+    /// Computes percentiles over the measured durations in `results`.
+    ///
+    /// Returns `None` if no result carries a duration (e.g. batch mode,
+    /// where tests share one conversion).
+    pub fn of(results: &[TestResult]) -> Option<Self> {
+        Self::of_durations(results.iter().filter_map(TestResult::duration).collect())
+    }
+
+    /// Computes percentiles over `durations`; `None` if it is empty.
+    fn of_durations(mut durations: Vec<Duration>) -> Option<Self> {
+        durations.sort_unstable();
+        Some(Self {
+            p50: percentile(&durations, 50)?,
+            p90: percentile(&durations, 90)?,
+            p99: percentile(&durations, 99)?,
+        })
+    }
+
+    /// Returns the percentiles as a JSON object in milliseconds.
+    pub fn to_json(self) -> serde_json::Value {
+        serde_json::json!({
+            "p50_ms": self.p50.as_secs_f64() * 1000.0,
+            "p90_ms": self.p90.as_secs_f64() * 1000.0,
+            "p99_ms": self.p99.as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+/// Returns the `p`th percentile of `sorted` using the nearest-rank method.
+fn percentile(sorted: &[Duration], p: usize) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    // Smallest value with at least p% of samples at or below it
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Metadata
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Code versions a report was produced with, for tying archived reports
+/// back to exact sources.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportMetadata {
+    /// Commit forge-e2e was built from, if built in a git checkout.
+    pub commit: Option<String>,
+    /// `forge-demo --version` output, if the binary reports one.
+    pub forge_version: Option<String>,
+}
+
+impl ReportMetadata {
+    /// Collects metadata for a run against `forge_binary`.
+    ///
+    /// Each field is `None` when it can't be determined (not built in a
+    /// checkout, binary without `--version`).
+    pub fn collect(forge_binary: &Path) -> Self {
+        Self {
+            commit: build_commit(),
+            forge_version: command_output(Command::new(forge_binary).arg("--version")),
+        }
+    }
+
+    /// Returns the metadata as a JSON object.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "forge_e2e_version": env!("CARGO_PKG_VERSION"),
+            "forge_e2e_commit": self.commit,
+            "forge_demo_version": self.forge_version,
+        })
+    }
+}
+
+/// Returns the commit forge-e2e was built from, captured by `build.rs`.
+fn build_commit() -> Option<String> {
+    let commit = env!("FORGE_E2E_COMMIT");
+    (!commit.is_empty()).then(|| commit.to_string())
+}
+
+/// Runs `cmd` and returns its trimmed stdout, or `None` on any failure.
+fn command_output(cmd: &mut Command) -> Option<String> {
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!stdout.is_empty()).then_some(stdout)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Manifest
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Builds the run manifest: every loaded spec file with the tests and skips
+/// extracted from it, the engine and binary versions, and the effective
+/// config. Lets a reviewer see exactly what a run covered before reading
+/// its results.
+pub fn manifest_json(
+    spec_files: &[SpecFile],
+    engine_version: &str,
+    config: &Config,
+    metadata: &ReportMetadata,
+) -> serde_json::Value {
+    let specs: Vec<serde_json::Value> = spec_files
+        .iter()
+        .map(|file| {
+            serde_json::json!({
+                "path": file.path.display().to_string(),
+                "tests": file.tests,
+                "skips": file.skips,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "spec_files": specs,
+        "total_tests": spec_files.iter().map(|f| f.tests.len()).sum::<usize>(),
+        "total_skips": spec_files.iter().map(|f| f.skips.len()).sum::<usize>(),
+        "engine": {
+            "name": SpreadsheetEngine::name(),
+            "version": engine_version,
+        },
+        "metadata": metadata.to_json(),
+        "config": config.to_json(),
+    })
+}
+
+/// Writes a manifest built by [`manifest_json`] to `path`.
+pub fn write_manifest(path: &Path, manifest: &serde_json::Value) -> Result<(), String> {
+    let json =
+        serde_json::to_string_pretty(manifest).map_err(|e| format!("Failed to serialize: {e}"))?;
+    write_file(path, &json)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Targets
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Where to write each report; `None` disables that format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportTargets {
+    /// JSON report path.
+    pub json: Option<PathBuf>,
+    /// Directory for one JSON file per result, grouped by mode.
+    pub json_dir: Option<PathBuf>,
+    /// `JUnit` XML report path.
+    pub junit: Option<PathBuf>,
+    /// Status badge path: shields.io endpoint JSON for `.json`, SVG otherwise.
+    pub badge: Option<PathBuf>,
+    /// `SQLite` database each run is appended to.
+    pub sqlite: Option<PathBuf>,
+    /// Whether skipped tests appear in the JSON results list.
+    pub include_skips: bool,
+    /// Whether to merge into an existing JSON report instead of replacing it.
+    pub merge_json: bool,
+    /// Code versions embedded in the JSON and `JUnit` reports.
+    pub metadata: ReportMetadata,
+}
+
+impl ReportTargets {
+    /// Resolves report paths from the CLI flags.
+    ///
+    /// `--output-dir` enables every format at its conventional name inside
+    /// the directory; explicit `--json`/`--junit` paths take precedence.
+    pub fn resolve(
+        json: Option<PathBuf>,
+        junit: Option<PathBuf>,
+        output_dir: Option<&Path>,
+    ) -> Self {
+        Self {
+            json: json.or_else(|| output_dir.map(|d| d.join(JSON_REPORT_NAME))),
+            junit: junit.or_else(|| output_dir.map(|d| d.join(JUNIT_REPORT_NAME))),
+            json_dir: None,
+            badge: None,
+            sqlite: None,
+            include_skips: true,
+            merge_json: false,
+            metadata: ReportMetadata::default(),
+        }
+    }
+
+    /// Sets whether skipped tests appear in the JSON results list.
+    #[must_use]
+    pub const fn with_skips(mut self, include_skips: bool) -> Self {
+        self.include_skips = include_skips;
+        self
+    }
+
+    /// Sets the directory for per-result JSON files.
+    #[must_use]
+    pub fn with_json_dir(mut self, json_dir: Option<PathBuf>) -> Self {
+        self.json_dir = json_dir;
+        self
+    }
+
+    /// Sets whether the JSON report is merged into an existing one.
+    #[must_use]
+    pub const fn with_merge_json(mut self, merge_json: bool) -> Self {
+        self.merge_json = merge_json;
+        self
+    }
+
+    /// Sets the status badge path.
+    #[must_use]
+    pub fn with_badge(mut self, badge: Option<PathBuf>) -> Self {
+        self.badge = badge;
+        self
+    }
+
+    /// Sets the `SQLite` database the run is appended to.
+    #[must_use]
+    pub fn with_sqlite(mut self, sqlite: Option<PathBuf>) -> Self {
+        self.sqlite = sqlite;
+        self
+    }
+
+    /// Sets the code versions embedded in the reports.
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: ReportMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Returns `true` if no report was requested.
+    pub const fn is_empty(&self) -> bool {
+        self.json.is_none()
+            && self.json_dir.is_none()
+            && self.junit.is_none()
+            && self.badge.is_none()
+            && self.sqlite.is_none()
+    }
+
+    /// Writes every requested report, returning the paths written.
+    ///
+    /// The per-result directory is reported once rather than per file.
+    pub fn write(&self, runs: &[ModeRun]) -> Result<Vec<PathBuf>, String> {
+        let mut written = Vec::new();
+        if let Some(path) = &self.json {
+            let report = json_report(runs, self.include_skips, &self.metadata);
+            if self.merge_json {
+                merge_json_file(path, &report)?;
+            } else {
+                let json = serde_json::to_string_pretty(&report)
+                    .map_err(|e| format!("Failed to serialize: {e}"))?;
+                write_file(path, &json)?;
+            }
+            written.push(path.clone());
+        }
+        if let Some(dir) = &self.json_dir {
+            write_result_files(dir, runs, self.include_skips)?;
+            written.push(dir.clone());
+        }
+        if let Some(path) = &self.junit {
+            write_file(path, &junit_report(runs, &self.metadata))?;
+            written.push(path.clone());
+        }
+        if let Some(path) = &self.badge {
+            let summary = RunSummary::of(runs);
+            let contents = if path.extension().is_some_and(|e| e == "json") {
+                serde_json::to_string_pretty(&badge::badge_endpoint_json(&summary))
+                    .map_err(|e| format!("Failed to serialize: {e}"))?
+            } else {
+                badge::badge_svg(&summary)
+            };
+            write_file(path, &contents)?;
+            written.push(path.clone());
+        }
+        if let Some(path) = &self.sqlite {
+            history::append_run(path, runs, &self.metadata)?;
+            written.push(path.clone());
+        }
+        Ok(written)
+    }
+}
+
+/// Writes `contents` to `path`, creating parent directories as needed.
+///
+/// The file is written beside `path` and renamed into place, so a
+/// concurrent run writing the same report can't interleave with this one
+/// and readers never see a partial file: the last writer wins whole. The
+/// temporary file is created like any other, so the report's permissions
+/// follow the umask.
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Failed to write {}: not a file path", path.display()))?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = parent.join(temp_name);
+    fs::write(&temp_path, contents)
+        .and_then(|()| fs::rename(&temp_path, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            format!("Failed to write {}: {e}", path.display())
+        })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Merging
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Merges `report` into the JSON report at `path`, creating it if missing.
+///
+/// For sharded CI runs sharing one `--json` path: an exclusive lock on the
+/// report itself serializes the read-merge-write of concurrent processes,
+/// so no shard's results are lost. The merged report is written in place
+/// while the lock is held.
+fn merge_json_file(path: &Path, report: &serde_json::Value) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    file.lock()
+        .map_err(|e| format!("Failed to lock {}: {e}", path.display()))?;
+
+    let mut existing = String::new();
+    file.read_to_string(&mut existing)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    // A file only just created by this merge is empty
+    let merged = if existing.trim().is_empty() {
+        report.clone()
+    } else {
+        let existing: serde_json::Value = serde_json::from_str(&existing)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+        merge_json_reports(&existing, report)
+            .map_err(|e| format!("Cannot merge into {}: {e}", path.display()))?
+    };
+    let json =
+        serde_json::to_string_pretty(&merged).map_err(|e| format!("Failed to serialize: {e}"))?;
+    file.set_len(0)
+        .and_then(|()| file.seek(SeekFrom::Start(0)))
+        .and_then(|_| file.write_all(json.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    // The lock is released when `file` is dropped
+}
+
+/// Combines two JSON reports built by [`json_report`].
+///
+/// Modes present in both have their results concatenated, summaries
+/// summed, elapsed time set to the longer one (shards run side by side)
+/// and latency recomputed from the combined results. Other modes are
+/// appended. The timestamp and metadata come from `new`.
+pub fn merge_json_reports(
+    existing: &serde_json::Value,
+    new: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let Some(existing_modes) = existing["modes"].as_array() else {
+        return Err("not a forge-e2e JSON report (no \"modes\" list)".to_string());
+    };
+    let mut modes = existing_modes.clone();
+    for mode in new["modes"].as_array().into_iter().flatten() {
+        match modes.iter_mut().find(|m| m["mode"] == mode["mode"]) {
+            Some(into) => merge_mode(into, mode),
+            None => modes.push(mode.clone()),
+        }
+    }
+    let mut merged = new.clone();
+    merged["modes"] = serde_json::Value::Array(modes);
+    Ok(merged)
+}
+
+/// Merges one mode entry of a JSON report into another.
+fn merge_mode(into: &mut serde_json::Value, from: &serde_json::Value) {
+    let mut results = into["results"].as_array().cloned().unwrap_or_default();
+    results.extend(from["results"].as_array().into_iter().flatten().cloned());
+
+    for key in ["total", "passed", "failed", "skipped"] {
+        let sum =
+            into["summary"][key].as_u64().unwrap_or(0) + from["summary"][key].as_u64().unwrap_or(0);
+        into["summary"][key] = sum.into();
+    }
+    let elapsed = into["elapsed_secs"]
+        .as_f64()
+        .unwrap_or(0.0)
+        .max(from["elapsed_secs"].as_f64().unwrap_or(0.0));
+    into["elapsed_secs"] = elapsed.into();
+    let durations = results
+        .iter()
+        .filter_map(|r| r["duration_secs"].as_f64())
+        .map(Duration::from_secs_f64)
+        .collect();
+    into["latency"] =
+        Percentiles::of_durations(durations).map_or(serde_json::Value::Null, Percentiles::to_json);
+    into["results"] = serde_json::Value::Array(results);
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Baseline
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// How a run's failures moved against a baseline JSON report.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BaselineDelta {
+    /// Keys of tests that failed in the baseline and pass now, sorted.
+    pub fixed: Vec<String>,
+    /// Keys of tests that fail now but didn't in the baseline (including
+    /// tests the baseline doesn't have), sorted.
+    pub regressed: Vec<String>,
+}
+
+impl BaselineDelta {
+    /// Compares `runs` with `baseline`, a JSON report of an earlier run.
+    ///
+    /// Results are matched by mode and [`TestResult::key`]. A test counts
+    /// once however many modes it moved in; regressing in any mode wins
+    /// over being fixed in another.
+    pub fn between(baseline: &serde_json::Value, runs: &[ModeRun]) -> Self {
+        let mut before: HashMap<(&str, &str), &str> = HashMap::new();
+        for mode in baseline["modes"].as_array().into_iter().flatten() {
+            let name = mode["mode"].as_str().unwrap_or_default();
+            for result in mode["results"].as_array().into_iter().flatten() {
+                let key = result["id"].as_str().or_else(|| result["name"].as_str());
+                if let (Some(key), Some(status)) = (key, result["status"].as_str()) {
+                    before.insert((name, key), status);
+                }
+            }
+        }
+
+        let mut fixed = BTreeSet::new();
+        let mut regressed = BTreeSet::new();
+        for run in runs {
+            for result in &run.results {
+                let was = before.get(&(run.mode.as_str(), result.key())).copied();
+                if result.is_fail() && was != Some("fail") {
+                    regressed.insert(result.key().to_string());
+                } else if result.is_pass() && was == Some("fail") {
+                    fixed.insert(result.key().to_string());
+                }
+            }
+        }
+        fixed.retain(|key| !regressed.contains(key));
+        Self {
+            fixed: fixed.into_iter().collect(),
+            regressed: regressed.into_iter().collect(),
+        }
+    }
+
+    /// Returns the one-line summary, e.g.
+    /// `Δ vs baseline: +2 fixed, -1 regressed (math.test_sqrt)`.
+    pub fn summary(&self) -> String {
+        let mut line = format!(
+            "Δ vs baseline: +{} fixed, -{} regressed",
+            self.fixed.len(),
+            self.regressed.len()
+        );
+        if !self.regressed.is_empty() {
+            let _ = write!(line, " ({})", self.regressed.join(", "));
+        }
+        line
+    }
+}
+
+/// Reads a JSON report written by an earlier `--json` run.
+pub fn read_baseline(path: &Path) -> Result<serde_json::Value, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read baseline {}: {e}", path.display()))?;
+    let report: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse baseline {}: {e}", path.display()))?;
+    if report["modes"].is_array() {
+        Ok(report)
+    } else {
+        Err(format!(
+            "{} is not a forge-e2e JSON report (no \"modes\" list)",
+            path.display()
+        ))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// JSON
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Builds the JSON summary block shared by the CLI and TUI reports.
+pub fn summary_json(results: &[TestResult]) -> serde_json::Value {
+    let (passed, failed, skipped) = count_results(results);
+    serde_json::json!({
+        "total": results.len(),
+        "passed": passed,
+        "failed": failed,
+        "skipped": skipped,
+    })
+}
+
+/// Returns the results to serialize, dropping skips unless `include_skips`.
+///
+/// Summaries should still be computed from the full result list.
+pub fn exported_results(results: &[TestResult], include_skips: bool) -> Vec<&TestResult> {
+    results
+        .iter()
+        .filter(|r| include_skips || !matches!(r, TestResult::Skip { .. }))
+        .collect()
+}
+
+/// Builds the JSON report for a multi-mode run.
+pub fn json_report(
+    runs: &[ModeRun],
+    include_skips: bool,
+    metadata: &ReportMetadata,
+) -> serde_json::Value {
+    let modes: Vec<serde_json::Value> = runs
+        .iter()
+        .map(|run| {
+            serde_json::json!({
+                "mode": run.mode,
+                "elapsed_secs": run.elapsed.as_secs_f64(),
+                "threads": run.threads,
+                "summary": summary_json(&run.results),
+                "latency": run.latency().map(Percentiles::to_json),
+                "results": exported_results(&run.results, include_skips),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "timestamp": chrono::Local::now().to_rfc3339(),
+        "metadata": metadata.to_json(),
+        "modes": modes,
+    })
+}
+
+/// Writes each result as `<dir>/<mode>/<test-key>.json`.
+///
+/// Files are named by [`TestResult::key`], so a test with an `id` keeps its
+/// file across renames. Keys are sanitized by [`result_file_name`]; should
+/// two keys collapse to the same file, later ones get a numeric suffix
+/// instead of overwriting.
+pub fn write_result_files(dir: &Path, runs: &[ModeRun], include_skips: bool) -> Result<(), String> {
+    for run in runs {
+        let mode_dir = dir.join(result_file_name(&run.mode.to_lowercase()));
+        let mut used = HashSet::new();
+        for result in exported_results(&run.results, include_skips) {
+            let stem = result_file_name(result.key());
+            let mut file_name = format!("{stem}.json");
+            let mut n = 1;
+            while !used.insert(file_name.clone()) {
+                n += 1;
+                file_name = format!("{stem}-{n}.json");
+            }
+            let json = serde_json::to_string_pretty(result)
+                .map_err(|e| format!("Failed to serialize: {e}"))?;
+            write_file(&mode_dir.join(file_name), &json)?;
+        }
+    }
+    Ok(())
+}
+
+/// Turns a test name into a safe file stem.
+///
+/// Keeps ASCII letters, digits, `.`, `_` and `-`; anything else (path
+/// separators, spaces, quotes) becomes `_`. Leading dots are replaced too,
+/// so no name yields a hidden file or `..`.
+pub fn result_file_name(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let safe = c.is_ascii_alphanumeric() || matches!(c, '_' | '-') || (c == '.' && i > 0);
+            if safe {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        "_".to_string()
+    } else {
+        stem
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// JUnit
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Builds a `JUnit` XML report with one `<testsuite>` per mode.
+///
+/// Metadata is repeated as `<properties>` in every suite, since the root
+/// `<testsuites>` element has no place for it.
+pub fn junit_report(runs: &[ModeRun], metadata: &ReportMetadata) -> String {
+    let (tests, failures, skipped) = runs.iter().fold((0, 0, 0), |(t, f, s), run| {
+        let (_, failed, skip) = run.counts();
+        (t + run.results.len(), f + failed, s + skip)
+    });
+    let total_time: f64 = runs.iter().map(|r| r.elapsed.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        r#"<testsuites name="forge-e2e" tests="{tests}" failures="{failures}" skipped="{skipped}" time="{total_time:.3}">"#
+    );
+    for run in runs {
+        let (_, failed, skip) = run.counts();
+        let mode = xml_escape(&run.mode);
+        let _ = writeln!(
+            xml,
+            r#"  <testsuite name="{mode}" tests="{}" failures="{failed}" skipped="{skip}" time="{:.3}">"#,
+            run.results.len(),
+            run.elapsed.as_secs_f64()
+        );
+        write_junit_properties(&mut xml, metadata);
+        for result in &run.results {
+            write_junit_case(&mut xml, &mode, result);
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Appends a `<properties>` block for the known `metadata` fields.
+fn write_junit_properties(xml: &mut String, metadata: &ReportMetadata) {
+    xml.push_str("    <properties>\n");
+    let known = [
+        ("forge_e2e_version", Some(env!("CARGO_PKG_VERSION"))),
+        ("forge_e2e_commit", metadata.commit.as_deref()),
+        ("forge_demo_version", metadata.forge_version.as_deref()),
+    ];
+    for (name, value) in known {
+        if let Some(value) = value {
+            let _ = writeln!(
+                xml,
+                r#"      <property name="{name}" value="{}"/>"#,
+                xml_escape(value)
+            );
+        }
+    }
+    xml.push_str("    </properties>\n");
+}
+
+/// Appends one `<testcase>` element for `result`.
+fn write_junit_case(xml: &mut String, classname: &str, result: &TestResult) {
+    let name = xml_escape(result.name());
+    let _ = write!(
+        xml,
+        r#"    <testcase classname="{classname}" name="{name}""#
+    );
+    match result {
+        TestResult::Pass { .. } => xml.push_str("/>\n"),
+        TestResult::Fail {
+            formula,
+            expected,
+            actual,
+            error,
+            ..
+        } => {
+            let message = error.as_deref().map_or_else(
+                || {
+                    actual.map_or_else(
+                        || format!("expected {expected}"),
+                        |a| format!("expected {expected}, got {a}"),
+                    )
+                },
+                |e| e.lines().next().unwrap_or_default().to_string(),
+            );
+            let mut details = format!("formula:  {formula}\nexpected: {expected}\n");
+            if let Some(a) = actual {
+                let _ = writeln!(details, "actual:   {a}");
+            }
+            if let Some(e) = error {
+                let _ = writeln!(details, "error:    {e}");
+            }
+            let _ = write!(
+                xml,
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                xml_escape(&message),
+                xml_escape(&details)
+            );
+        }
+        TestResult::Skip { reason, .. } => {
+            let _ = write!(
+                xml,
+                ">\n      <skipped message=\"{}\"/>\n    </testcase>\n",
+                xml_escape(reason)
+            );
+        }
+    }
+}
+
+/// Escapes text for XML attribute values and character data.
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than tab/newline are illegal in XML 1.0
+            c if c.is_control() && c != '\t' && c != '\n' && c != '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// GitHub Annotations
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Returns a GitHub Actions `::error` workflow command for each failure in
+/// `runs`, pointing at the line of the spec file that defines the test so
+/// it shows up inline on the PR diff.
+///
+/// A test failing the same way in several modes is annotated once.
+pub fn github_annotations(spec_files: &[SpecFile], runs: &[ModeRun]) -> Vec<String> {
+    let cwd = std::env::current_dir().ok();
+    let mut seen = HashSet::new();
+    let mut annotations = Vec::new();
+    for result in runs.iter().flat_map(|run| &run.results) {
+        let TestResult::Fail {
+            name,
+            expected,
+            actual,
+            error,
+            ..
+        } = result
+        else {
+            continue;
+        };
+        let message = match (actual, error) {
+            (Some(a), _) => format!("{name} expected {expected} got {a}"),
+            (None, Some(e)) => format!("{name} failed: {}", e.lines().next().unwrap_or_default()),
+            (None, None) => format!("{name} expected {expected}"),
+        };
+        let location = spec_files
+            .iter()
+            .find(|file| file.tests.contains(name))
+            .map(|file| {
+                // GitHub resolves paths against the workspace root
+                let path = cwd
+                    .as_deref()
+                    .and_then(|cwd| file.path.strip_prefix(cwd).ok())
+                    .unwrap_or(&file.path);
+                let mut location =
+                    format!(" file={}", escape_property(&path.display().to_string()));
+                if let Some(line) = file.line_of(name) {
+                    let _ = write!(location, ",line={line}");
+                }
+                location
+            })
+            .unwrap_or_default();
+        let annotation = format!("::error{location}::{}", escape_data(&message));
+        if seen.insert(annotation.clone()) {
+            annotations.push(annotation);
+        }
+    }
+    annotations
+}
+
+/// Escapes a workflow command's message.
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a workflow command property value.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn sample_run() -> ModeRun {
+        ModeRun {
+            mode: "Normal".to_string(),
+            results: vec![
+                TestResult::Pass {
+                    name: "assumptions.test_abs".to_string(),
+                    id: None,
+                    formula: "=ABS(-1)".to_string(),
+                    expected: 1.0,
+                    actual: 1.0,
+                    heuristic: false,
+                    forge: None,
+                    duration: None,
+                },
+                TestResult::Fail {
+                    name: "assumptions.test_lt".to_string(),
+                    id: None,
+                    formula: "=IF(1<2,1,0)".to_string(),
+                    expected: 1.0,
+                    actual: Some(0.0),
+                    error: None,
+                    duration: None,
+                },
+                TestResult::Skip {
+                    name: "assumptions.test_datedif".to_string(),
+                    id: None,
+                    reason: "Gnumeric & Excel differ".to_string(),
+                },
+            ],
+            elapsed: Duration::from_millis(1500),
+            threads: None,
+        }
+    }
+
+    fn case(name: &str, tolerance: Option<f64>) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            formula: "=1".to_string(),
+            expected: 1.0,
+            tolerance,
+            ..TestCase::default()
+        }
+    }
+
+    fn millis(ms: &[u64]) -> Vec<Duration> {
+        ms.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn github_annotations_point_at_failing_tests() {
+        let spec_files = [SpecFile {
+            path: PathBuf::from("tests/e2e/assumptions.yaml"),
+            tests: vec!["assumptions.test_lt".to_string()],
+            skips: Vec::new(),
+            lines: BTreeMap::from([("assumptions.test_lt".to_string(), 12)]),
+        }];
+        let mut batch = sample_run();
+        batch.mode = "Batch".to_string();
+        batch.results.push(TestResult::Fail {
+            name: "other.test_missing".to_string(),
+            id: None,
+            formula: "=1".to_string(),
+            expected: 1.0,
+            actual: None,
+            error: Some("Result not found\nin 3 CSV files".to_string()),
+            duration: None,
+        });
+
+        assert_eq!(
+            github_annotations(&spec_files, &[sample_run(), batch]),
+            [
+                "::error file=tests/e2e/assumptions.yaml,line=12::assumptions.test_lt expected 1 got 0",
+                "::error::other.test_missing failed: Result not found",
+            ]
+        );
+        assert_eq!(escape_data("50%\nmore"), "50%25%0Amore");
+        assert_eq!(escape_property("C:\\a,b"), "C%3A\\a%2Cb");
+    }
+
+    #[test]
+    fn heuristic_note_counts_heuristic_passes() {
+        let mut results = sample_run().results;
+        assert_eq!(heuristic_note(&results), None);
+        let mut heuristic_pass = results[0].clone();
+        if let TestResult::Pass { heuristic, .. } = &mut heuristic_pass {
+            *heuristic = true;
+        }
+        results.push(heuristic_pass.clone());
+        assert_eq!(
+            heuristic_note(&results).as_deref(),
+            Some("1 pass via heuristic — review recommended")
+        );
+        results.push(heuristic_pass);
+        assert_eq!(
+            heuristic_note(&results).as_deref(),
+            Some("2 passes via heuristic — review recommended")
+        );
+    }
+
+    #[test]
+    fn comparison_methods_count_passes_and_failures() {
+        let cases = [
+            case("assumptions.test_abs", None),
+            case("assumptions.test_lt", Some(0.01)),
+            case("assumptions.test_datedif", None),
+        ];
+        let mut run = sample_run();
+        let mut heuristic_pass = run.results[0].clone();
+        if let TestResult::Pass { heuristic, .. } = &mut heuristic_pass {
+            *heuristic = true;
+        }
+        run.results.push(heuristic_pass);
+
+        let config = Config::default();
+        assert_eq!(
+            comparison_methods(&[run, sample_run()], &cases, &config),
+            vec![
+                (ComparisonMethod::Exact, 2, 0),
+                (ComparisonMethod::Tolerance, 0, 2),
+                (ComparisonMethod::Heuristic, 1, 0),
+            ]
+        );
+        assert_eq!(
+            ComparisonMethod::of(&cases[0], &config),
+            ComparisonMethod::Exact
+        );
+        let loose = Config {
+            tolerance: 1e-9,
+            ..Config::default()
+        };
+        assert_eq!(
+            ComparisonMethod::of(&cases[0], &loose),
+            ComparisonMethod::Tolerance
+        );
+        let text = TestCase {
+            base: Some(16),
+            ..cases[1].clone()
+        };
+        assert_eq!(ComparisonMethod::of(&text, &config), ComparisonMethod::Text);
+    }
+
+    #[test]
+    fn tolerance_headroom_sorts_closest_to_limit_first() {
+        let pass = |name: &str, actual: f64| TestResult::Pass {
+            name: name.to_string(),
+            id: None,
+            formula: "=1".to_string(),
+            expected: 1.0,
+            actual,
+            heuristic: false,
+            forge: None,
+            duration: None,
+        };
+        let cases = [
+            case("math.test_loose", Some(0.1)),
+            case("math.test_tight", Some(0.01)),
+            TestCase {
+                tolerance_ulps: Some(4),
+                ..case("math.test_ulps", None)
+            },
+            TestCase {
+                expected: f64::INFINITY,
+                ..case("math.test_inf", None)
+            },
+        ];
+        let results = [
+            pass("math.test_loose", 1.02),
+            pass("math.test_tight", 0.995),
+            pass("math.test_ulps", 1.0 + f64::EPSILON),
+            pass("math.test_inf", f64::INFINITY),
+            sample_run().results[1].clone(),
+        ];
+
+        let headroom = tolerance_headroom(&results, &cases, &Config::default());
+        let names: Vec<&str> = headroom.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["math.test_tight", "math.test_ulps", "math.test_loose"]
+        );
+        assert!((headroom[0].used - 0.5).abs() < 1e-9);
+        assert!((headroom[0].abs_diff - 0.005).abs() < 1e-9);
+        assert_eq!(headroom[0].limit, ToleranceLimit::Absolute(0.01));
+        assert!((headroom[1].used - 0.25).abs() < f64::EPSILON);
+        assert_eq!(headroom[1].limit, ToleranceLimit::Ulps(4));
+        assert!((headroom[2].rel_diff.unwrap() - 0.02).abs() < 1e-9);
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let sorted = millis(&(1..=100).collect::<Vec<_>>());
+        assert_eq!(percentile(&sorted, 50), Some(Duration::from_millis(50)));
+        assert_eq!(percentile(&sorted, 90), Some(Duration::from_millis(90)));
+        assert_eq!(percentile(&sorted, 99), Some(Duration::from_millis(99)));
+
+        let few = millis(&[10, 20, 30]);
+        assert_eq!(percentile(&few, 50), Some(Duration::from_millis(20)));
+        assert_eq!(percentile(&few, 99), Some(Duration::from_millis(30)));
+        assert_eq!(percentile(&[], 50), None);
+    }
+
+    #[test]
+    fn percentiles_ignore_unmeasured_results() {
+        let mut run = sample_run();
+        assert_eq!(run.latency(), None);
+
+        run.results = run
+            .results
+            .into_iter()
+            .zip(millis(&[5, 500, 0]))
+            .map(|(r, d)| r.with_duration(d))
+            .collect();
+        let latency = run.latency().unwrap();
+        assert_eq!(latency.p50, Duration::from_millis(5));
+        assert_eq!(latency.p99, Duration::from_millis(500));
+        assert_eq!(
+            json_report(&[run], true, &ReportMetadata::default())["modes"][0]["latency"]["p99_ms"],
+            500.0
+        );
+    }
+
+    #[test]
+    fn json_report_can_omit_skips_but_still_counts_them() {
+        let runs = [sample_run()];
+
+        let with = json_report(&runs, true, &ReportMetadata::default());
+        assert_eq!(with["modes"][0]["results"].as_array().unwrap().len(), 3);
+
+        let without = json_report(&runs, false, &ReportMetadata::default());
+        let results = without["modes"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r["status"] != "skip"));
+        assert_eq!(without["modes"][0]["summary"], with["modes"][0]["summary"]);
+        assert_eq!(without["modes"][0]["summary"]["skipped"], 1);
+    }
+
+    #[test]
+    fn baseline_delta_reports_fixed_and_new_failures() {
+        let mut baseline = json_report(&[sample_run()], true, &ReportMetadata::default());
+        // test_abs failed in the baseline and test_lt passed
+        baseline["modes"][0]["results"][0]["status"] = "fail".into();
+        baseline["modes"][0]["results"][1]["status"] = "pass".into();
+
+        let delta = BaselineDelta::between(&baseline, &[sample_run()]);
+        assert_eq!(delta.fixed, ["assumptions.test_abs"]);
+        assert_eq!(delta.regressed, ["assumptions.test_lt"]);
+        assert_eq!(
+            delta.summary(),
+            "Δ vs baseline: +1 fixed, -1 regressed (assumptions.test_lt)"
+        );
+
+        // Known failures aren't regressions
+        let baseline = json_report(&[sample_run()], true, &ReportMetadata::default());
+        let delta = BaselineDelta::between(&baseline, &[sample_run()]);
+        assert_eq!(delta, BaselineDelta::default());
+
+        // A failing test the baseline doesn't have is new
+        let mut other_mode = sample_run();
+        other_mode.mode = "Batch".to_string();
+        let delta = BaselineDelta::between(&baseline, &[other_mode]);
+        assert_eq!(delta.regressed, ["assumptions.test_lt"]);
+    }
+
+    #[test]
+    fn baseline_must_be_a_json_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        fs::write(&path, r#"{"results": []}"#).unwrap();
+        assert!(read_baseline(&path)
+            .unwrap_err()
+            .contains("not a forge-e2e JSON report"));
+        fs::write(&path, r#"{"modes": []}"#).unwrap();
+        assert!(read_baseline(&path).is_ok());
+    }
+
+    #[test]
+    fn metadata_is_embedded_in_reports() {
+        let metadata = ReportMetadata {
+            commit: Some("abc123".to_string()),
+            forge_version: Some("forge-demo 1.2.0 <dev>".to_string()),
+        };
+
+        let json = json_report(&[sample_run()], true, &metadata);
+        assert_eq!(json["metadata"]["forge_e2e_commit"], "abc123");
+        assert_eq!(
+            json["metadata"]["forge_demo_version"],
+            "forge-demo 1.2.0 <dev>"
+        );
+
+        let xml = junit_report(&[sample_run()], &metadata);
+        assert!(xml.contains(r#"<property name="forge_e2e_commit" value="abc123"/>"#));
+        assert!(xml.contains(
+            r#"<property name="forge_demo_version" value="forge-demo 1.2.0 &lt;dev&gt;"/>"#
+        ));
+    }
+
+    #[test]
+    fn manifest_lists_specs_versions_and_config() {
+        let spec_files = [SpecFile {
+            path: PathBuf::from("tests/e2e/math.yaml"),
+            tests: vec!["math.test_abs".to_string(), "math.test_sqrt".to_string()],
+            skips: vec!["math.test_rand".to_string()],
+            lines: BTreeMap::new(),
+        }];
+        let mut config = Config::default();
+        config.category_tolerances.insert("math".to_string(), 1e-6);
+        let metadata = ReportMetadata {
+            commit: Some("abc123".to_string()),
+            forge_version: None,
+        };
+
+        let manifest = manifest_json(&spec_files, "ssconvert 1.12.57", &config, &metadata);
+        assert_eq!(manifest["spec_files"][0]["path"], "tests/e2e/math.yaml");
+        assert_eq!(manifest["spec_files"][0]["tests"][1], "math.test_sqrt");
+        assert_eq!(manifest["spec_files"][0]["skips"][0], "math.test_rand");
+        assert_eq!(manifest["total_tests"], 2);
+        assert_eq!(manifest["total_skips"], 1);
+        assert_eq!(manifest["engine"]["version"], "ssconvert 1.12.57");
+        assert_eq!(manifest["metadata"]["forge_e2e_commit"], "abc123");
+        assert_eq!(manifest["config"]["category_tolerances"]["math"], 1e-6);
+        assert_eq!(manifest["config"]["today"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn metadata_degrades_without_binary() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let metadata = ReportMetadata::collect(&temp_dir.path().join("missing-forge"));
+        assert_eq!(metadata.commit, build_commit());
+        assert_eq!(metadata.forge_version, None);
+        let json = metadata.to_json();
+        assert_eq!(json["forge_demo_version"], serde_json::Value::Null);
+        assert_eq!(json["forge_e2e_version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn resolve_uses_output_dir_for_unset_targets() {
+        let dir = Path::new("artifacts");
+        let targets = ReportTargets::resolve(Some(PathBuf::from("custom.json")), None, Some(dir));
+        assert_eq!(targets.json, Some(PathBuf::from("custom.json")));
+        assert_eq!(targets.junit, Some(dir.join(JUNIT_REPORT_NAME)));
+
+        assert!(ReportTargets::resolve(None, None, None).is_empty());
+    }
+
+    #[test]
+    fn run_summary_sums_modes() {
+        let summary = RunSummary::of(&[sample_run(), sample_run()]);
+        assert_eq!(
+            summary,
+            RunSummary {
+                passed: 2,
+                failed: 2,
+                skipped: 2
+            }
+        );
+        assert_eq!(summary.pass_rate(), Some(50.0));
+        assert_eq!(RunSummary::default().pass_rate(), None);
+    }
+
+    #[test]
+    fn write_picks_badge_format_by_extension() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for (name, prefix) in [("badge.svg", "<svg"), ("badge.json", "{")] {
+            let path = temp_dir.path().join(name);
+            let targets = ReportTargets::default().with_badge(Some(path.clone()));
+            assert_eq!(targets.write(&[sample_run()]).unwrap(), vec![path.clone()]);
+            assert!(fs::read_to_string(&path).unwrap().starts_with(prefix));
+        }
+    }
+
+    #[test]
+    fn write_creates_directory_tree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let out = temp_dir.path().join("ci/reports");
+        let targets = ReportTargets::resolve(None, None, Some(&out));
+
+        let written = targets.write(&[sample_run()]).unwrap();
+
+        assert_eq!(
+            written,
+            vec![out.join("report.json"), out.join("junit.xml")]
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&written[0]).unwrap()).unwrap();
+        assert_eq!(json["modes"][0]["summary"]["failed"], 1);
+        assert_eq!(json["modes"][0]["results"][0]["status"], "pass");
+    }
+
+    #[test]
+    fn result_file_names_are_safe() {
+        assert_eq!(result_file_name("math.test_abs"), "math.test_abs");
+        assert_eq!(result_file_name("../etc/passwd"), "_._etc_passwd");
+        assert_eq!(result_file_name("a b\\c:\"d\""), "a_b_c__d_");
+        assert_eq!(result_file_name(""), "_");
+    }
+
+    #[test]
+    fn json_dir_writes_one_file_per_result() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("results");
+        let mut run = sample_run();
+        run.results.push(TestResult::Skip {
+            name: "a/b".to_string(),
+            id: None,
+            reason: "collides".to_string(),
+        });
+        run.results.push(TestResult::Skip {
+            name: "a:b".to_string(),
+            id: None,
+            reason: "collides".to_string(),
+        });
+        let targets = ReportTargets::default()
+            .with_skips(true)
+            .with_json_dir(Some(dir.clone()));
+
+        assert_eq!(targets.write(&[run]).unwrap(), vec![dir.clone()]);
+        let mut files: Vec<String> = fs::read_dir(dir.join("normal"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 5);
+        assert!(files.contains(&"a_b.json".to_string()));
+        assert!(files.contains(&"a_b-2.json".to_string()));
+
+        let json: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(dir.join("normal/assumptions.test_abs.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json["name"], "assumptions.test_abs");
+        assert_eq!(json["status"], "pass");
+        assert!(json.get("id").is_none());
+    }
+
+    #[test]
+    fn json_dir_names_files_by_id_when_present() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let run = ModeRun {
+            mode: "Perf".to_string(),
+            results: vec![TestResult::Skip {
+                name: "math.test_renamed".to_string(),
+                id: Some("abs-001".to_string()),
+                reason: "pending".to_string(),
+            }],
+            elapsed: Duration::ZERO,
+            threads: None,
+        };
+
+        write_result_files(temp_dir.path(), &[run], true).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.path().join("perf/abs-001.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json["id"], "abs-001");
+        assert_eq!(json["name"], "math.test_renamed");
+    }
+
+    #[test]
+    fn merge_combines_shards_of_the_same_mode() {
+        let mut first = sample_run();
+        let timed = first
+            .results
+            .remove(0)
+            .with_duration(Duration::from_millis(10));
+        first.results.insert(0, timed);
+        let mut second = sample_run();
+        second.elapsed = Duration::from_secs(3);
+        let timed = second
+            .results
+            .remove(1)
+            .with_duration(Duration::from_millis(30));
+        second.results.insert(1, timed);
+        let batch = ModeRun {
+            mode: "Batch".to_string(),
+            ..sample_run()
+        };
+        let metadata = ReportMetadata::default();
+
+        let merged = merge_json_reports(
+            &json_report(&[first], true, &metadata),
+            &json_report(&[second, batch], true, &metadata),
+        )
+        .unwrap();
+
+        let modes = merged["modes"].as_array().unwrap();
+        assert_eq!(modes.len(), 2);
+        assert_eq!(modes[0]["mode"], "Normal");
+        assert_eq!(modes[0]["results"].as_array().unwrap().len(), 6);
+        assert_eq!(modes[0]["summary"]["total"], 6);
+        assert_eq!(modes[0]["summary"]["failed"], 2);
+        assert_eq!(modes[0]["elapsed_secs"], 3.0);
+        assert_eq!(modes[0]["latency"]["p99_ms"], 30.0);
+        assert_eq!(modes[1]["mode"], "Batch");
+
+        assert!(merge_json_reports(&serde_json::json!({}), &merged).is_err());
+    }
+
+    #[test]
+    fn concurrent_merges_keep_every_shard() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("report.json");
+        let targets = ReportTargets::resolve(Some(path.clone()), None, None).with_merge_json(true);
+
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| targets.write(&[sample_run()]).unwrap());
+            }
+        });
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["modes"][0]["summary"]["total"], 24);
+        assert_eq!(json["modes"][0]["results"].as_array().unwrap().len(), 24);
+        // No lock or temporary files are left beside the report
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn written_reports_follow_the_umask() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("report.json");
+        let plain = temp_dir.path().join("plain.txt");
+        ReportTargets::resolve(Some(path.clone()), None, None)
+            .write(&[sample_run()])
+            .unwrap();
+        fs::write(&plain, "").unwrap();
+        let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), mode(&plain));
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn junit_report_counts_and_escapes() {
+        let xml = junit_report(&[sample_run()], &ReportMetadata::default());
+        assert!(xml.contains(
+            r#"<testsuite name="Normal" tests="3" failures="1" skipped="1" time="1.500">"#
+        ));
+        assert!(xml.contains(r#"<failure message="expected 1, got 0">formula:  =IF(1&lt;2,1,0)"#));
+        assert!(xml.contains(r#"<skipped message="Gnumeric &amp; Excel differ"/>"#));
+        assert!(xml.contains(r#"<testcase classname="Normal" name="assumptions.test_abs"/>"#));
+    }
+}
@@ -7,23 +7,85 @@
 //! 4. Use spreadsheet engine to recalculate and export to CSV
 //! 5. Compare results against expected values
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write;
 use std::fs;
-use std::io::{BufRead, BufReader};
+use std::io;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::time::Instant;
 
 use rayon::prelude::*;
 
-use crate::engine::SpreadsheetEngine;
+use crate::calculator::{Calculator, SubprocessCalculator};
+use crate::checkpoint::Checkpoint;
+use crate::config::{Config, TODAY_ENV};
+use crate::engine::{RecalcStrategy, SpreadsheetEngine};
+use crate::excel;
+use crate::jobs::JobLimit;
+use crate::profile::{Phase, Profile};
+use crate::selection::NameFilter;
+use crate::transport::{ForgeArg, LocalTransport, Transport};
 use crate::types::{
-    extract_skip_cases, extract_test_cases, SkipCase, TestCase, TestResult, TestSpec,
+    attach_source_lines, extract_skip_cases, extract_test_cases, extract_xlsx_expectations,
+    parse_number, source_line, spec_errors, InputFormat, SkipCase, TestCase, TestResult, TestSpec,
 };
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Test Runner
 // ─────────────────────────────────────────────────────────────────────────────
 
+/// Name of the scalar that holds the formula in generated test YAML.
+pub const RESULT_LABEL: &str = "test_result";
+
+/// Lines of each produced CSV shown in failures with `--show-csv-on-fail`.
+const CSV_EXCERPT_LINES: usize = 20;
+
+/// How close an unlabeled cell must be to an expected value for the
+/// heuristic fallback to take it as the result. The test's own tolerance
+/// is used when wider; the value found is then compared as usual.
+const HEURISTIC_TOLERANCE: f64 = 0.0001;
+
+/// Error for a test whose label a batch CSV has no value for.
+const MISSING_RESULT: &str = "Missing result in CSV output";
+
+/// Formula used by the pipeline self-test.
+const SELF_TEST_FORMULA: &str = "=1+1";
+/// Expected result of [`SELF_TEST_FORMULA`].
+const SELF_TEST_EXPECTED: f64 = 2.0;
+
+/// A result value located in converted CSV output.
+#[derive(Debug, Clone, Copy)]
+struct CsvMatch {
+    /// The parsed result value.
+    value: f64,
+    /// `true` if found by matching the expected value, not by label.
+    heuristic: bool,
+}
+
+/// A loaded spec file and the tests extracted from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecFile {
+    /// Path of the spec file.
+    pub path: PathBuf,
+    /// Names of the runnable tests it defines, sorted.
+    pub tests: Vec<String>,
+    /// Names of its skipped tests, sorted.
+    pub skips: Vec<String>,
+    /// Line each test and skip is defined on, where it could be found.
+    pub lines: BTreeMap<String, usize>,
+}
+
+impl SpecFile {
+    /// Returns the line the test `name` is defined on, if known.
+    pub fn line_of(&self, name: &str) -> Option<usize> {
+        self.lines.get(name).copied()
+    }
+}
+
 /// Test runner for E2E validation.
 ///
 /// Manages test case loading and execution against the forge-demo binary.
@@ -39,6 +101,22 @@ pub struct TestRunner {
     test_cases: Vec<TestCase>,
     /// All loaded skip cases.
     skip_cases: Vec<SkipCase>,
+    /// Loaded spec files, sorted by path.
+    spec_files: Vec<SpecFile>,
+    /// Execution options.
+    config: Config,
+    /// How forge-demo is invoked (locally by default).
+    transport: Arc<dyn Transport>,
+    /// Backend that evaluates formulas in perf mode.
+    calculator: Box<dyn Calculator>,
+    /// Where normal-mode results are recorded and resumed from, if anywhere.
+    checkpoint: Option<Checkpoint>,
+    /// Bounds concurrent forge-demo exports.
+    export_limit: JobLimit,
+    /// Bounds concurrent ssconvert conversions.
+    convert_limit: JobLimit,
+    /// Time spent per pipeline phase, when profiling (`--profile`).
+    profile: Option<Profile>,
 }
 
 impl TestRunner {
@@ -49,22 +127,161 @@ impl TestRunner {
         forge_binary: PathBuf,
         engine: SpreadsheetEngine,
         tests_dir: PathBuf,
+        input_format: InputFormat,
     ) -> anyhow::Result<Self> {
-        let (test_cases, skip_cases) = Self::load_test_cases(&tests_dir)?;
+        let (test_cases, skip_cases, spec_files) = Self::load_test_cases(&tests_dir, input_format)?;
 
         Ok(Self {
-            forge_binary,
-            engine,
             tests_dir,
             test_cases,
             skip_cases,
+            spec_files,
+            ..Self::without_specs(forge_binary, engine)
+        })
+    }
+
+    /// Creates a test runner with no test specs loaded.
+    ///
+    /// Used for pipeline checks such as [`Self::self_test`] that don't
+    /// depend on a tests directory.
+    pub fn without_specs(forge_binary: PathBuf, engine: SpreadsheetEngine) -> Self {
+        Self {
+            calculator: Box::new(SubprocessCalculator::new(forge_binary.clone())),
+            transport: Arc::new(LocalTransport),
+            forge_binary,
+            engine,
+            tests_dir: PathBuf::new(),
+            test_cases: Vec::new(),
+            skip_cases: Vec::new(),
+            spec_files: Vec::new(),
+            config: Config::default(),
+            checkpoint: None,
+            export_limit: JobLimit::default(),
+            convert_limit: JobLimit::default(),
+            profile: None,
+        }
+    }
+
+    /// Applies execution options to this runner.
+    #[must_use]
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.engine = self
+            .engine
+            .with_recalc(config.recalc != RecalcStrategy::Never);
+        self.export_limit = JobLimit::new(config.export_jobs);
+        self.convert_limit = JobLimit::new(config.convert_jobs);
+        self.config = config;
+        self
+    }
+
+    /// Runs forge-demo through `transport` instead of as a local subprocess.
+    ///
+    /// Also routes the `forge calculate` backend through it.
+    #[must_use]
+    pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        let transport: Arc<dyn Transport> = Arc::new(transport);
+        self.calculator = Box::new(SubprocessCalculator::with_transport(
+            self.forge_binary.clone(),
+            Arc::clone(&transport),
+        ));
+        self.transport = transport;
+        self
+    }
+
+    /// Keeps only the tests and skips `filter` selects, for every mode.
+    ///
+    /// Tests whose dependencies are filtered out are skipped when run.
+    #[must_use]
+    pub fn with_name_filter(mut self, filter: &NameFilter) -> Self {
+        self.test_cases.retain(|tc| filter.matches(&tc.name));
+        self.skip_cases.retain(|sc| filter.matches(&sc.name));
+        self
+    }
+
+    /// Records normal-mode results in `checkpoint` and reuses the ones it
+    /// already holds instead of running those tests again.
+    #[must_use]
+    pub fn with_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// Accumulates the time spent in each pipeline phase into `profile`.
+    #[must_use]
+    pub const fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Returns the phase timings, if profiling.
+    pub const fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Runs `f`, recording its time under `phase` when profiling.
+    fn timed<T>(&self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        match &self.profile {
+            Some(profile) => profile.time(phase, f),
+            None => f(),
+        }
+    }
+
+    /// Returns whether the heuristic fallback may take `value` as the
+    /// result of `test_case` (see [`HEURISTIC_TOLERANCE`]).
+    fn heuristic_accepts(&self, test_case: &TestCase, value: f64) -> bool {
+        let tolerance = self
+            .config
+            .tolerance_for(test_case)
+            .max(HEURISTIC_TOLERANCE);
+        test_case.accepts(value, tolerance)
+    }
+
+    /// Evaluates `formula` with the perf-mode [`Calculator`].
+    fn calculate(&self, formula: &str) -> Result<f64, String> {
+        self.timed(Phase::Calculate, || {
+            self.calculator.calculate(&self.config, formula)
+        })
+    }
+
+    /// Evaluates every formula in `formulas` with the perf-mode
+    /// [`Calculator`] at once, returning their values in order.
+    pub fn calculate_all(&self, formulas: &[&str]) -> Vec<Result<f64, String>> {
+        self.timed(Phase::Calculate, || {
+            self.calculator.calculate_all(&self.config, formulas)
         })
     }
 
+    /// Evaluates every formula in `formulas` separately (in parallel) with
+    /// the perf-mode [`Calculator`], so one failure only costs its own
+    /// value. Returns the values in order.
+    pub fn calculate_each(&self, formulas: &[&str]) -> Vec<Result<f64, String>> {
+        formulas
+            .par_iter()
+            .map(|formula| self.calculate(formula))
+            .collect()
+    }
+
+    /// Replaces the perf-mode calculation backend (default: `forge calculate`).
+    #[cfg(test)]
+    #[must_use]
+    pub fn with_calculator(mut self, calculator: impl Calculator + 'static) -> Self {
+        self.calculator = Box::new(calculator);
+        self
+    }
+
     /// Loads all test cases from the tests directory.
-    fn load_test_cases(tests_dir: &Path) -> anyhow::Result<(Vec<TestCase>, Vec<SkipCase>)> {
+    ///
+    /// Spec files are parsed as YAML or JSON by extension; `input_format`
+    /// restricts loading to one of them. JSON files without
+    /// `_forge_version` are other tools' files and are skipped. Also
+    /// returns which tests came from which file, for the run manifest.
+    fn load_test_cases(
+        tests_dir: &Path,
+        input_format: InputFormat,
+    ) -> anyhow::Result<(Vec<TestCase>, Vec<SkipCase>, Vec<SpecFile>)> {
         let mut all_cases = Vec::new();
         let mut all_skips = Vec::new();
+        let mut spec_files = Vec::new();
 
         if !tests_dir.exists() {
             anyhow::bail!("Tests directory does not exist: {}", tests_dir.display());
@@ -74,14 +291,77 @@ impl TestRunner {
             let entry = entry?;
             let path = entry.path();
 
-            if path.extension().is_some_and(|e| e == "yaml") {
+            if let Some(format) = input_format.format_of(&path) {
                 let content = fs::read_to_string(&path)?;
-                match serde_yaml_ng::from_str::<TestSpec>(&content) {
+                if format == InputFormat::Json && is_foreign_json(&content) {
+                    continue;
+                }
+                match TestSpec::parse(&content, format) {
                     Ok(spec) => {
-                        let cases = extract_test_cases(&spec);
-                        let skips = extract_skip_cases(&spec);
+                        let errors = spec_errors(&spec);
+                        if !errors.is_empty() {
+                            anyhow::bail!(
+                                "Invalid spec {}:\n  {}",
+                                path.display(),
+                                errors.join("\n  ")
+                            );
+                        }
+                        let mut cases = extract_test_cases(&spec);
+                        let mut skips = extract_skip_cases(&spec);
+                        attach_source_lines(&content, &mut cases, &mut skips);
+                        let first_case = all_cases.len();
+                        let mut file = SpecFile {
+                            path: path.clone(),
+                            tests: Vec::new(),
+                            skips: skips.iter().map(|sc| sc.name.clone()).collect(),
+                            lines: skips
+                                .iter()
+                                .filter_map(|sc| Some((sc.name.clone(), sc.line?)))
+                                .collect(),
+                        };
                         all_cases.extend(cases);
                         all_skips.extend(skips);
+
+                        // Resolve expected values from reference spreadsheets
+                        let base_dir = path.parent().unwrap_or(tests_dir);
+                        for oracle in extract_xlsx_expectations(&spec) {
+                            match excel::read_cell_ref(base_dir, &oracle.cell_ref) {
+                                Ok(expected) => all_cases.push(TestCase {
+                                    line: source_line(&content, &oracle.name),
+                                    name: oracle.name,
+                                    formula: oracle.formula,
+                                    expected,
+                                    depends_on: oracle.depends_on,
+                                    scale: oracle.scale,
+                                    tolerance: oracle.tolerance,
+                                    tolerance_ulps: oracle.tolerance_ulps,
+                                    base: oracle.base,
+                                    result_cell: oracle.result_cell,
+                                    id: oracle.id,
+                                    ..TestCase::default()
+                                }),
+                                Err(e) => {
+                                    eprintln!(
+                                        "Warning: {} in {}: {e}",
+                                        oracle.name,
+                                        path.display()
+                                    );
+                                }
+                            }
+                        }
+
+                        file.tests = all_cases[first_case..]
+                            .iter()
+                            .map(|tc| tc.name.clone())
+                            .collect();
+                        file.tests.sort();
+                        file.skips.sort();
+                        file.lines.extend(
+                            all_cases[first_case..]
+                                .iter()
+                                .filter_map(|tc| Some((tc.name.clone(), tc.line?))),
+                        );
+                        spec_files.push(file);
                     }
                     Err(e) => {
                         eprintln!("Warning: Failed to parse {}: {e}", path.display());
@@ -90,7 +370,10 @@ impl TestRunner {
             }
         }
 
-        Ok((all_cases, all_skips))
+        check_unique_ids(&all_cases, &all_skips).map_err(anyhow::Error::msg)?;
+        let all_cases = order_by_dependencies(all_cases, &all_skips).map_err(anyhow::Error::msg)?;
+        spec_files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok((all_cases, all_skips, spec_files))
     }
 
     /// Returns the total number of test cases (including skips).
@@ -103,28 +386,241 @@ impl TestRunner {
         &self.test_cases
     }
 
-    /// Returns all skip cases.
-    pub fn skip_cases(&self) -> &[SkipCase] {
-        &self.skip_cases
+    /// Returns the loaded spec files, sorted by path.
+    pub fn spec_files(&self) -> &[SpecFile] {
+        &self.spec_files
+    }
+
+    /// Returns the execution options.
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns the spreadsheet engine's version string.
+    pub fn engine_version(&self) -> &str {
+        self.engine.version()
+    }
+
+    /// Returns the path to the forge-demo binary under test.
+    pub fn forge_binary(&self) -> &Path {
+        &self.forge_binary
+    }
+
+    /// Returns the section name used for generated test scalars.
+    pub fn section(&self) -> &str {
+        &self.config.section
+    }
+
+    /// Verifies the whole export → ssconvert → CSV pipeline works.
+    ///
+    /// Runs a trivial known formula through [`Self::run_test`], so a pass
+    /// means both forge-demo and the spreadsheet engine are functional,
+    /// independent of the spec suite.
+    pub fn self_test(&self) -> TestResult {
+        self.run_test(&TestCase {
+            name: "selftest.test_one_plus_one".to_string(),
+            formula: SELF_TEST_FORMULA.to_string(),
+            expected: SELF_TEST_EXPECTED,
+            ..TestCase::default()
+        })
     }
 
     /// Runs all tests and returns results (including skips).
+    ///
+    /// Tests whose dependencies did not pass are skipped without running.
     pub fn run_all(&self) -> Vec<TestResult> {
+        let (tx, rx) = mpsc::channel();
+        self.stream_all(&tx);
+        drop(tx);
+        rx.into_iter().collect()
+    }
+
+    /// Runs all tests like [`Self::run_all`], sending each result as soon
+    /// as it is available.
+    ///
+    /// Returns early once the receiving end has been dropped.
+    pub fn stream_all(&self, tx: &Sender<TestResult>) {
+        self.stream_tests(tx, self.checkpoint.as_ref());
+    }
+
+    /// Body of [`Self::stream_all`], resuming from `checkpoint` if given.
+    fn stream_tests(&self, tx: &Sender<TestResult>, checkpoint: Option<&Checkpoint>) {
         // Skip results first, then run actual tests
-        self.skip_cases
-            .iter()
-            .map(|sc| TestResult::Skip {
+        for sc in &self.skip_cases {
+            let skip = TestResult::Skip {
                 name: sc.name.clone(),
+                id: sc.id.clone(),
                 reason: sc.reason.clone(),
+            };
+            if tx.send(skip).is_err() {
+                return;
+            }
+        }
+        let mut gate = DependencyGate::default();
+        // The outputs of one multi-output test share an export
+        for test in self
+            .test_cases
+            .chunk_by(|a, b| a.output_of.is_some() && a.output_of == b.output_of)
+        {
+            let known: Vec<Option<TestResult>> = test
+                .iter()
+                .map(|tc| {
+                    gate.check(tc)
+                        .or_else(|| checkpoint.and_then(|cp| cp.completed(tc)))
+                })
+                .collect();
+            let pending: Vec<TestCase> = test
+                .iter()
+                .zip(&known)
+                .filter(|(_, known)| known.is_none())
+                .map(|(tc, _)| tc.clone())
+                .collect();
+            let mut fresh = self.run_cases(&pending).into_iter();
+            for known in known {
+                let result = known.or_else(|| {
+                    let result = fresh.next()?;
+                    if let Some(Err(e)) = checkpoint.map(|cp| cp.record(&result)) {
+                        eprintln!("Warning: {e}");
+                    }
+                    Some(result)
+                });
+                let Some(result) = result else {
+                    break;
+                };
+                gate.record(&result);
+                if tx.send(result).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Runs `test_cases`: a single test on its own, and the outputs of a
+    /// multi-output test through one export and conversion.
+    fn run_cases(&self, test_cases: &[TestCase]) -> Vec<TestResult> {
+        match test_cases {
+            [] => Vec::new(),
+            [test_case] => vec![self.run_test(test_case)],
+            _ => self.run_outputs(test_cases),
+        }
+    }
+
+    /// Runs the outputs of one multi-output test through a shared export,
+    /// with the checks [`Self::run_test`] applies to a single test.
+    ///
+    /// `--recalc compare` and `--compare-formulas` read a test's own
+    /// export, so with either set every output runs alone. Otherwise each
+    /// output is charged an equal share of the export's time, and one the
+    /// shared CSV has no value for runs alone, where heuristic matching can
+    /// still find it.
+    fn run_outputs(&self, outputs: &[TestCase]) -> Vec<TestResult> {
+        if self.config.recalc == RecalcStrategy::Compare || self.config.compare_formulas {
+            return outputs.iter().map(|tc| self.run_test(tc)).collect();
+        }
+        let start = Instant::now();
+        let results = self.run_batch_chunk(outputs);
+        let share = start.elapsed() / u32::try_from(outputs.len()).unwrap_or(u32::MAX);
+        outputs
+            .iter()
+            .zip(results)
+            .map(|(tc, result)| match &result {
+                TestResult::Fail {
+                    actual: None,
+                    error: Some(error),
+                    ..
+                } if self.config.heuristic_match && error == MISSING_RESULT => self.run_test(tc),
+                _ if self.config.annotate_forge => {
+                    self.annotate_forge(tc, result.with_duration(share))
+                }
+                _ => result.with_duration(share),
+            })
+            .collect()
+    }
+
+    /// Runs every skipped test that has a formula and expected value,
+    /// returning the skips whose test now passes.
+    ///
+    /// For `--warn-unused-skip`: a passing skip is probably stale. The
+    /// results are informational and never count towards a run.
+    pub fn passing_skips(&self) -> Vec<&SkipCase> {
+        self.skip_cases
+            .iter()
+            .filter(|sc| {
+                sc.case
+                    .as_ref()
+                    .is_some_and(|tc| self.run_test(tc).is_pass())
+            })
+            .collect()
+    }
+
+    /// Evaluates every test with the perf-mode [`Calculator`] and returns
+    /// those whose declared expected value it disagrees with, along with the
+    /// calculated (scaled) value.
+    ///
+    /// For `--verify-expected`: flags likely typos in spec `expected` values
+    /// before Gnumeric runs. Tests forge can't calculate are left for the
+    /// real run to report.
+    pub fn disputed_expectations(&self) -> Vec<(&TestCase, f64)> {
+        self.test_cases
+            .par_iter()
+            .filter(|tc| !tc.expect_export_error)
+            .filter_map(|tc| {
+                let actual = self.calculate(&tc.formula).ok()?;
+                (!tc.accepts(actual, self.config.tolerance_for(tc)))
+                    .then(|| (tc, tc.scaled(actual)))
+            })
+            .collect()
+    }
+
+    /// Runs the suite once in every mode and discards the results.
+    ///
+    /// For `--warmup`: fills OS file caches and starts Gnumeric's plugins
+    /// once, so the measured run that follows isn't charged for cold starts.
+    ///
+    /// The checkpoint is neither read nor written, and the pass's phase
+    /// timings are dropped from the profile.
+    pub fn warm_up(&self) {
+        let (tx, rx) = mpsc::channel();
+        self.stream_tests(&tx, None);
+        drop(tx);
+        let _ = rx.into_iter().count();
+        let _ = self.run_perf();
+        let _ = self.run_batch();
+        if let Some(profile) = &self.profile {
+            profile.reset_run();
+        }
+    }
+
+    /// Replaces results of tests whose dependencies did not pass with skips.
+    ///
+    /// For modes that run tests concurrently; `results` must list test
+    /// cases in [`Self::test_cases`] order, which runs dependencies first.
+    fn skip_blocked(&self, results: Vec<TestResult>) -> Vec<TestResult> {
+        let cases: HashMap<&str, &TestCase> = self
+            .test_cases
+            .iter()
+            .map(|tc| (tc.name.as_str(), tc))
+            .collect();
+        let mut gate = DependencyGate::default();
+        results
+            .into_iter()
+            .map(|result| {
+                let result = cases
+                    .get(result.name())
+                    .and_then(|tc| gate.check(tc))
+                    .unwrap_or(result);
+                gate.record(&result);
+                result
             })
-            .chain(self.test_cases.iter().map(|tc| self.run_test(tc)))
             .collect()
     }
 
     /// Runs all tests in batch mode (single XLSX, faster).
     ///
     /// Creates one YAML with all formulas, exports once, validates with Gnumeric once.
-    #[allow(clippy::too_many_lines)]
+    /// With a configured batch size, test cases are split into batches of at
+    /// most that many tests, each exported and converted separately (in
+    /// parallel) to bound the XLSX size and ssconvert memory use.
     pub fn run_batch(&self) -> Vec<TestResult> {
         // Skip results first
         let mut results: Vec<TestResult> = self
@@ -132,36 +628,75 @@ impl TestRunner {
             .iter()
             .map(|sc| TestResult::Skip {
                 name: sc.name.clone(),
+                id: sc.id.clone(),
                 reason: sc.reason.clone(),
             })
             .collect();
 
-        if self.test_cases.is_empty() {
-            return results;
+        let batch_size = self
+            .config
+            .batch_size
+            .map_or_else(|| self.test_cases.len().max(1), NonZeroUsize::get);
+        let batches: Vec<Vec<TestResult>> = self
+            .test_cases
+            .par_chunks(batch_size)
+            .map(|batch| self.run_batch_chunk(batch))
+            .collect();
+
+        results.extend(batches.into_iter().flatten());
+        self.skip_blocked(results)
+    }
+
+    /// Runs one batch of test cases through a single export and conversion.
+    #[allow(clippy::too_many_lines)]
+    fn run_batch_chunk(&self, test_cases: &[TestCase]) -> Vec<TestResult> {
+        // A rejected formula would fail the whole export, so those tests
+        // run alone and the rest still share one
+        if test_cases.iter().any(|tc| tc.expect_export_error) {
+            let shared: Vec<TestCase> = test_cases
+                .iter()
+                .filter(|tc| !tc.expect_export_error)
+                .cloned()
+                .collect();
+            let mut shared_results = if shared.is_empty() {
+                Vec::new()
+            } else {
+                self.run_batch_chunk(&shared)
+            }
+            .into_iter();
+            return test_cases
+                .iter()
+                .filter_map(|tc| {
+                    if tc.expect_export_error {
+                        Some(self.validate_test(tc))
+                    } else {
+                        shared_results.next()
+                    }
+                })
+                .collect();
         }
 
+        let mut results = Vec::with_capacity(test_cases.len());
+
         // Create a single YAML with all test formulas
-        let mut yaml_content = String::from("_forge_version: \"1.0.0\"\nassumptions:\n");
-        for (i, tc) in self.test_cases.iter().enumerate() {
-            let escaped_formula = tc.formula.replace('"', "\\\"");
-            let _ = write!(
-                yaml_content,
-                "  test_{i}:\n    value: null\n    formula: \"{escaped_formula}\"\n"
-            );
-        }
+        let names: Vec<String> = (0..test_cases.len()).map(|i| format!("test_{i}")).collect();
+        let cases: Vec<(&str, &str)> = names
+            .iter()
+            .zip(test_cases)
+            .map(|(name, tc)| (name.as_str(), tc.formula.as_str()))
+            .collect();
+        let yaml_content = build_test_yaml(&self.config.section, &cases);
 
         let temp_dir = match tempfile::tempdir() {
             Ok(d) => d,
             Err(e) => {
                 // Return all as failed
-                for tc in &self.test_cases {
-                    results.push(TestResult::Fail {
-                        name: tc.name.clone(),
-                        formula: tc.formula.clone(),
-                        expected: tc.expected,
-                        actual: None,
-                        error: Some(format!("Failed to create temp dir: {e}")),
-                    });
+                for tc in test_cases {
+                    results.push(TestResult::fail(
+                        tc,
+                        None,
+                        Some(format!("Failed to create temp dir: {e}")),
+                    ));
                 }
                 return results;
             }
@@ -171,35 +706,26 @@ impl TestRunner {
         let xlsx_path = temp_dir.path().join("batch.xlsx");
 
         if let Err(e) = fs::write(&yaml_path, &yaml_content) {
-            for tc in &self.test_cases {
-                results.push(TestResult::Fail {
-                    name: tc.name.clone(),
-                    formula: tc.formula.clone(),
-                    expected: tc.expected,
-                    actual: None,
-                    error: Some(format!("Failed to write YAML: {e}")),
-                });
+            for tc in test_cases {
+                results.push(TestResult::fail(
+                    tc,
+                    None,
+                    Some(format!("Failed to write YAML: {e}")),
+                ));
             }
             return results;
         }
 
         // Run forge-demo export once
-        let output = match Command::new(&self.forge_binary)
-            .arg("export")
-            .arg(&yaml_path)
-            .arg(&xlsx_path)
-            .output()
-        {
+        let output = match self.export(&yaml_path, &xlsx_path) {
             Ok(o) => o,
             Err(e) => {
-                for tc in &self.test_cases {
-                    results.push(TestResult::Fail {
-                        name: tc.name.clone(),
-                        formula: tc.formula.clone(),
-                        expected: tc.expected,
-                        actual: None,
-                        error: Some(format!("Failed to run forge-demo: {e}")),
-                    });
+                for tc in test_cases {
+                    results.push(TestResult::fail(
+                        tc,
+                        None,
+                        Some(format!("Failed to run forge-demo: {e}")),
+                    ));
                 }
                 return results;
             }
@@ -207,74 +733,62 @@ impl TestRunner {
 
         if !output.status.success() {
             let err = String::from_utf8_lossy(&output.stderr);
-            for tc in &self.test_cases {
-                results.push(TestResult::Fail {
-                    name: tc.name.clone(),
-                    formula: tc.formula.clone(),
-                    expected: tc.expected,
-                    actual: None,
-                    error: Some(format!("forge-demo export failed: {err}")),
-                });
+            for tc in test_cases {
+                results.push(TestResult::fail(
+                    tc,
+                    None,
+                    Some(format!("forge-demo export failed: {err}")),
+                ));
+            }
+            return results;
+        }
+
+        if let Err(e) = Self::check_export_output(&xlsx_path) {
+            for tc in test_cases {
+                results.push(TestResult::fail(tc, None, Some(e.clone())));
             }
             return results;
         }
 
         // Convert XLSX to CSV using Gnumeric once
-        let csv_path = match self.engine.xlsx_to_csv(&xlsx_path, temp_dir.path()) {
+        let converted = self.convert_limit.run(|| {
+            self.timed(Phase::Convert, || {
+                self.engine.xlsx_to_csv(&xlsx_path, temp_dir.path())
+            })
+        });
+        let csv_path = match converted {
             Ok(p) => p,
             Err(e) => {
-                for tc in &self.test_cases {
-                    results.push(TestResult::Fail {
-                        name: tc.name.clone(),
-                        formula: tc.formula.clone(),
-                        expected: tc.expected,
-                        actual: None,
-                        error: Some(format!("CSV conversion failed: {e}")),
-                    });
+                for tc in test_cases {
+                    results.push(TestResult::fail(
+                        tc,
+                        None,
+                        Some(format!("CSV conversion failed: {e}")),
+                    ));
                 }
                 return results;
             }
         };
 
         // Parse CSV and match results to test cases
-        let csv_results = Self::parse_batch_csv(&csv_path, self.test_cases.len());
-        for (i, tc) in self.test_cases.iter().enumerate() {
+        let csv_results = self.timed(Phase::Parse, || {
+            Self::parse_batch_csv(
+                &csv_path,
+                &self.config.section,
+                self.config.label_columns,
+                test_cases,
+            )
+        });
+        for (i, tc) in test_cases.iter().enumerate() {
             match csv_results.get(i) {
                 Some(Ok(actual)) => {
-                    if (*actual - tc.expected).abs() < f64::EPSILON {
-                        results.push(TestResult::Pass {
-                            name: tc.name.clone(),
-                            formula: tc.formula.clone(),
-                            expected: tc.expected,
-                            actual: *actual,
-                        });
-                    } else {
-                        results.push(TestResult::Fail {
-                            name: tc.name.clone(),
-                            formula: tc.formula.clone(),
-                            expected: tc.expected,
-                            actual: Some(*actual),
-                            error: None,
-                        });
-                    }
+                    results.push(self.compare(tc, *actual, false));
                 }
                 Some(Err(e)) => {
-                    results.push(TestResult::Fail {
-                        name: tc.name.clone(),
-                        formula: tc.formula.clone(),
-                        expected: tc.expected,
-                        actual: None,
-                        error: Some(e.clone()),
-                    });
+                    results.push(TestResult::fail(tc, None, Some(e.clone())));
                 }
                 None => {
-                    results.push(TestResult::Fail {
-                        name: tc.name.clone(),
-                        formula: tc.formula.clone(),
-                        expected: tc.expected,
-                        actual: None,
-                        error: Some("Missing result in CSV".to_string()),
-                    });
+                    results.push(TestResult::fail(tc, None, Some(MISSING_RESULT.to_string())));
                 }
             }
         }
@@ -283,13 +797,24 @@ impl TestRunner {
     }
 
     /// Parses batch CSV output to extract results for each test.
-    fn parse_batch_csv(csv_path: &Path, count: usize) -> Vec<Result<f64, String>> {
+    ///
+    /// A label may appear more than once (e.g. echoed in a header row); only
+    /// occurrences with a value the test can parse count (numeric, or an
+    /// integer in its `base`). If those disagree, the first wins and a
+    /// warning is printed.
+    fn parse_batch_csv(
+        csv_path: &Path,
+        section: &str,
+        label_columns: Option<usize>,
+        test_cases: &[TestCase],
+    ) -> Vec<Result<f64, String>> {
+        let count = test_cases.len();
+        let qualified_prefix = format!("{section}.test_");
         // Initialize results array with errors - will be filled by index
-        let mut results: Vec<Result<f64, String>> =
-            vec![Err("Missing result in CSV output".to_string()); count];
+        let mut results: Vec<Result<f64, String>> = vec![Err(MISSING_RESULT.to_string()); count];
 
-        let file = match fs::File::open(csv_path) {
-            Ok(f) => f,
+        let content = match read_csv_text(csv_path) {
+            Ok((content, _)) => content,
             Err(e) => {
                 for r in &mut results {
                     *r = Err(format!("Failed to open CSV: {e}"));
@@ -298,26 +823,43 @@ impl TestRunner {
             }
         };
 
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let Ok(line) = line else { continue };
-            let cells: Vec<&str> = line
-                .split(',')
-                .map(|s| s.trim_matches('"').trim())
-                .collect();
-
-            // Look for test_N labels and extract index
-            // Format: "assumptions.test_N" or "test_N" in first column, value in second
-            if cells.len() >= 2 {
-                let label = cells[0];
+        let rows: Vec<Vec<&str>> = content
+            .lines()
+            .map(|line| {
+                line.split(',')
+                    .map(|s| s.trim_matches('"').trim())
+                    .collect()
+            })
+            .collect();
+        // Results can be text (hex, durations), so a column with no numbers
+        // may hold values: only a leading index column is detected here
+        let label_columns =
+            label_columns.unwrap_or_else(|| 1 + usize::from(has_index_column(&rows)));
+
+        // Look for test_N labels and extract index
+        // Format: "<section>.test_N" or "test_N" in a label column, value in the first column after them
+        for cells in &rows {
+            let Some(raw) = cells.get(label_columns) else {
+                continue;
+            };
+            for &label in &cells[..label_columns] {
                 if let Some(idx_str) = label
-                    .strip_prefix("assumptions.test_")
+                    .strip_prefix(qualified_prefix.as_str())
                     .or_else(|| label.strip_prefix("test_"))
                 {
                     if let Ok(idx) = idx_str.parse::<usize>() {
                         if idx < count {
-                            if let Ok(value) = cells[1].replace(',', "").parse::<f64>() {
-                                results[idx] = Ok(value);
+                            if let Some(value) = test_cases[idx].parse_actual(raw) {
+                                match results[idx] {
+                                    Ok(first) if first.to_bits() != value.to_bits() => {
+                                        eprintln!(
+                                            "Warning: conflicting values for {label} in {}: {first} and {value}; using {first}",
+                                            csv_path.display()
+                                        );
+                                    }
+                                    Ok(_) => {}
+                                    Err(_) => results[idx] = Ok(value),
+                                }
                             }
                         }
                     }
@@ -330,360 +872,2533 @@ impl TestRunner {
 
     /// Runs a perf test using forge's calculation engine (no Gnumeric).
     ///
-    /// Tests formula calculation directly via `forge calculate`.
-    /// Compares calculated value against expected value.
+    /// Tests formula calculation through the runner's [`Calculator`]
+    /// (`forge calculate` by default). Compares calculated value against expected value. The result
+    /// records how long the test took.
     pub fn run_perf_test(&self, test_case: &TestCase) -> TestResult {
-        let escaped_formula = test_case.formula.replace('"', "\\\"");
-        let yaml_content = format!(
-            r#"_forge_version: "1.0.0"
-assumptions:
-  test_result:
-    value: null
-    formula: "{escaped_formula}"
-"#
-        );
+        let start = Instant::now();
+        self.calculate_test(test_case)
+            .with_duration(start.elapsed())
+    }
+
+    /// Untimed body of [`Self::run_perf_test`].
+    fn calculate_test(&self, test_case: &TestCase) -> TestResult {
+        if test_case.expect_export_error {
+            let rejected = self.timed(Phase::Calculate, || {
+                self.calculator.rejects(&self.config, &test_case.formula)
+            });
+            return match rejected {
+                Ok(true) => TestResult::pass(test_case, f64::NAN, false),
+                Ok(false) => TestResult::fail(
+                    test_case,
+                    None,
+                    Some("forge calculate accepted a formula that should be rejected".to_string()),
+                ),
+                Err(e) => TestResult::fail(test_case, None, Some(e)),
+            };
+        }
+        match self.calculate(&test_case.formula) {
+            Ok(actual) => self.compare(test_case, actual, false),
+            Err(e) => TestResult::fail(test_case, None, Some(e)),
+        }
+    }
+
+    /// Runs all perf tests, with [`Self::run_perf_batch`] if
+    /// [`Config::perf_batch`] is set and [`Self::run_perf_parallel`]
+    /// otherwise.
+    pub fn run_perf(&self) -> Vec<TestResult> {
+        if self.config.perf_batch {
+            self.run_perf_batch()
+        } else {
+            self.run_perf_parallel()
+        }
+    }
+
+    /// Runs all perf tests through a single [`Calculator::calculate_all`]
+    /// call, so forge-demo is spawned once rather than once per test.
+    ///
+    /// Tests expecting their formula to be rejected still run alone, since
+    /// one rejected formula would fail the whole call. Returns results in
+    /// the same order as test cases.
+    pub fn run_perf_batch(&self) -> Vec<TestResult> {
+        let mut results: Vec<TestResult> = self
+            .skip_cases
+            .iter()
+            .map(|sc| TestResult::Skip {
+                name: sc.name.clone(),
+                id: sc.id.clone(),
+                reason: sc.reason.clone(),
+            })
+            .collect();
+
+        let formulas: Vec<&str> = self
+            .test_cases
+            .iter()
+            .filter(|tc| !tc.expect_export_error)
+            .map(|tc| tc.formula.as_str())
+            .collect();
+        let mut values = self.calculate_all(&formulas).into_iter();
+        results.extend(self.test_cases.iter().map(|tc| {
+            if tc.expect_export_error {
+                return self.calculate_test(tc);
+            }
+            match values.next() {
+                Some(Ok(actual)) => self.compare(tc, actual, false),
+                Some(Err(e)) => TestResult::fail(tc, None, Some(e)),
+                None => TestResult::fail(
+                    tc,
+                    None,
+                    Some("Missing result in forge calculate output".to_string()),
+                ),
+            }
+        }));
+        self.skip_blocked(results)
+    }
+
+    /// Runs all perf tests in parallel using rayon.
+    ///
+    /// Tests formula calculation via `forge calculate` concurrently, on a
+    /// pool of [`Config::perf_threads`] threads when set. Returns results
+    /// in the same order as test cases.
+    pub fn run_perf_parallel(&self) -> Vec<TestResult> {
+        // Skip results first (not parallelized - usually just one)
+        let mut results: Vec<TestResult> = self
+            .skip_cases
+            .iter()
+            .map(|sc| TestResult::Skip {
+                name: sc.name.clone(),
+                id: sc.id.clone(),
+                reason: sc.reason.clone(),
+            })
+            .collect();
+
+        // Run all test cases in parallel
+        let run = || -> Vec<TestResult> {
+            self.test_cases
+                .par_iter()
+                .map(|tc| self.run_perf_test(tc))
+                .collect()
+        };
+        let parallel_results = self.config.perf_threads.map_or_else(run, |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads.get())
+                .build()
+                .map_or_else(
+                    |e| {
+                        let error = format!("Failed to start {threads} perf thread(s): {e}");
+                        self.test_cases
+                            .iter()
+                            .map(|tc| TestResult::fail(tc, None, Some(error.clone())))
+                            .collect()
+                    },
+                    |pool| pool.install(run),
+                )
+        });
+
+        results.extend(parallel_results);
+        self.skip_blocked(results)
+    }
+
+    /// Returns the number of threads [`Self::run_perf_parallel`] runs on.
+    pub fn perf_threads(&self) -> usize {
+        self.config
+            .perf_threads
+            .map_or_else(rayon::current_num_threads, NonZeroUsize::get)
+    }
+
+    /// Runs a single test case.
+    ///
+    /// Creates a temporary YAML file with the formula, runs forge-demo export,
+    /// converts to CSV using the spreadsheet engine, and compares results.
+    /// The result records how long the test took.
+    pub fn run_test(&self, test_case: &TestCase) -> TestResult {
+        let start = Instant::now();
+        let result = self.validate_test(test_case).with_duration(start.elapsed());
+        if self.config.annotate_forge {
+            self.annotate_forge(test_case, result)
+        } else {
+            result
+        }
+    }
+
+    /// Records `forge calculate`'s value on a pass it disagrees with.
+    ///
+    /// For `--annotate-forge`: surfaces forge bugs the tested expected
+    /// value happens not to catch. Failures, and formulas forge can't
+    /// calculate, are left as they are; perf mode reports those.
+    fn annotate_forge(&self, test_case: &TestCase, result: TestResult) -> TestResult {
+        let TestResult::Pass { actual, .. } = result else {
+            return result;
+        };
+        if test_case.expect_export_error {
+            return result;
+        }
+        let Ok(forge) = self.calculate(&test_case.formula) else {
+            return result;
+        };
+        let forge = test_case.scaled(forge);
+        if test_case.values_agree(forge, actual, self.config.tolerance_for(test_case)) {
+            result
+        } else {
+            result.with_forge_value(forge)
+        }
+    }
+
+    /// Untimed body of [`Self::run_test`].
+    #[allow(clippy::too_many_lines)]
+    fn validate_test(&self, test_case: &TestCase) -> TestResult {
+        // Create a minimal YAML with just this test
+        let yaml_content = test_yaml(&self.config.section, &test_case.formula);
 
         let temp_dir = match tempfile::tempdir() {
             Ok(d) => d,
             Err(e) => {
-                return TestResult::Fail {
-                    name: test_case.name.clone(),
-                    formula: test_case.formula.clone(),
-                    expected: test_case.expected,
-                    actual: None,
-                    error: Some(format!("Failed to create temp dir: {e}")),
-                };
+                return TestResult::fail(
+                    test_case,
+                    None,
+                    Some(format!("Failed to create temp dir: {e}")),
+                );
             }
         };
 
         let yaml_path = temp_dir.path().join("test.yaml");
+        let xlsx_path = temp_dir.path().join("test.xlsx");
 
+        // Write YAML
         if let Err(e) = fs::write(&yaml_path, &yaml_content) {
-            return TestResult::Fail {
-                name: test_case.name.clone(),
-                formula: test_case.formula.clone(),
-                expected: test_case.expected,
-                actual: None,
-                error: Some(format!("Failed to write YAML: {e}")),
-            };
+            return TestResult::fail(test_case, None, Some(format!("Failed to write YAML: {e}")));
         }
 
-        // Use `forge calculate --dry-run` to test calculation engine
-        let output = match Command::new(&self.forge_binary)
-            .arg("calculate")
-            .arg("--dry-run")
-            .arg(&yaml_path)
-            .output()
-        {
+        // Run forge-demo export
+        let output = match self.export(&yaml_path, &xlsx_path) {
             Ok(o) => o,
             Err(e) => {
-                return TestResult::Fail {
-                    name: test_case.name.clone(),
-                    formula: test_case.formula.clone(),
-                    expected: test_case.expected,
-                    actual: None,
-                    error: Some(format!("Failed to run forge calculate: {e}")),
-                };
+                return TestResult::fail(
+                    test_case,
+                    None,
+                    Some(format!("Failed to run forge-demo: {e}")),
+                );
             }
         };
 
+        if test_case.expect_export_error {
+            return Self::check_export_rejected(test_case, &output);
+        }
         if !output.status.success() {
-            return TestResult::Fail {
-                name: test_case.name.clone(),
-                formula: test_case.formula.clone(),
-                expected: test_case.expected,
-                actual: None,
-                error: Some(format!(
-                    "forge calculate failed: {}",
+            return TestResult::fail(
+                test_case,
+                None,
+                Some(format!(
+                    "forge-demo export failed: {}",
                     String::from_utf8_lossy(&output.stderr)
                 )),
-            };
+            );
         }
 
-        // Parse output: "assumptions.test_result = <value>"
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        match Self::parse_calculate_output(&stdout, "test_result") {
-            Ok(actual) => {
-                if (actual - test_case.expected).abs() < f64::EPSILON {
-                    TestResult::Pass {
-                        name: test_case.name.clone(),
-                        formula: test_case.formula.clone(),
-                        expected: test_case.expected,
-                        actual,
-                    }
-                } else {
-                    TestResult::Fail {
-                        name: test_case.name.clone(),
-                        formula: test_case.formula.clone(),
-                        expected: test_case.expected,
-                        actual: Some(actual),
-                        error: None,
-                    }
-                }
+        if let Err(e) = Self::check_export_output(&xlsx_path) {
+            return TestResult::fail(test_case, None, Some(e));
+        }
+
+        // Convert XLSX to CSV (one file per sheet) using spreadsheet engine
+        let converted = self.convert_limit.run(|| {
+            self.timed(Phase::Convert, || {
+                self.engine
+                    .xlsx_to_csv_per_sheet(&xlsx_path, temp_dir.path())
+            })
+        });
+        let csv_paths = match converted {
+            Ok(p) => p,
+            Err(e) => {
+                return TestResult::fail(
+                    test_case,
+                    None,
+                    Some(format!("CSV conversion failed: {e}")),
+                );
+            }
+        };
+
+        // Parse CSV and find result
+        let CsvMatch {
+            value: actual,
+            heuristic,
+        } = match self.timed(Phase::Parse, || {
+            Self::find_result_in_csv(
+                &csv_paths,
+                test_case.result_cell.as_deref(),
+                self.config.label_columns,
+                self.config.heuristic_match,
+                |cell| test_case.parse_actual(cell),
+                |v| self.heuristic_accepts(test_case, v),
+            )
+        }) {
+            Ok(found) => found,
+            Err(mut e) => {
+                if self.config.show_csv_on_fail {
+                    e.push_str(&csv_excerpt(&csv_paths));
+                }
+                return TestResult::fail(test_case, None, Some(e));
+            }
+        };
+
+        if self.config.recalc == RecalcStrategy::Compare {
+            if let Err(e) = self.check_cached_value(&xlsx_path, temp_dir.path(), test_case, actual)
+            {
+                return TestResult::fail(test_case, Some(actual), Some(e));
+            }
+        }
+
+        let result = self.compare(test_case, actual, heuristic);
+        if self.config.compare_formulas && result.is_pass() {
+            if let Err(e) = Self::check_exported_formula(&xlsx_path, test_case) {
+                return TestResult::fail(test_case, Some(actual), Some(e));
+            }
+        }
+        result
+    }
+
+    /// Passes a test expecting an export error if `output` is a failed
+    /// export, and fails it if the export succeeded.
+    ///
+    /// Only a non-zero exit counts as a rejection; a forge-demo killed by a
+    /// signal fails the test.
+    fn check_export_rejected(test_case: &TestCase, output: &Output) -> TestResult {
+        let error = match output.status.code() {
+            Some(0) => "forge-demo export succeeded, but the formula should be rejected",
+            Some(_) => return TestResult::pass(test_case, f64::NAN, false),
+            None => "forge-demo export was killed by a signal",
+        };
+        TestResult::fail(test_case, None, Some(error.to_string()))
+    }
+
+    /// Runs `forge-demo export` on `yaml_path`, writing `xlsx_path`.
+    fn export(&self, yaml_path: &Path, xlsx_path: &Path) -> io::Result<Output> {
+        self.export_limit.run(|| {
+            self.timed(Phase::Export, || {
+                self.transport.run(
+                    &self.forge_binary,
+                    &self.config,
+                    &[
+                        ForgeArg::Plain("export"),
+                        ForgeArg::Input(yaml_path),
+                        ForgeArg::Output(xlsx_path),
+                    ],
+                )
+            })
+        })
+    }
+
+    /// Compares `actual` with `test_case`'s expectation at its configured tolerance.
+    fn compare(&self, test_case: &TestCase, actual: f64, heuristic: bool) -> TestResult {
+        self.timed(Phase::Compare, || {
+            TestResult::compare(
+                test_case,
+                actual,
+                heuristic,
+                self.config.tolerance_for(test_case),
+            )
+        })
+    }
+
+    /// Compares the formula forge-demo wrote into the XLSX with the test's.
+    ///
+    /// A mismatch means forge-demo exported a wrong formula, even if it
+    /// evaluates to the expected value. The formula is read from the
+    /// test's `result_cell` when that names a sheet, then from the cell
+    /// after a result label, and is otherwise the export's only formula.
+    /// An export with several formulas and no label can't be checked, which
+    /// is an error too.
+    fn check_exported_formula(xlsx_path: &Path, test_case: &TestCase) -> Result<(), String> {
+        let located = match test_case
+            .result_cell
+            .as_deref()
+            .and_then(|c| c.split_once('!'))
+        {
+            Some((sheet, cell)) => Some((sheet.to_string(), cell.to_string())),
+            None => excel::find_labeled_cell(xlsx_path, Self::is_result_label)?,
+        };
+        let exported = match located {
+            Some((sheet, cell)) => excel::read_formula_at(xlsx_path, &sheet, &cell)?,
+            None => match excel::read_formulas(xlsx_path)?.as_slice() {
+                [] => None,
+                [(_, _, formula)] => Some(formula.clone()),
+                formulas => {
+                    return Err(format!(
+                        "Formula not checked: forge-demo exported {} formulas and no {RESULT_LABEL} label",
+                        formulas.len()
+                    ))
+                }
+            },
+        };
+        match exported {
+            None => Err(format!(
+                "Formula mismatch: forge-demo exported a value instead of {}",
+                test_case.formula
+            )),
+            Some(exported)
+                if excel::normalize_formula(&exported)
+                    != excel::normalize_formula(&test_case.formula) =>
+            {
+                Err(format!(
+                    "Formula mismatch: forge-demo exported ={exported} for {}",
+                    test_case.formula
+                ))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Compares the value forge-demo cached in the XLSX with the recalculated one.
+    ///
+    /// A mismatch means forge-demo wrote a wrong cached value, even if the
+    /// formula itself recalculates correctly.
+    fn check_cached_value(
+        &self,
+        xlsx_path: &Path,
+        temp_dir: &Path,
+        test_case: &TestCase,
+        recalculated: f64,
+    ) -> Result<(), String> {
+        let cached_dir = temp_dir.join("cached");
+        fs::create_dir_all(&cached_dir)
+            .map_err(|e| format!("Failed to create cached CSV dir: {e}"))?;
+        let csv_paths = self
+            .convert_limit
+            .run(|| {
+                self.engine
+                    .clone()
+                    .with_recalc(false)
+                    .xlsx_to_csv_per_sheet(xlsx_path, &cached_dir)
+            })
+            .map_err(|e| format!("Cached CSV conversion failed: {e}"))?;
+        let cached = Self::find_result_in_csv(
+            &csv_paths,
+            test_case.result_cell.as_deref(),
+            self.config.label_columns,
+            self.config.heuristic_match,
+            |cell| test_case.parse_actual(cell),
+            |v| self.heuristic_accepts(test_case, v),
+        )?
+        .value;
+
+        // Compared like an expected value, so equal infinities match and
+        // the test's own tolerance applies
+        if test_case.values_agree(
+            test_case.scaled(cached),
+            test_case.scaled(recalculated),
+            self.config.tolerance_for(test_case),
+        ) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Cached value {cached} differs from recalculated value {recalculated}"
+            ))
+        }
+    }
+
+    /// Verifies that a successful export actually produced an XLSX file.
+    ///
+    /// forge-demo can exit 0 without writing anything (e.g. when it skips
+    /// validation), which otherwise surfaces later as a confusing
+    /// conversion error.
+    fn check_export_output(xlsx_path: &Path) -> Result<(), String> {
+        match fs::metadata(xlsx_path) {
+            Ok(meta) if meta.len() > 0 => Ok(()),
+            Ok(_) => Err(format!(
+                "forge-demo export succeeded but produced an empty file: {}",
+                xlsx_path.display()
+            )),
+            Err(_) => Err(format!(
+                "forge-demo export succeeded but produced no file: {}",
+                xlsx_path.display()
+            )),
+        }
+    }
+
+    /// Finds the result value in per-sheet CSV output.
+    ///
+    /// The generated YAML always names the result `test_result`, so the
+    /// labeled cell is searched for first across every sheet and, when
+    /// present, is used deterministically. Only if no sheet carries the
+    /// label does it fall back to the first value `accepts` approves of
+    /// (i.e. one matching an expected value). Cells are read with `parse`
+    /// (see [`TestCase::parse_actual`]).
+    ///
+    /// A `result_cell` (see [`TestCase::result_cell`]) replaces both
+    /// searches: only that cell is read.
+    ///
+    /// With `label_columns`, labels are only looked for in that many
+    /// leading columns and their value is the first column after them;
+    /// otherwise a label's value is the cell next to it. Either way, the
+    /// fallback never matches a value in a label column (detected when not
+    /// given), such as a row index.
+    ///
+    /// Without `heuristic_match`, there is no fallback: a missing label is
+    /// an error.
+    fn find_result_in_csv(
+        csv_paths: &[PathBuf],
+        result_cell: Option<&str>,
+        label_columns: Option<usize>,
+        heuristic_match: bool,
+        parse: impl Fn(&str) -> Option<f64>,
+        accepts: impl Fn(f64) -> bool,
+    ) -> Result<CsvMatch, String> {
+        let mut sheets = Vec::with_capacity(csv_paths.len());
+        for csv_path in csv_paths {
+            sheets.push(Self::read_csv_cells(csv_path)?);
+        }
+
+        if let Some(cell) = result_cell {
+            return Self::read_result_cell(csv_paths, &sheets, cell, &parse).map(|value| {
+                CsvMatch {
+                    value,
+                    heuristic: false,
+                }
+            });
+        }
+
+        // A present label is authoritative, even if its value is unusable:
+        // falling back could match an unrelated input cell instead.
+        if let Some(labeled) = sheets
+            .iter()
+            .find_map(|rows| Self::find_labeled_value(rows, label_columns, &parse))
+        {
+            return labeled.map(|value| CsvMatch {
+                value,
+                heuristic: false,
+            });
+        }
+
+        if !heuristic_match {
+            return Err(
+                "No labeled result cell found (heuristic matching is disabled)".to_string(),
+            );
+        }
+        sheets
+            .iter()
+            .find_map(|rows| {
+                let label_columns = label_columns.unwrap_or_else(|| detect_label_columns(rows));
+                Self::find_matching_value(rows, label_columns, &parse, &accepts)
+            })
+            .map(|value| CsvMatch {
+                value,
+                heuristic: true,
+            })
+            .ok_or_else(|| "Could not find result in CSV output".to_string())
+    }
+
+    /// Reads a CSV file into rows of trimmed cells.
+    fn read_csv_cells(csv_path: &Path) -> Result<Vec<Vec<String>>, String> {
+        let (content, _) =
+            read_csv_text(csv_path).map_err(|e| format!("Failed to open CSV: {e}"))?;
+
+        let mut rows = Vec::new();
+        for line in content.lines() {
+            // Simple CSV parsing
+            rows.push(
+                line.split(',')
+                    .map(|s| s.trim_matches('"').trim().to_string())
+                    .collect(),
+            );
+        }
+        Ok(rows)
+    }
+
+    /// Returns `true` if a cell labels the generated result scalar.
+    ///
+    /// Matches `result`, `test_result`, and section-qualified labels such
+    /// as `assumptions.test_result`.
+    fn is_result_label(cell: &str) -> bool {
+        cell == "result"
+            || cell == RESULT_LABEL
+            || cell
+                .rsplit_once('.')
+                .is_some_and(|(_, name)| name == RESULT_LABEL)
+    }
+
+    /// Finds the value in the cell following a result label, or in the
+    /// first column after `label_columns` when set.
+    ///
+    /// Returns `None` if no cell carries the label, and an error if the
+    /// labeled value is missing or unparseable (e.g. `#VALUE!`).
+    fn find_labeled_value(
+        rows: &[Vec<String>],
+        label_columns: Option<usize>,
+        parse: impl Fn(&str) -> Option<f64>,
+    ) -> Option<Result<f64, String>> {
+        rows.iter().find_map(|cells| {
+            let labels = &cells[..label_columns.map_or(cells.len(), |n| n.min(cells.len()))];
+            let i = labels.iter().position(|cell| Self::is_result_label(cell))?;
+            let raw = cells
+                .get(label_columns.unwrap_or(i + 1))
+                .map_or("", String::as_str);
+            Some(
+                parse(raw)
+                    .ok_or_else(|| format!("Result cell {} is not numeric: {raw:?}", cells[i])),
+            )
+        })
+    }
+
+    /// Reads the value at `cell` (`B2` or `Sheet!B2`) from the first sheet
+    /// that has it.
+    ///
+    /// `sheets` are the parsed contents of `csv_paths`, whose file names
+    /// end in `.<sheet>.csv`.
+    fn read_result_cell(
+        csv_paths: &[PathBuf],
+        sheets: &[Vec<Vec<String>>],
+        cell: &str,
+        parse: impl Fn(&str) -> Option<f64>,
+    ) -> Result<f64, String> {
+        let (sheet, address) = cell
+            .rsplit_once('!')
+            .map_or((None, cell), |(sheet, address)| (Some(sheet), address));
+        let (row, col) = excel::parse_a1(address)
+            .ok_or_else(|| format!("Invalid result_cell {cell:?}: expected A1 or Sheet!A1"))?;
+        let suffix = sheet.map(|sheet| format!(".{sheet}.csv"));
+        let raw = csv_paths
+            .iter()
+            .zip(sheets)
+            .filter(|(path, _)| {
+                suffix
+                    .as_ref()
+                    .is_none_or(|suffix| path.to_string_lossy().ends_with(suffix.as_str()))
+            })
+            .find_map(|(_, rows)| {
+                let value = rows.get(row as usize)?.get(col as usize)?;
+                (!value.is_empty()).then_some(value)
+            })
+            .ok_or_else(|| format!("result_cell {cell} is empty"))?;
+        parse(raw).ok_or_else(|| format!("result_cell {cell} is not numeric: {raw:?}"))
+    }
+
+    /// Finds any parseable cell after the first `label_columns` columns
+    /// matching an expected value (heuristic fallback).
+    fn find_matching_value(
+        rows: &[Vec<String>],
+        label_columns: usize,
+        parse: impl Fn(&str) -> Option<f64>,
+        accepts: impl Fn(f64) -> bool,
+    ) -> Option<f64> {
+        rows.iter()
+            .flat_map(|cells| cells.iter().skip(label_columns))
+            .find_map(|cell| {
+                let value = parse(cell)?;
+                accepts(value).then_some(value)
+            })
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// YAML Generation
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Builds the minimal forge YAML used to evaluate a single formula.
+///
+/// This is exactly what [`TestRunner::run_test`] feeds to forge-demo, so it
+/// doubles as a standalone reproducer for bug reports.
+pub fn test_yaml(section: &str, formula: &str) -> String {
+    build_test_yaml(section, &[(RESULT_LABEL, formula)])
+}
+
+/// Builds a forge YAML spec with one `section` scalar per `(name, formula)`.
+///
+/// Every generated spec (single test, perf, batch) goes through here so the
+/// escaping rules live in one place.
+pub fn build_test_yaml(section: &str, cases: &[(&str, &str)]) -> String {
+    let mut yaml = format!("_forge_version: \"1.0.0\"\n{section}:\n");
+    for (name, formula) in cases {
+        let _ = write!(
+            yaml,
+            "  {name}:\n    value: null\n    formula: \"{}\"\n",
+            escape_yaml_string(formula)
+        );
+    }
+    yaml
+}
+
+/// Escapes `s` for use inside a YAML double-quoted scalar.
+///
+/// Control characters and the Unicode line breaks YAML folds (NEL, LS, PS)
+/// are written as escape sequences so the formula survives a round trip
+/// byte-for-byte.
+fn escape_yaml_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{85}' | '\u{2028}' | '\u{2029}' => {
+                let _ = write!(out, "\\u{:04X}", u32::from(c));
+            }
+            c if c.is_control() => {
+                let _ = write!(out, "\\x{:02X}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Dependencies
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Fails if two tests or skips declare the same `id`.
+///
+/// Ids key results across runs, so a shared one would silently merge two
+/// tests' histories.
+fn check_unique_ids(cases: &[TestCase], skips: &[SkipCase]) -> Result<(), String> {
+    let mut owners: HashMap<&str, &str> = HashMap::new();
+    let declared = cases
+        .iter()
+        .map(|tc| (tc.id.as_deref(), tc.name.as_str()))
+        .chain(skips.iter().map(|sc| (sc.id.as_deref(), sc.name.as_str())));
+    for (id, name) in declared {
+        let Some(id) = id else { continue };
+        if let Some(first) = owners.insert(id, name) {
+            return Err(format!("Duplicate test id {id:?}: {first} and {name}"));
+        }
+    }
+    Ok(())
+}
+
+/// Orders test cases so every test comes after the tests it depends on.
+///
+/// Independent tests keep their relative order. Fails on a dependency
+/// cycle or on a dependency that names no loaded test or skip.
+fn order_by_dependencies(
+    cases: Vec<TestCase>,
+    skips: &[SkipCase],
+) -> Result<Vec<TestCase>, String> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Mark {
+        Unvisited,
+        InProgress,
+        Done,
+    }
+
+    /// Depth-first visit appending `i` after its dependencies.
+    fn visit(
+        i: usize,
+        cases: &[TestCase],
+        index: &HashMap<&str, usize>,
+        marks: &mut [Mark],
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), String> {
+        match marks[i] {
+            Mark::Done => return Ok(()),
+            Mark::InProgress => {
+                let start = path.iter().position(|&p| p == i).unwrap_or(0);
+                let cycle: Vec<&str> = path[start..]
+                    .iter()
+                    .chain(std::iter::once(&i))
+                    .map(|&p| cases[p].name.as_str())
+                    .collect();
+                return Err(format!("Dependency cycle: {}", cycle.join(" -> ")));
+            }
+            Mark::Unvisited => {}
+        }
+        marks[i] = Mark::InProgress;
+        path.push(i);
+        for dep in &cases[i].depends_on {
+            if let Some(&d) = index.get(dep.as_str()) {
+                visit(d, cases, index, marks, path, order)?;
+            }
+        }
+        path.pop();
+        marks[i] = Mark::Done;
+        order.push(i);
+        Ok(())
+    }
+
+    let index: HashMap<&str, usize> = cases
+        .iter()
+        .enumerate()
+        .map(|(i, tc)| (tc.name.as_str(), i))
+        .collect();
+    for tc in &cases {
+        for dep in &tc.depends_on {
+            if !index.contains_key(dep.as_str()) && !skips.iter().any(|s| &s.name == dep) {
+                return Err(format!("{} depends on unknown test {dep}", tc.name));
+            }
+        }
+    }
+
+    let mut marks = vec![Mark::Unvisited; cases.len()];
+    let mut order = Vec::with_capacity(cases.len());
+    for i in 0..cases.len() {
+        visit(i, &cases, &index, &mut marks, &mut Vec::new(), &mut order)?;
+    }
+
+    let mut slots: Vec<Option<TestCase>> = cases.into_iter().map(Some).collect();
+    Ok(order.into_iter().filter_map(|i| slots[i].take()).collect())
+}
+
+/// Tracks which tests passed so dependents of the rest can be skipped.
+#[derive(Debug, Default)]
+pub struct DependencyGate {
+    /// Names of tests that passed so far.
+    passed: HashSet<String>,
+}
+
+impl DependencyGate {
+    /// Returns a skip result if a dependency of `test_case` has not passed.
+    pub fn check(&self, test_case: &TestCase) -> Option<TestResult> {
+        let dep = test_case
+            .depends_on
+            .iter()
+            .find(|dep| !self.passed.contains(*dep))?;
+        Some(TestResult::Skip {
+            name: test_case.name.clone(),
+            id: test_case.id.clone(),
+            reason: format!("Dependency {dep} did not pass"),
+        })
+    }
+
+    /// Records a finished result.
+    pub fn record(&mut self, result: &TestResult) {
+        if result.is_pass() {
+            self.passed.insert(result.name().to_string());
+        }
+    }
+}
+
+/// Reads a CSV file as text, decoding invalid UTF-8 lossily.
+///
+/// ssconvert writes cells in the workbook's encoding, so a non-UTF-8 cell
+/// must not make its whole row unreadable. Returns the text and whether
+/// any bytes were replaced.
+fn read_csv_text(path: &Path) -> io::Result<(String, bool)> {
+    let bytes = fs::read(path)?;
+    Ok(match String::from_utf8(bytes) {
+        Ok(text) => (text, false),
+        Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+    })
+}
+
+/// Returns `true` for a JSON file that isn't a spec: valid JSON without a
+/// top-level `_forge_version`, such as a `package.json` beside the specs.
+///
+/// Invalid JSON is still loaded, so a broken spec is reported rather
+/// than skipped.
+fn is_foreign_json(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .is_ok_and(|json| json.get("_forge_version").is_none())
+}
+
+/// Guesses how many leading columns of `rows` hold labels: an optional
+/// row index (consecutive integers) followed by columns with no numbers.
+///
+/// An index only counts when a label column follows it, so a lone column
+/// of consecutive values stays data.
+pub fn detect_label_columns<S: AsRef<str>>(rows: &[Vec<S>]) -> usize {
+    let column = |c: usize| {
+        rows.iter()
+            .filter_map(move |cells| cells.get(c))
+            .map(AsRef::as_ref)
+            .filter(|cell| !cell.is_empty())
+    };
+    let is_label =
+        |c: usize| column(c).next().is_some() && column(c).all(|cell| parse_number(cell).is_none());
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let labels_from = |first: usize| (first..width).find(|&c| !is_label(c)).unwrap_or(width);
+    if has_index_column(rows) {
+        labels_from(1)
+    } else {
+        labels_from(0)
+    }
+}
+
+/// Returns whether the first column of `rows` is a row index: consecutive
+/// integers followed by a column with no numbers.
+fn has_index_column<S: AsRef<str>>(rows: &[Vec<S>]) -> bool {
+    let column = |c: usize| {
+        rows.iter()
+            .filter_map(move |cells| cells.get(c))
+            .map(AsRef::as_ref)
+            .filter(|cell| !cell.is_empty())
+    };
+    let index: Vec<i64> = column(0).filter_map(|cell| cell.parse().ok()).collect();
+    index.len() >= 2
+        && index.windows(2).all(|w| w[1] == w[0] + 1)
+        && column(1).next().is_some()
+        && column(1).all(|cell| parse_number(cell).is_none())
+}
+
+/// Returns the first [`CSV_EXCERPT_LINES`] lines of each CSV, each file
+/// under a `--- name ---` heading, for appending to a failure message.
+fn csv_excerpt(csv_paths: &[PathBuf]) -> String {
+    let mut excerpt = String::new();
+    for path in csv_paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let _ = write!(excerpt, "\n--- {name} ---");
+        match read_csv_text(path) {
+            Ok((content, lossy)) => {
+                if lossy {
+                    excerpt.push_str("\n(not valid UTF-8; invalid bytes shown as \u{fffd})");
+                }
+                let total = content.lines().count();
+                for line in content.lines().take(CSV_EXCERPT_LINES) {
+                    let _ = write!(excerpt, "\n{line}");
+                }
+                if total > CSV_EXCERPT_LINES {
+                    let _ = write!(excerpt, "\n... ({} more lines)", total - CSV_EXCERPT_LINES);
+                }
+            }
+            Err(e) => {
+                let _ = write!(excerpt, "\n(unreadable: {e})");
+            }
+        }
+    }
+    excerpt
+}
+
+/// Builds a forge-demo command with the environment `config` asks for.
+pub fn forge_command(forge_binary: &Path, config: &Config) -> Command {
+    let mut cmd = Command::new(forge_binary);
+    if let Some(today) = config.today {
+        cmd.env(TODAY_ENV, today.format("%Y-%m-%d").to_string());
+    }
+    cmd
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_SECTION;
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
+    use crate::transport::SshTransport;
+    use crate::types::parse_number;
+    use chrono::NaiveDate;
+    use std::ffi::OsStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_yaml_is_a_loadable_spec() {
+        let yaml = test_yaml(DEFAULT_SECTION, r#"=LEN("abc")"#);
+        let spec: TestSpec = serde_yaml_ng::from_str(&yaml).unwrap();
+        let Some(crate::types::Section::ScalarGroup(scalars)) = spec.sections.get(DEFAULT_SECTION)
+        else {
+            panic!("missing assumptions section");
+        };
+        assert_eq!(
+            scalars[RESULT_LABEL].formula.as_deref(),
+            Some(r#"=LEN("abc")"#)
+        );
+    }
+
+    /// Parses generated YAML back and returns the formula stored under `name`.
+    fn roundtrip_formula(yaml: &str, name: &str) -> Option<String> {
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let Some(crate::types::Section::ScalarGroup(scalars)) = spec.sections.get(DEFAULT_SECTION)
+        else {
+            panic!("missing assumptions section");
+        };
+        scalars[name].formula.clone()
+    }
+
+    #[test]
+    fn build_test_yaml_escapes_tricky_formulas() {
+        let formulas = [
+            r#"=IF(A1="x","say ""hi""","")"#,
+            r#"=LEN("C:\temp\data")"#,
+            "=TEXT(NOW(), \"hh:mm:ss\")",
+            "=\"key: value\"",
+            "=\"line1\nline2\"",
+        ];
+        let names: Vec<String> = (0..formulas.len()).map(|i| format!("test_{i}")).collect();
+        let cases: Vec<(&str, &str)> = names.iter().map(String::as_str).zip(formulas).collect();
+
+        let yaml = build_test_yaml(DEFAULT_SECTION, &cases);
+        for (name, formula) in cases {
+            assert_eq!(roundtrip_formula(&yaml, name).as_deref(), Some(formula));
+        }
+    }
+
+    #[test]
+    fn generated_section_matches_parsed_output() {
+        let yaml = test_yaml("inputs", "=1+1");
+        assert!(yaml.contains("\ninputs:\n  test_result:\n"));
+    }
+
+    #[test]
+    fn parse_batch_csv_uses_configured_section() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("batch.csv");
+        fs::write(&csv, "inputs.test_0,5\nassumptions.test_1,6\ntest_1,7\n").unwrap();
+
+        let results =
+            TestRunner::parse_batch_csv(&csv, "inputs", None, &[sample_case(), sample_case()]);
+        assert_eq!(results[0], Ok(5.0));
+        assert_eq!(results[1], Ok(7.0));
+    }
+
+    #[test]
+    fn parse_batch_csv_skips_leading_index_column() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("batch.csv");
+        fs::write(&csv, "0,assumptions.test_0,5\n1,assumptions.test_1,6\n").unwrap();
+
+        let cases = [sample_case(), sample_case()];
+        let results = TestRunner::parse_batch_csv(&csv, DEFAULT_SECTION, None, &cases);
+        assert_eq!(results, vec![Ok(5.0), Ok(6.0)]);
+        let results = TestRunner::parse_batch_csv(&csv, DEFAULT_SECTION, Some(1), &cases);
+        assert!(results.iter().all(Result::is_err));
+    }
+
+    #[test]
+    fn parse_batch_csv_reads_text_results() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("batch.csv");
+        fs::write(&csv, "test_0,FF\ntest_1,1A\n").unwrap();
+
+        let hex = TestCase {
+            base: Some(16),
+            ..sample_case()
+        };
+        let cases = [hex.clone(), hex];
+        let results = TestRunner::parse_batch_csv(&csv, DEFAULT_SECTION, None, &cases);
+        assert_eq!(results, vec![Ok(255.0), Ok(26.0)]);
+
+        fs::write(&csv, "0,test_0,FF\n1,test_1,1A\n").unwrap();
+        let results = TestRunner::parse_batch_csv(&csv, DEFAULT_SECTION, None, &cases);
+        assert_eq!(results, vec![Ok(255.0), Ok(26.0)]);
+    }
+
+    #[test]
+    fn label_columns_are_detected() {
+        let rows = |csv: &str| -> Vec<Vec<String>> {
+            csv.lines()
+                .map(|line| line.split(',').map(str::to_string).collect())
+                .collect()
+        };
+        assert_eq!(detect_label_columns(&rows("test_0,5\ntest_1,6")), 1);
+        assert_eq!(detect_label_columns(&rows("0,test_0,5\n1,test_1,6")), 2);
+        assert_eq!(
+            detect_label_columns(&rows("#,section,name,value\n1,inputs,a,5\n2,inputs,b,6")),
+            3
+        );
+        // Consecutive values without labels after them are data
+        assert_eq!(detect_label_columns(&rows("1,5\n2,6")), 0);
+        // A single row can't tell an index from a value
+        assert_eq!(detect_label_columns(&rows("1,test_0,5")), 0);
+        assert_eq!(detect_label_columns::<String>(&[]), 0);
+    }
+
+    #[test]
+    fn csv_rows_with_invalid_utf8_are_kept() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("batch.csv");
+        // 0xE9 is Latin-1 `é`, invalid on its own in UTF-8
+        fs::write(
+            &csv,
+            b"caf\xe9,1\nassumptions.test_0,5,caf\xe9\nassumptions.test_1,6 \xe9\n",
+        )
+        .unwrap();
+
+        let results =
+            TestRunner::parse_batch_csv(&csv, "assumptions", None, &[sample_case(), sample_case()]);
+        assert_eq!(results[0], Ok(5.0));
+        assert!(results[1].is_err());
+
+        let rows = TestRunner::read_csv_cells(&csv).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][0], "caf\u{fffd}");
+        assert!(csv_excerpt(&[csv]).contains("not valid UTF-8"));
+    }
+
+    #[test]
+    fn parse_batch_csv_prefers_numeric_occurrence_of_label() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("batch.csv");
+        fs::write(
+            &csv,
+            "assumptions.test_0,assumptions.test_1\nassumptions.test_0,3\nassumptions.test_1,4\nassumptions.test_1,value\nassumptions.test_1,9\n",
+        )
+        .unwrap();
+
+        let results = TestRunner::parse_batch_csv(
+            &csv,
+            DEFAULT_SECTION,
+            None,
+            &[sample_case(), sample_case()],
+        );
+        assert_eq!(results[0], Ok(3.0));
+        assert_eq!(results[1], Ok(4.0));
+    }
+
+    #[test]
+    fn build_test_yaml_roundtrips_control_characters() {
+        let formulas = [
+            "=\"a\\b\"",
+            "=\"trailing\\\"",
+            "=\"tab\there\"",
+            "=\"crlf\r\nend\"",
+            "=\"nul\u{0}bell\u{7}del\u{7f}\"",
+            "=\"nel\u{85}ls\u{2028}ps\u{2029}\"",
+        ];
+        for formula in formulas {
+            let yaml = build_test_yaml(DEFAULT_SECTION, &[(RESULT_LABEL, formula)]);
+            assert_eq!(
+                roundtrip_formula(&yaml, RESULT_LABEL).as_deref(),
+                Some(formula),
+                "{yaml}"
+            );
+        }
+    }
+
+    #[test]
+    fn escape_yaml_string_keeps_output_on_one_line() {
+        let escaped = escape_yaml_string("=\"a\r\n\u{2028}b\"");
+        assert_eq!(escaped, r#"=\"a\r\n\u2028b\""#);
+    }
+
+    #[test]
+    fn load_empty_dir_returns_empty_cases() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result = TestRunner::load_test_cases(temp_dir.path(), InputFormat::Auto);
+        assert!(result.is_ok());
+        let (cases, skips, files) = result.unwrap();
+        assert!(cases.is_empty());
+        assert!(skips.is_empty());
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn load_nonexistent_dir_returns_error() {
+        let result = TestRunner::load_test_cases(Path::new("/nonexistent/path"), InputFormat::Auto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn load_dir_with_yaml_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let yaml_content = r#"
+_forge_version: "1.0.0"
+assumptions:
+  test_one:
+    value: null
+    formula: "=1+1"
+    expected: 2
+"#;
+        fs::write(temp_dir.path().join("test.yaml"), yaml_content).unwrap();
+
+        let result = TestRunner::load_test_cases(temp_dir.path(), InputFormat::Auto);
+        assert!(result.is_ok());
+        let (cases, _, files) = result.unwrap();
+        assert_eq!(cases.len(), 1);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, temp_dir.path().join("test.yaml"));
+        assert_eq!(files[0].tests, ["assumptions.test_one"]);
+        assert!(files[0].skips.is_empty());
+    }
+
+    #[test]
+    fn load_rejects_duplicate_ids() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let yaml_content = r#"
+_forge_version: "1.0.0"
+assumptions:
+  test_one:
+    formula: "=1+1"
+    expected: 2
+    id: sum-001
+"#;
+        fs::write(temp_dir.path().join("a.yaml"), yaml_content).unwrap();
+        fs::write(
+            temp_dir.path().join("b.yaml"),
+            yaml_content.replace("assumptions", "copied"),
+        )
+        .unwrap();
+
+        let err = TestRunner::load_test_cases(temp_dir.path(), InputFormat::Auto).unwrap_err();
+        assert!(
+            err.to_string().contains("Duplicate test id \"sum-001\""),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn load_rejects_unparseable_expected_text() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let yaml_content = r#"
+_forge_version: "1.0.0"
+engineering:
+  test_dec2hex:
+    formula: "=DEC2HEX(255)"
+    expected: "FG"
+    base: 16
+"#;
+        fs::write(temp_dir.path().join("hex.yaml"), yaml_content).unwrap();
+
+        let err = TestRunner::load_test_cases(temp_dir.path(), InputFormat::Auto).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("engineering.test_dec2hex: expected \"FG\" is not an integer in base 16"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn load_ignores_files_that_are_not_specs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("readme.txt"), "not yaml").unwrap();
+        fs::write(
+            temp_dir.path().join("package.json"),
+            r#"{"name": "fixtures", "version": "1.0.0"}"#,
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("values.json"), "[1, 2, 3]").unwrap();
+        fs::write(
+            temp_dir.path().join("math.json"),
+            r#"{"_forge_version": "1.0.0", "math": {"test_one": {"formula": "=1", "expected": 1}}}"#,
+        )
+        .unwrap();
+
+        let (cases, _, spec_files) =
+            TestRunner::load_test_cases(temp_dir.path(), InputFormat::Auto).unwrap();
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].name, "math.test_one");
+        let loaded: Vec<_> = spec_files
+            .iter()
+            .map(|f| f.path.file_name().unwrap())
+            .collect();
+        assert_eq!(loaded, ["math.json"]);
+
+        assert!(is_foreign_json("{}"));
+        assert!(!is_foreign_json("{\"_forge_version\": \"1.0.0\""));
+    }
+
+    #[test]
+    fn load_resolves_expected_from_xlsx() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        excel::create_test_scalars_xlsx(&temp_dir.path().join("model.xlsx")).unwrap();
+        let yaml_content = r#"
+_forge_version: "1.0.0"
+assumptions:
+  test_revenue:
+    value: null
+    formula: "=100000"
+    expected_from_xlsx: "model.xlsx!Scalars!B2"
+  test_broken:
+    value: null
+    formula: "=1"
+    expected_from_xlsx: "missing.xlsx!Scalars!B2"
+"#;
+        fs::write(temp_dir.path().join("oracle.yaml"), yaml_content).unwrap();
+
+        let (cases, _, _) =
+            TestRunner::load_test_cases(temp_dir.path(), InputFormat::Auto).unwrap();
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].name, "assumptions.test_revenue");
+        assert!((cases[0].expected - 100_000.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_json_spec_matches_yaml() {
+        let yaml_dir = tempfile::tempdir().unwrap();
+        let yaml_content = r#"
+_forge_version: "1.0.0"
+assumptions:
+  test_sum:
+    value: null
+    formula: "=SUM(1,2)"
+    expected: 3
+  test_pick:
+    value: null
+    formula: "=CHOOSE(2,1,2,3)"
+    expected_any: [2, 3]
+  test_skipped:
+    value: null
+    formula: "=NOW()"
+    skip: "volatile"
+"#;
+        fs::write(yaml_dir.path().join("spec.yaml"), yaml_content).unwrap();
+
+        let json_dir = tempfile::tempdir().unwrap();
+        let json_content = r#"{
+  "_forge_version": "1.0.0",
+  "assumptions": {
+    "test_sum": {"value": null, "formula": "=SUM(1,2)", "expected": 3},
+    "test_pick": {"value": null, "formula": "=CHOOSE(2,1,2,3)", "expected_any": [2, 3]},
+    "test_skipped": {"value": null, "formula": "=NOW()", "skip": "volatile"}
+  }
+}"#;
+        fs::write(json_dir.path().join("spec.json"), json_content).unwrap();
+
+        let (mut yaml_cases, mut yaml_skips, _) =
+            TestRunner::load_test_cases(yaml_dir.path(), InputFormat::Auto).unwrap();
+        let (mut json_cases, mut json_skips, json_files) =
+            TestRunner::load_test_cases(json_dir.path(), InputFormat::Auto).unwrap();
+        yaml_cases.sort_by(|a, b| a.name.cmp(&b.name));
+        json_cases.sort_by(|a, b| a.name.cmp(&b.name));
+        // Only where the tests are defined differs
+        assert_eq!(yaml_cases[1].line, Some(4));
+        assert_eq!(yaml_skips[0].line, Some(12));
+        assert_eq!(json_skips[0].line, Some(6));
+        for case in yaml_cases.iter_mut().chain(&mut json_cases) {
+            case.line = None;
+        }
+        for skip in yaml_skips.iter_mut().chain(&mut json_skips) {
+            skip.line = None;
+            if let Some(case) = &mut skip.case {
+                case.line = None;
+            }
+        }
+        assert_eq!(format!("{yaml_cases:?}"), format!("{json_cases:?}"));
+        assert_eq!(format!("{yaml_skips:?}"), format!("{json_skips:?}"));
+        assert_eq!(json_cases.len(), 2);
+        assert_eq!(json_skips.len(), 1);
+        assert_eq!(
+            json_files[0].tests,
+            ["assumptions.test_pick", "assumptions.test_sum"]
+        );
+        assert_eq!(json_files[0].skips, ["assumptions.test_skipped"]);
+
+        // An explicit format ignores the other extension
+        let (cases, _, _) =
+            TestRunner::load_test_cases(json_dir.path(), InputFormat::Yaml).unwrap();
+        assert!(cases.is_empty());
+    }
+
+    /// Creates a runner with no loaded specs around a fake forge-demo script.
+    #[cfg(unix)]
+    fn fake_runner(dir: &Path, forge_script: &str) -> TestRunner {
+        let forge = fake_binary(dir, "forge-demo", forge_script);
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("ssconvert"));
+        TestRunner::new(forge, engine, dir.to_path_buf(), InputFormat::Auto).unwrap()
+    }
+
+    /// Fake forge-demo that writes a placeholder XLSX on export.
+    #[cfg(unix)]
+    const FAKE_FORGE_EXPORT: &str = r#"[ "$1" = export ] && echo PK > "$3""#;
+
+    /// Creates a runner whose forge-demo and ssconvert are both fake scripts.
+    ///
+    /// The ssconvert script receives the output path as its last argument
+    /// (`$out`, with `%s` replaced by `Scalars`) and `$recalc` set to 1 when
+    /// `--recalc` was passed.
+    #[cfg(unix)]
+    fn fake_pipeline(dir: &Path, forge_script: &str, ssconvert_script: &str) -> TestRunner {
+        let forge = fake_binary(dir, "forge-demo", forge_script);
+        let ssconvert = fake_binary(
+            dir,
+            "ssconvert",
+            &format!(
+                r#"recalc=0; for arg; do [ "$arg" = --recalc ] && recalc=1; out="$arg"; done
+out=$(echo "$out" | sed 's/%s/Scalars/')
+{ssconvert_script}"#
+            ),
+        );
+        let engine = SpreadsheetEngine::with_path(ssconvert);
+        TestRunner::new(forge, engine, dir.to_path_buf(), InputFormat::Auto).unwrap()
+    }
+
+    /// Fake ssconvert whose cached value (0) differs from the recalculated one (2).
+    #[cfg(unix)]
+    const FAKE_SSCONVERT_STALE: &str = r#"if [ $recalc = 1 ]; then v=2; else v=0; fi
+echo "assumptions.test_result,$v" > "$out""#;
+
+    /// Calculator returning a fixed value for `=1+1` in the `inputs` section.
+    struct FixedCalculator(f64);
+
+    impl Calculator for FixedCalculator {
+        fn calculate(&self, config: &Config, formula: &str) -> Result<f64, String> {
+            if config.section == "inputs" && formula == "=1+1" {
+                Ok(self.0)
+            } else {
+                Err(format!("unexpected {}: {formula}", config.section))
             }
-            Err(e) => TestResult::Fail {
-                name: test_case.name.clone(),
-                formula: test_case.formula.clone(),
-                expected: test_case.expected,
-                actual: None,
-                error: Some(e),
+        }
+    }
+
+    #[test]
+    fn heuristic_fallback_keeps_its_own_tolerance() {
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("ssconvert"));
+        let runner = TestRunner::without_specs(PathBuf::from("/nonexistent/forge"), engine);
+        let case = TestCase {
+            expected_any: vec![5.0],
+            ..sample_case()
+        };
+        assert!(runner.heuristic_accepts(&case, 2.00005));
+        assert!(runner.heuristic_accepts(&case, 4.99995));
+        assert!(!runner.heuristic_accepts(&case, 2.001));
+
+        let loose = TestCase {
+            tolerance: Some(0.01),
+            ..case
+        };
+        assert!(runner.heuristic_accepts(&loose, 2.005));
+    }
+
+    #[test]
+    fn run_perf_test_goes_through_calculator() {
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("ssconvert"));
+        let config = Config {
+            section: "inputs".to_string(),
+            ..Config::default()
+        };
+        let runner = TestRunner::without_specs(PathBuf::from("/nonexistent/forge"), engine)
+            .with_config(config)
+            .with_calculator(FixedCalculator(2.0));
+        assert!(matches!(
+            runner.run_perf_test(&sample_case()),
+            TestResult::Pass { .. }
+        ));
+
+        let runner = runner.with_calculator(FixedCalculator(3.0));
+        assert!(matches!(
+            runner.run_perf_test(&sample_case()),
+            TestResult::Fail { actual: Some(a), .. } if (a - 3.0).abs() < f64::EPSILON
+        ));
+    }
+
+    /// Answers with the size of the thread pool it is called from.
+    struct PoolSizeCalculator;
+
+    impl Calculator for PoolSizeCalculator {
+        #[allow(clippy::cast_precision_loss)]
+        fn calculate(&self, _config: &Config, _formula: &str) -> Result<f64, String> {
+            Ok(rayon::current_num_threads() as f64)
+        }
+    }
+
+    #[test]
+    fn perf_threads_sets_perf_pool_size() {
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("ssconvert"));
+        let mut runner = TestRunner::without_specs(PathBuf::from("/nonexistent/forge"), engine)
+            .with_config(Config {
+                perf_threads: NonZeroUsize::new(3),
+                ..Config::default()
+            })
+            .with_calculator(PoolSizeCalculator);
+        runner.test_cases = vec![TestCase {
+            expected: 3.0,
+            ..sample_case()
+        }];
+        assert_eq!(runner.perf_threads(), 3);
+        assert!(runner.run_perf_parallel()[0].is_pass());
+    }
+
+    /// Answers every formula of a batch with its length, counting calls.
+    struct CountingCalculator(Arc<AtomicUsize>);
+
+    impl Calculator for CountingCalculator {
+        fn calculate(&self, _config: &Config, formula: &str) -> Result<f64, String> {
+            Err(format!("{formula} calculated alone"))
+        }
+
+        fn rejects(&self, _config: &Config, _formula: &str) -> Result<bool, String> {
+            Ok(true)
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        fn calculate_all(&self, _config: &Config, formulas: &[&str]) -> Vec<Result<f64, String>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            formulas.iter().map(|f| Ok(f.len() as f64)).collect()
+        }
+    }
+
+    #[test]
+    fn perf_batch_calculates_all_tests_in_one_call() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("ssconvert"));
+        let mut runner = TestRunner::without_specs(PathBuf::from("/nonexistent/forge"), engine)
+            .with_config(Config {
+                perf_batch: true,
+                ..Config::default()
+            })
+            .with_calculator(CountingCalculator(Arc::clone(&calls)));
+        runner.test_cases = vec![
+            TestCase {
+                expected: 4.0,
+                ..sample_case()
             },
+            TestCase {
+                expect_export_error: true,
+                ..sample_case()
+            },
+            TestCase {
+                formula: "=1+1+1".to_string(),
+                expected: 6.0,
+                ..sample_case()
+            },
+        ];
+        let results = runner.run_perf();
+        assert!(results[0].is_pass());
+        // Calculated alone, where the calculator rejects it
+        assert!(results[1].is_pass());
+        assert!(results[2].is_pass());
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn forge_command_sets_frozen_date() {
+        let forge = Path::new("forge-demo");
+        let cmd = forge_command(forge, &Config::default());
+        assert_eq!(cmd.get_envs().count(), 0);
+
+        let config = Config {
+            today: NaiveDate::from_ymd_opt(2024, 6, 15),
+            ..Config::default()
+        };
+        let cmd = forge_command(forge, &config);
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert_eq!(
+            envs,
+            vec![(OsStr::new(TODAY_ENV), Some(OsStr::new("2024-06-15")))]
+        );
+    }
+
+    /// Returns a sample case named `name` that depends on `deps`.
+    fn dependent_case(name: &str, deps: &[&str]) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            depends_on: deps.iter().map(ToString::to_string).collect(),
+            ..sample_case()
+        }
+    }
+
+    fn names(cases: &[TestCase]) -> Vec<&str> {
+        cases.iter().map(|tc| tc.name.as_str()).collect()
+    }
+
+    #[test]
+    fn order_by_dependencies_runs_dependencies_first() {
+        let cases = vec![
+            dependent_case("m.test_c", &["m.test_b"]),
+            dependent_case("m.test_x", &[]),
+            dependent_case("m.test_b", &["m.test_a"]),
+            dependent_case("m.test_a", &[]),
+        ];
+        let ordered = order_by_dependencies(cases, &[]).unwrap();
+        assert_eq!(
+            names(&ordered),
+            vec!["m.test_a", "m.test_b", "m.test_c", "m.test_x"]
+        );
+    }
+
+    #[test]
+    fn order_by_dependencies_rejects_cycles_and_unknown_tests() {
+        let cycle = vec![
+            dependent_case("m.test_a", &["m.test_b"]),
+            dependent_case("m.test_b", &["m.test_a"]),
+        ];
+        assert_eq!(
+            order_by_dependencies(cycle, &[]).unwrap_err(),
+            "Dependency cycle: m.test_a -> m.test_b -> m.test_a"
+        );
+
+        let unknown = vec![dependent_case("m.test_a", &["m.test_missing"])];
+        assert!(order_by_dependencies(unknown, &[])
+            .unwrap_err()
+            .contains("unknown test m.test_missing"));
+
+        let on_skip = vec![dependent_case("m.test_a", &["m.test_skipped"])];
+        let skips = [SkipCase {
+            name: "m.test_skipped".to_string(),
+            reason: "unsupported".to_string(),
+            case: None,
+            id: None,
+            line: None,
+        }];
+        assert!(order_by_dependencies(on_skip, &skips).is_ok());
+    }
+
+    #[test]
+    fn skip_blocked_skips_dependents_of_failures() {
+        let mut runner = TestRunner::without_specs(
+            PathBuf::from("forge-demo"),
+            SpreadsheetEngine::with_path(PathBuf::from("ssconvert")),
+        );
+        runner.test_cases = vec![
+            dependent_case("m.test_a", &[]),
+            dependent_case("m.test_b", &["m.test_a"]),
+            dependent_case("m.test_c", &["m.test_b"]),
+            dependent_case("m.test_d", &[]),
+        ];
+        let results = runner
+            .test_cases
+            .iter()
+            .map(|tc| {
+                if tc.name == "m.test_a" {
+                    runner.compare(tc, 0.0, false)
+                } else {
+                    runner.compare(tc, 2.0, false)
+                }
+            })
+            .collect();
+
+        let results = runner.skip_blocked(results);
+        assert!(matches!(&results[0], TestResult::Fail { .. }));
+        assert!(matches!(
+            &results[1],
+            TestResult::Skip { reason, .. } if reason == "Dependency m.test_a did not pass"
+        ));
+        assert!(matches!(
+            &results[2],
+            TestResult::Skip { reason, .. } if reason == "Dependency m.test_b did not pass"
+        ));
+        assert!(results[3].is_pass());
+    }
+
+    fn sample_case() -> TestCase {
+        TestCase {
+            name: "assumptions.test_sample".to_string(),
+            formula: "=1+1".to_string(),
+            expected: 2.0,
+            ..TestCase::default()
         }
     }
 
-    /// Parses `forge calculate` output to extract a value.
-    ///
-    /// Output format: `assumptions.<name> = <value>`
-    fn parse_calculate_output(output: &str, var_name: &str) -> Result<f64, String> {
-        let pattern = format!("assumptions.{var_name} = ");
-        for line in output.lines() {
-            if let Some(rest) = line.trim().strip_prefix(&pattern) {
-                return rest
-                    .trim()
-                    .parse::<f64>()
-                    .map_err(|e| format!("Failed to parse value: {e}"));
-            }
-        }
-        Err(format!("Could not find {var_name} in output"))
+    #[test]
+    fn check_export_output_rejects_missing_and_empty_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let xlsx = temp_dir.path().join("test.xlsx");
+        let missing = TestRunner::check_export_output(&xlsx).unwrap_err();
+        assert!(missing.contains("produced no file"));
+
+        fs::write(&xlsx, "").unwrap();
+        let empty = TestRunner::check_export_output(&xlsx).unwrap_err();
+        assert!(empty.contains("produced an empty file"));
+
+        fs::write(&xlsx, "PK").unwrap();
+        assert!(TestRunner::check_export_output(&xlsx).is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_test_reports_export_without_output() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let runner = fake_runner(temp_dir.path(), "exit 0");
+
+        let result = runner.run_test(&sample_case());
+        assert!(result.duration().is_some());
+        let TestResult::Fail { error, .. } = result else {
+            panic!("expected failure, got {result:?}");
+        };
+        assert!(error.unwrap().contains("produced no file"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn stream_all_stops_once_receiver_is_dropped() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log = temp_dir.path().join("calls.log");
+        let mut runner = fake_pipeline(
+            temp_dir.path(),
+            &format!("echo call >> {}; exit 1", log.display()),
+            "exit 1",
+        );
+        runner.test_cases = vec![dependent_case("a.test_one", &[]), sample_case()];
+        let calls = || fs::read_to_string(&log).unwrap_or_default().lines().count();
+
+        // The test in flight finishes, but nothing after it runs
+        let (tx, rx) = mpsc::channel();
+        drop(rx);
+        runner.stream_all(&tx);
+        assert_eq!(calls(), 1);
+
+        let (tx, rx) = mpsc::channel();
+        runner.stream_all(&tx);
+        drop(tx);
+        let results: Vec<_> = rx.into_iter().collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(TestResult::is_fail));
+        assert_eq!(calls(), 3);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn checkpoint_skips_tests_completed_by_an_earlier_run() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log = temp_dir.path().join("calls.log");
+        let path = temp_dir.path().join("run.checkpoint");
+        let pipeline = || {
+            let mut runner = fake_pipeline(
+                temp_dir.path(),
+                &format!("echo call >> {}; exit 1", log.display()),
+                "exit 1",
+            );
+            runner.test_cases = vec![dependent_case("a.test_one", &[]), sample_case()];
+            runner.with_checkpoint(Checkpoint::open(&path).unwrap())
+        };
+        let calls = || fs::read_to_string(&log).unwrap_or_default().lines().count();
+
+        // Interrupted after the first test
+        let (tx, rx) = mpsc::channel();
+        drop(rx);
+        pipeline().stream_all(&tx);
+        assert_eq!(calls(), 1);
+
+        let results = pipeline().run_all();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(TestResult::is_fail));
+        assert_eq!(calls(), 2);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn warm_up_runs_every_mode_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log = temp_dir.path().join("calls.log");
+        let mut runner = fake_pipeline(
+            temp_dir.path(),
+            &format!(r#"echo "$1" >> {}; exit 1"#, log.display()),
+            "exit 1",
+        );
+        runner.test_cases = vec![sample_case()];
+
+        runner.warm_up();
+        let mut calls: Vec<_> = fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        calls.sort();
+        // Normal and batch export; perf calculates
+        assert_eq!(calls, ["calculate", "export", "export"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn passing_skips_reports_only_skips_that_now_pass() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut runner = fake_pipeline(
+            temp_dir.path(),
+            FAKE_FORGE_EXPORT,
+            r#"echo "assumptions.test_result,2" > "$out""#,
+        );
+        let skip = |name: &str, case: Option<TestCase>| SkipCase {
+            name: name.to_string(),
+            reason: "not implemented".to_string(),
+            case,
+            id: None,
+            line: None,
+        };
+        runner.skip_cases = vec![
+            skip("a.test_fixed", Some(sample_case())),
+            skip(
+                "a.test_broken",
+                Some(TestCase {
+                    expected: 3.0,
+                    ..sample_case()
+                }),
+            ),
+            skip("a.test_no_formula", None),
+        ];
+
+        let names: Vec<_> = runner
+            .passing_skips()
+            .iter()
+            .map(|sc| sc.name.as_str())
+            .collect();
+        assert_eq!(names, ["a.test_fixed"]);
+    }
+
+    #[test]
+    fn perf_comparison_uses_category_tolerance() {
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("/nonexistent/ssconvert"));
+        let config = Config {
+            section: "inputs".to_string(),
+            category_tolerances: HashMap::from([("assumptions".to_string(), 0.01)]),
+            ..Config::default()
+        };
+        let runner = TestRunner::without_specs(PathBuf::from("/nonexistent/forge"), engine)
+            .with_config(config)
+            .with_calculator(FixedCalculator(2.005));
+        assert!(runner.run_perf_test(&sample_case()).is_pass());
+
+        let strict = TestCase {
+            tolerance: Some(0.001),
+            ..sample_case()
+        };
+        assert!(runner.run_perf_test(&strict).is_fail());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn scale_applies_to_gnumeric_result() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let runner = fake_pipeline(
+            temp_dir.path(),
+            FAKE_FORGE_EXPORT,
+            r#"echo "assumptions.test_result,0.002" > "$out""#,
+        );
+        let case = TestCase {
+            scale: Some(1000.0),
+            ..sample_case()
+        };
+        assert!(matches!(
+            runner.run_test(&case),
+            TestResult::Pass { actual, .. } if (actual - 2.0).abs() < f64::EPSILON
+        ));
+        assert!(runner.run_test(&sample_case()).is_fail());
+    }
+
+    #[test]
+    fn disputed_expectations_flags_disagreeing_forge_values() {
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("ssconvert"));
+        let mut runner = TestRunner::without_specs(PathBuf::from("forge-demo"), engine)
+            .with_config(Config {
+                section: "inputs".to_string(),
+                ..Config::default()
+            })
+            .with_calculator(FixedCalculator(3.0));
+        runner.test_cases = vec![
+            sample_case(),
+            TestCase {
+                name: "assumptions.test_other".to_string(),
+                formula: "=2+2".to_string(),
+                ..sample_case()
+            },
+        ];
+
+        // `=2+2` can't be calculated, so only the mismatch is reported
+        let disputed = runner.disputed_expectations();
+        assert_eq!(disputed.len(), 1);
+        assert_eq!(disputed[0].0.name, "assumptions.test_sample");
+        assert!((disputed[0].1 - 3.0).abs() < f64::EPSILON);
+
+        let runner = runner.with_calculator(FixedCalculator(2.0));
+        assert!(runner.disputed_expectations().is_empty());
+    }
+
+    #[test]
+    fn name_filter_applies_to_tests_and_skips() {
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("ssconvert"));
+        let mut runner = TestRunner::without_specs(PathBuf::from("forge-demo"), engine);
+        runner.test_cases = vec![
+            dependent_case("math.test_abs", &[]),
+            dependent_case("math.test_sqrt", &[]),
+            dependent_case("text.test_len", &[]),
+        ];
+        runner.skip_cases = ["math.test_rand", "text.test_now"]
+            .into_iter()
+            .map(|name| SkipCase {
+                name: name.to_string(),
+                reason: "volatile".to_string(),
+                case: None,
+                id: None,
+                line: None,
+            })
+            .collect();
+
+        let filter = NameFilter::new(&["math.*".to_string()], &["*sqrt".to_string()]).unwrap();
+        let runner = runner.with_name_filter(&filter);
+
+        let names: Vec<&str> = runner
+            .test_cases()
+            .iter()
+            .map(|tc| tc.name.as_str())
+            .collect();
+        assert_eq!(names, ["math.test_abs"]);
+        assert_eq!(runner.skip_cases.len(), 1);
+        assert_eq!(runner.skip_cases[0].name, "math.test_rand");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn annotate_forge_notes_disagreeing_passes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ssconvert = r#"echo "inputs.test_result,2" > "$out""#;
+        let runner =
+            fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, ssconvert).with_config(Config {
+                section: "inputs".to_string(),
+                annotate_forge: true,
+                ..Config::default()
+            });
+
+        let runner = runner.with_calculator(FixedCalculator(2.0));
+        let result = runner.run_test(&sample_case());
+        assert!(matches!(result, TestResult::Pass { forge: None, .. }));
+
+        // Gnumeric still decides the outcome
+        let runner = runner.with_calculator(FixedCalculator(3.0));
+        let result = runner.run_test(&sample_case());
+        assert!(matches!(
+            result,
+            TestResult::Pass { forge: Some(f), .. } if (f - 3.0).abs() < f64::EPSILON
+        ));
+
+        // ULP tests agree within their ULPs, not the absolute tolerance
+        let ulps_case = TestCase {
+            tolerance_ulps: Some(4),
+            ..sample_case()
+        };
+        let runner = runner.with_calculator(FixedCalculator(2.0_f64.next_up()));
+        let result = runner.run_test(&ulps_case);
+        assert!(matches!(result, TestResult::Pass { forge: None, .. }));
+        let result = runner.run_test(&sample_case());
+        assert!(matches!(result, TestResult::Pass { forge: Some(_), .. }));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn no_heuristic_match_fails_unlabeled_results() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ssconvert = r#"echo "value,2" > "$out""#;
+        let runner = fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, ssconvert);
+        assert!(runner.run_test(&sample_case()).is_heuristic_pass());
+
+        let runner = runner.with_config(Config {
+            heuristic_match: false,
+            ..Config::default()
+        });
+        let result = runner.run_test(&sample_case());
+        assert!(matches!(
+            result,
+            TestResult::Fail { error: Some(e), .. } if e.starts_with("No labeled result cell found")
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn ssh_transport_runs_export_remotely() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let log = dir.join("ssh.log");
+        let ssh = fake_binary(
+            dir,
+            "ssh",
+            &format!(
+                r#"shift 2; echo "$1" >> {}; exec sh -c "$1""#,
+                log.display()
+            ),
+        );
+        let scp = fake_binary(dir, "scp", r#"shift; cp "${1#build:}" "${2#build:}""#);
+        let ssconvert = r#"echo "assumptions.test_result,2" > "$out""#;
+
+        let runner = fake_pipeline(dir, FAKE_FORGE_EXPORT, ssconvert)
+            .with_transport(SshTransport::new("build".to_string()).with_programs(ssh, scp));
+        assert!(runner.run_test(&sample_case()).is_pass());
+
+        // Staging dir, export inside it, cleanup
+        let commands = fs::read_to_string(&log).unwrap();
+        let commands: Vec<&str> = commands.lines().collect();
+        assert_eq!(commands.len(), 3, "{commands:?}");
+        assert_eq!(commands[0], "mktemp -d");
+        assert!(
+            commands[1].contains("/forge-demo' 'export' '"),
+            "{}",
+            commands[1]
+        );
+        assert!(commands[2].starts_with("rm -rf "));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn show_csv_on_fail_includes_csv_excerpt() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ssconvert = r#"{ echo "label,value"; seq 101 130 | sed 's/^/x,/'; } > "$out""#;
+
+        let runner = fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, ssconvert);
+        let TestResult::Fail { error, .. } = runner.run_test(&sample_case()) else {
+            panic!("expected failure");
+        };
+        assert_eq!(
+            error.as_deref(),
+            Some("Could not find result in CSV output")
+        );
+
+        let runner =
+            fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, ssconvert).with_config(Config {
+                show_csv_on_fail: true,
+                ..Config::default()
+            });
+        let TestResult::Fail { error, .. } = runner.run_test(&sample_case()) else {
+            panic!("expected failure");
+        };
+        let error = error.unwrap();
+        assert!(
+            error.contains("\n--- test.Scalars.csv ---\nlabel,value\nx,101\n"),
+            "{error}"
+        );
+        assert!(error.contains("\nx,119\n... (11 more lines)"), "{error}");
+        assert!(!error.contains("x,120"), "{error}");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn recalc_strategy_controls_cached_values() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let runner = fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, FAKE_SSCONVERT_STALE);
+        assert!(runner.run_test(&sample_case()).is_pass());
+
+        let runner = fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, FAKE_SSCONVERT_STALE)
+            .with_config(Config {
+                recalc: RecalcStrategy::Never,
+                ..Config::default()
+            });
+        let result = runner.run_test(&sample_case());
+        assert!(matches!(result, TestResult::Fail { actual: Some(a), .. } if a == 0.0));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn recalc_compare_flags_stale_cached_values() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let runner = fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, FAKE_SSCONVERT_STALE)
+            .with_config(Config {
+                recalc: RecalcStrategy::Compare,
+                ..Config::default()
+            });
+
+        let result = runner.run_test(&sample_case());
+        let TestResult::Fail { error, .. } = result else {
+            panic!("expected failure, got {result:?}");
+        };
+        assert!(error.unwrap().contains("Cached value 0 differs"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn recalc_compare_matches_cache_within_tolerance() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let compare = Config {
+            recalc: RecalcStrategy::Compare,
+            ..Config::default()
+        };
+
+        let runner = fake_pipeline(
+            temp_dir.path(),
+            FAKE_FORGE_EXPORT,
+            r#"echo "assumptions.test_result,inf" > "$out""#,
+        )
+        .with_config(compare.clone());
+        let infinite = TestCase {
+            formula: "=1/0".to_string(),
+            expected: f64::INFINITY,
+            ..sample_case()
+        };
+        assert!(runner.run_test(&infinite).is_pass());
+
+        let runner = fake_pipeline(
+            temp_dir.path(),
+            FAKE_FORGE_EXPORT,
+            r#"if [ $recalc = 1 ]; then v=2; else v=2.0001; fi
+echo "assumptions.test_result,$v" > "$out""#,
+        )
+        .with_config(compare);
+        let loose = TestCase {
+            tolerance: Some(0.001),
+            ..sample_case()
+        };
+        assert!(runner.run_test(&loose).is_pass());
+        assert!(runner.run_test(&sample_case()).is_fail());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn profile_times_each_pipeline_phase() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let runner = fake_pipeline(
+            temp_dir.path(),
+            FAKE_FORGE_EXPORT,
+            r#"echo "assumptions.test_result,2" > "$out""#,
+        )
+        .with_profile(Profile::default());
+        assert!(runner.run_test(&sample_case()).is_pass());
+
+        let phases: Vec<_> = runner
+            .profile()
+            .unwrap()
+            .breakdown()
+            .into_iter()
+            .map(|(phase, _, calls)| (phase, calls))
+            .collect();
+        assert_eq!(
+            phases,
+            [
+                (Phase::Export, 1),
+                (Phase::Convert, 1),
+                (Phase::Parse, 1),
+                (Phase::Compare, 1)
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn compare_formulas_flags_wrong_exported_formula() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let exported = temp_dir.path().join("exported.xlsx");
+        let forge = format!(r#"[ "$1" = export ] && cp "{}" "$3""#, exported.display());
+        let ssconvert = r#"echo "assumptions.test_result,2" > "$out""#;
+        let config = Config {
+            compare_formulas: true,
+            ..Config::default()
+        };
+
+        excel::create_formula_xlsx(&exported, "=1 + 1", "2").unwrap();
+        let runner = fake_pipeline(temp_dir.path(), &forge, ssconvert).with_config(config);
+        assert!(runner.run_test(&sample_case()).is_pass());
+
+        // Right value, wrong formula
+        excel::create_formula_xlsx(&exported, "=4/2", "2").unwrap();
+        let result = runner.run_test(&sample_case());
+        let TestResult::Fail { actual, error, .. } = result else {
+            panic!("expected failure, got {result:?}");
+        };
+        assert_eq!(actual, Some(2.0));
+        assert!(error.unwrap().contains("exported =4/2 for =1+1"));
+
+        let runner = fake_pipeline(temp_dir.path(), &forge, ssconvert);
+        assert!(runner.run_test(&sample_case()).is_pass());
+    }
+
+    #[test]
+    fn exported_formula_is_found_by_its_label() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let xlsx = temp_dir.path().join("exported.xlsx");
+        let write = |label: &str| {
+            let mut workbook = rust_xlsxwriter::Workbook::new();
+            let sheet = workbook.add_worksheet();
+            sheet.write(0, 0, "assumptions.input").unwrap();
+            sheet
+                .write_formula(0, 1, rust_xlsxwriter::Formula::new("=3-1"))
+                .unwrap();
+            sheet.write(1, 0, label).unwrap();
+            sheet
+                .write_formula(1, 1, rust_xlsxwriter::Formula::new("=B1"))
+                .unwrap();
+            workbook.save(&xlsx).unwrap();
+        };
+        let case = TestCase {
+            formula: "=B1".to_string(),
+            ..sample_case()
+        };
+
+        write("assumptions.test_result");
+        assert_eq!(TestRunner::check_exported_formula(&xlsx, &case), Ok(()));
+        let err = TestRunner::check_exported_formula(&xlsx, &sample_case()).unwrap_err();
+        assert!(err.contains("exported =B1 for =1+1"), "{err}");
+
+        write("assumptions.other");
+        let err = TestRunner::check_exported_formula(&xlsx, &case).unwrap_err();
+        assert!(err.contains("not checked"), "{err}");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn self_test_runs_known_formula_through_pipeline() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let runner = fake_pipeline(
+            temp_dir.path(),
+            FAKE_FORGE_EXPORT,
+            r#"echo "assumptions.test_result,2" > "$out""#,
+        );
+        assert!(runner.self_test().is_pass());
+
+        let broken = fake_pipeline(temp_dir.path(), "exit 1", "exit 1");
+        assert!(broken.self_test().is_fail());
     }
 
-    /// Runs all perf tests in parallel using rayon.
-    ///
-    /// Tests formula calculation via `forge calculate` concurrently.
-    /// Returns results in the same order as test cases.
-    pub fn run_perf_parallel(&self) -> Vec<TestResult> {
-        // Skip results first (not parallelized - usually just one)
-        let mut results: Vec<TestResult> = self
-            .skip_cases
-            .iter()
-            .map(|sc| TestResult::Skip {
-                name: sc.name.clone(),
-                reason: sc.reason.clone(),
+    #[test]
+    #[cfg(unix)]
+    fn run_batch_splits_cases_into_batches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let calls = temp_dir.path().join("calls.log");
+        let script = format!(
+            r#"for t in $(grep -o '^  test_[0-9]*' "$(dirname "$out")/batch.yaml"); do echo "$t,2"; done > "$out"
+echo call >> "{}""#,
+            calls.display()
+        );
+        let mut runner =
+            fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, &script).with_config(Config {
+                batch_size: NonZeroUsize::new(2),
+                ..Config::default()
+            });
+        runner.test_cases = (0..5)
+            .map(|i| TestCase {
+                name: format!("assumptions.test_{i}"),
+                ..sample_case()
             })
             .collect();
 
-        // Run all test cases in parallel
-        let parallel_results: Vec<TestResult> = self
-            .test_cases
-            .par_iter()
-            .map(|tc| self.run_perf_test(tc))
-            .collect();
-
-        results.extend(parallel_results);
-        results
+        let results = runner.run_batch();
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(TestResult::is_pass));
+        let names: Vec<_> = results.iter().map(TestResult::name).collect();
+        assert_eq!(names[0], "assumptions.test_0");
+        assert_eq!(names[4], "assumptions.test_4");
+        assert_eq!(fs::read_to_string(&calls).unwrap().lines().count(), 3);
     }
 
-    /// Runs a single test case.
-    ///
-    /// Creates a temporary YAML file with the formula, runs forge-demo export,
-    /// converts to CSV using the spreadsheet engine, and compares results.
-    pub fn run_test(&self, test_case: &TestCase) -> TestResult {
-        // Create a minimal YAML with just this test
-        // Escape double quotes in formula for YAML compatibility
-        let escaped_formula = test_case.formula.replace('"', "\\\"");
-        let yaml_content = format!(
-            r#"_forge_version: "1.0.0"
-assumptions:
-  test_result:
-    value: null
-    formula: "{escaped_formula}"
-"#
+    #[test]
+    #[cfg(unix)]
+    fn outputs_of_one_test_share_an_export() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log = temp_dir.path().join("calls.log");
+        let forge = format!("echo call >> {}; {FAKE_FORGE_EXPORT}", log.display());
+        let ssconvert = r#"case "$out" in
+*.Scalars.csv) echo "assumptions.test_result,2" > "$out" ;;
+*) for t in $(grep -o '^  test_[0-9]*' "$(dirname "$out")/batch.yaml"); do echo "$t,2"; done > "$out" ;;
+esac"#;
+        let mut runner = fake_pipeline(temp_dir.path(), &forge, ssconvert);
+        let output = |name: &str| TestCase {
+            name: format!("assumptions.test_pnl.{name}"),
+            output_of: Some("assumptions.test_pnl".to_string()),
+            ..sample_case()
+        };
+        runner.test_cases = vec![output("gross"), output("net"), sample_case()];
+
+        let results = runner.run_all();
+        let names: Vec<&str> = results.iter().map(TestResult::name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "assumptions.test_pnl.gross",
+                "assumptions.test_pnl.net",
+                "assumptions.test_sample"
+            ]
         );
+        assert!(results.iter().all(TestResult::is_pass));
+        // One export for both outputs, one for the plain test
+        assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 2);
+    }
 
-        let temp_dir = match tempfile::tempdir() {
-            Ok(d) => d,
-            Err(e) => {
-                return TestResult::Fail {
-                    name: test_case.name.clone(),
-                    formula: test_case.formula.clone(),
-                    expected: test_case.expected,
-                    actual: None,
-                    error: Some(format!("Failed to create temp dir: {e}")),
-                };
-            }
+    #[test]
+    #[cfg(unix)]
+    fn outputs_get_the_checks_of_a_single_test() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log = temp_dir.path().join("calls.log");
+        let forge = format!("echo call >> {}; {FAKE_FORGE_EXPORT}", log.display());
+        // The shared CSV lacks the second output, found alone by its label
+        let ssconvert = r#"case "$out" in
+*.Scalars.csv) echo "assumptions.test_result,2" > "$out" ;;
+*) echo "test_0,1:30:00" > "$out" ;;
+esac"#;
+        let mut runner = fake_pipeline(temp_dir.path(), &forge, ssconvert);
+        let output = |name: &str| TestCase {
+            name: format!("assumptions.test_shift.{name}"),
+            output_of: Some("assumptions.test_shift".to_string()),
+            ..sample_case()
         };
+        let length = TestCase {
+            formula: "=TIME(1,30,0)".to_string(),
+            expected: 1.5 / 24.0,
+            duration: true,
+            ..output("length")
+        };
+        runner.test_cases = vec![length, output("net")];
+
+        let results = runner.run_all();
+        assert!(results.iter().all(TestResult::is_pass), "{results:?}");
+        assert!(results.iter().all(|r| r.duration().is_some()));
+        // The shared export, then one for the missing output
+        assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 2);
+
+        // A cached value check reads each output's own export
+        fs::remove_file(&log).unwrap();
+        runner.test_cases = vec![output("gross"), output("net")];
+        let runner = runner.with_config(Config {
+            recalc: RecalcStrategy::Compare,
+            ..Config::default()
+        });
+        let results = runner.run_all();
+        assert!(results.iter().all(TestResult::is_pass), "{results:?}");
+        assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 2);
+    }
 
-        let yaml_path = temp_dir.path().join("test.yaml");
-        let xlsx_path = temp_dir.path().join("test.xlsx");
-
-        // Write YAML
-        if let Err(e) = fs::write(&yaml_path, &yaml_content) {
-            return TestResult::Fail {
-                name: test_case.name.clone(),
-                formula: test_case.formula.clone(),
-                expected: test_case.expected,
-                actual: None,
-                error: Some(format!("Failed to write YAML: {e}")),
+    #[test]
+    #[cfg(unix)]
+    fn expect_export_error_passes_only_on_rejection() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let forge = format!(r#"grep -q 'BAD(' "$2" && exit 1; {FAKE_FORGE_EXPORT}"#);
+        let ssconvert = r#"case "$out" in
+*.Scalars.csv) echo "assumptions.test_result,2" > "$out" ;;
+*) for t in $(grep -o '^  test_[0-9]*' "$(dirname "$out")/batch.yaml"); do echo "$t,2"; done > "$out" ;;
+esac"#;
+        let mut runner = fake_pipeline(temp_dir.path(), &forge, ssconvert);
+        let rejected = |name: &str, formula: &str| TestCase {
+            name: format!("assumptions.{name}"),
+            formula: formula.to_string(),
+            expected: f64::NAN,
+            expect_export_error: true,
+            ..sample_case()
+        };
+        runner.test_cases = vec![
+            rejected("test_bad", "=BAD(1)"),
+            sample_case(),
+            rejected("test_valid", "=1+1"),
+        ];
+
+        // Batch mode runs the rejection tests alone and batches the rest
+        for results in [runner.run_all(), runner.run_batch()] {
+            assert!(results[0].is_pass(), "{:?}", results[0]);
+            assert!(results[1].is_pass(), "{:?}", results[1]);
+            let TestResult::Fail { error, .. } = &results[2] else {
+                panic!("expected failure, got {:?}", results[2]);
             };
+            assert!(error.as_deref().unwrap().contains("should be rejected"));
         }
+    }
 
-        // Run forge-demo export
-        let output = match Command::new(&self.forge_binary)
-            .arg("export")
-            .arg(&yaml_path)
-            .arg(&xlsx_path)
-            .output()
-        {
-            Ok(o) => o,
-            Err(e) => {
-                return TestResult::Fail {
-                    name: test_case.name.clone(),
-                    formula: test_case.formula.clone(),
-                    expected: test_case.expected,
-                    actual: None,
-                    error: Some(format!("Failed to run forge-demo: {e}")),
-                };
-            }
+    #[test]
+    fn expect_export_error_fails_without_forge_binary() {
+        let engine = SpreadsheetEngine::with_path(PathBuf::from("ssconvert"));
+        let runner = TestRunner::without_specs(PathBuf::from("/nonexistent/forge"), engine);
+        let case = TestCase {
+            expected: f64::NAN,
+            expect_export_error: true,
+            ..sample_case()
         };
-
-        if !output.status.success() {
-            return TestResult::Fail {
-                name: test_case.name.clone(),
-                formula: test_case.formula.clone(),
-                expected: test_case.expected,
-                actual: None,
-                error: Some(format!(
-                    "forge-demo export failed: {}",
-                    String::from_utf8_lossy(&output.stderr)
-                )),
+        for result in [runner.run_test(&case), runner.run_perf_test(&case)] {
+            let TestResult::Fail { error, .. } = &result else {
+                panic!("expected failure, got {result:?}");
             };
+            assert!(error.as_deref().unwrap().contains("Failed to run forge"));
         }
+    }
 
-        // Convert XLSX to CSV using spreadsheet engine
-        let csv_path = match self.engine.xlsx_to_csv(&xlsx_path, temp_dir.path()) {
-            Ok(p) => p,
-            Err(e) => {
-                return TestResult::Fail {
-                    name: test_case.name.clone(),
-                    formula: test_case.formula.clone(),
-                    expected: test_case.expected,
-                    actual: None,
-                    error: Some(format!("CSV conversion failed: {e}")),
-                };
-            }
-        };
+    #[test]
+    #[cfg(unix)]
+    fn run_batch_respects_convert_jobs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let busy = temp_dir.path().join("converting");
+        let overlaps = temp_dir.path().join("overlaps.log");
+        // mkdir is atomic: it fails while another conversion holds the dir
+        let script = format!(
+            r#"mkdir "{busy}" 2>/dev/null || echo overlap >> "{overlaps}"
+sleep 0.1
+for t in $(grep -o '^  test_[0-9]*' "$(dirname "$out")/batch.yaml"); do echo "$t,2"; done > "$out"
+rmdir "{busy}" 2>/dev/null; true"#,
+            busy = busy.display(),
+            overlaps = overlaps.display()
+        );
+        let mut runner =
+            fake_pipeline(temp_dir.path(), FAKE_FORGE_EXPORT, &script).with_config(Config {
+                batch_size: NonZeroUsize::new(1),
+                export_jobs: NonZeroUsize::new(4),
+                convert_jobs: NonZeroUsize::new(1),
+                ..Config::default()
+            });
+        runner.test_cases = (0..4)
+            .map(|i| TestCase {
+                name: format!("assumptions.test_{i}"),
+                ..sample_case()
+            })
+            .collect();
 
-        // Parse CSV and find result
-        match Self::find_result_in_csv(&csv_path, test_case.expected) {
-            Ok(actual) => {
-                if (actual - test_case.expected).abs() < f64::EPSILON {
-                    TestResult::Pass {
-                        name: test_case.name.clone(),
-                        formula: test_case.formula.clone(),
-                        expected: test_case.expected,
-                        actual,
-                    }
-                } else {
-                    TestResult::Fail {
-                        name: test_case.name.clone(),
-                        formula: test_case.formula.clone(),
-                        expected: test_case.expected,
-                        actual: Some(actual),
-                        error: None,
-                    }
-                }
-            }
-            Err(e) => TestResult::Fail {
-                name: test_case.name.clone(),
-                formula: test_case.formula.clone(),
-                expected: test_case.expected,
-                actual: None,
-                error: Some(e),
-            },
-        }
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let results = pool.install(|| runner.run_batch());
+        assert!(results.iter().all(TestResult::is_pass));
+        assert!(!overlaps.exists(), "conversions overlapped");
     }
 
-    /// Finds the result value in CSV output.
-    ///
-    /// Looks for labeled results ("result" or `test_result`) or matches
-    /// numeric values against the expected value.
-    fn find_result_in_csv(csv_path: &Path, expected: f64) -> Result<f64, String> {
-        let file = fs::File::open(csv_path).map_err(|e| format!("Failed to open CSV: {e}"))?;
-        let reader = BufReader::new(file);
-
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("Failed to read line: {e}"))?;
-            // Simple CSV parsing
-            let cells: Vec<&str> = line
-                .split(',')
-                .map(|s| s.trim_matches('"').trim())
-                .collect();
-
-            for (i, cell) in cells.iter().enumerate() {
-                // Look for "result" or "test_result" label followed by value
-                if (*cell == "result" || *cell == "test_result") && i + 1 < cells.len() {
-                    if let Ok(value) = cells[i + 1].replace(',', "").parse::<f64>() {
-                        return Ok(value);
-                    }
-                }
+    #[test]
+    fn find_result_prefers_labeled_cell_in_any_sheet() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let inputs = temp_dir.path().join("test.Inputs.csv");
+        let scalars = temp_dir.path().join("test.Scalars.csv");
+        fs::write(&inputs, "name,value\ninput,42\n").unwrap();
+        fs::write(&scalars, "name,value\nassumptions.test_result,41\n").unwrap();
+
+        let found = TestRunner::find_result_in_csv(
+            &[inputs, scalars],
+            None,
+            None,
+            true,
+            parse_number,
+            |v| (v - 42.0).abs() < 0.0001,
+        )
+        .unwrap();
+        assert!((found.value - 41.0).abs() < f64::EPSILON);
+        assert!(!found.heuristic);
+    }
 
-                // Also try parsing any numeric value
-                if let Ok(value) = cell.replace(',', "").parse::<f64>() {
-                    // Check if it matches expected (for simple formulas)
-                    if (value - expected).abs() < 0.0001 {
-                        return Ok(value);
-                    }
-                }
-            }
-        }
+    #[test]
+    fn find_result_ignores_input_sharing_expected_count() {
+        // =COUNT(1,2,3) is 3; a stray input of 3 must not be mistaken for it
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("test.Scalars.csv");
+        fs::write(
+            &csv,
+            "name,value
+inputs.n,3
+assumptions.test_result,2
+",
+        )
+        .unwrap();
+
+        let found = TestRunner::find_result_in_csv(&[csv], None, None, true, parse_number, |v| {
+            (v - 3.0).abs() < 0.0001
+        })
+        .unwrap();
+        assert!((found.value - 2.0).abs() < f64::EPSILON);
+        assert!(!found.heuristic);
+    }
 
-        Err("Could not find result in CSV output".to_string())
+    #[test]
+    fn find_result_does_not_fall_back_when_label_is_not_numeric() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("test.Scalars.csv");
+        fs::write(
+            &csv,
+            "name,value
+inputs.n,3
+assumptions.test_result,#VALUE!
+",
+        )
+        .unwrap();
+
+        let err = TestRunner::find_result_in_csv(&[csv], None, None, true, parse_number, |v| {
+            (v - 3.0).abs() < 0.0001
+        })
+        .unwrap_err();
+        assert!(err.contains("#VALUE!"), "{err}");
     }
-}
 
-// ─────────────────────────────────────────────────────────────────────────────
-// Tests
-// ─────────────────────────────────────────────────────────────────────────────
+    #[test]
+    fn find_result_ignores_index_column() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("test.Inputs.csv");
+        fs::write(&csv, "1,inputs.a,5\n2,inputs.b,7\n").unwrap();
+
+        // The index 1 is not a result, even though it matches
+        let err = TestRunner::find_result_in_csv(
+            std::slice::from_ref(&csv),
+            None,
+            None,
+            true,
+            parse_number,
+            |v| (v - 1.0).abs() < 0.0001,
+        )
+        .unwrap_err();
+        assert_eq!(err, "Could not find result in CSV output");
+        let found = TestRunner::find_result_in_csv(&[csv], None, None, true, parse_number, |v| {
+            (v - 7.0).abs() < 0.0001
+        })
+        .unwrap();
+        assert!((found.value - 7.0).abs() < f64::EPSILON);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn find_result_reads_value_after_label_columns() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("test.Scalars.csv");
+        fs::write(&csv, "assumptions.test_result,sum of inputs,2\n").unwrap();
+
+        let err = TestRunner::find_result_in_csv(
+            std::slice::from_ref(&csv),
+            None,
+            None,
+            true,
+            parse_number,
+            |_| true,
+        )
+        .unwrap_err();
+        assert!(err.contains("not numeric"));
+        let found =
+            TestRunner::find_result_in_csv(&[csv], None, Some(2), true, parse_number, |_| false)
+                .unwrap();
+        assert!((found.value - 2.0).abs() < f64::EPSILON);
+        assert!(!found.heuristic);
+    }
 
     #[test]
-    fn load_empty_dir_returns_empty_cases() {
+    fn find_result_falls_back_to_matching_value() {
         let temp_dir = tempfile::tempdir().unwrap();
-        let result = TestRunner::load_test_cases(temp_dir.path());
-        assert!(result.is_ok());
-        let (cases, skips) = result.unwrap();
-        assert!(cases.is_empty());
-        assert!(skips.is_empty());
+        let csv = temp_dir.path().join("test.Sheet1.csv");
+        fs::write(&csv, "7,42\n").unwrap();
+
+        let found = TestRunner::find_result_in_csv(&[csv], None, None, true, parse_number, |v| {
+            (v - 42.0).abs() < 0.0001
+        })
+        .unwrap();
+        assert!((found.value - 42.0).abs() < f64::EPSILON);
+        assert!(found.heuristic);
     }
 
     #[test]
-    fn load_nonexistent_dir_returns_error() {
-        let result = TestRunner::load_test_cases(Path::new("/nonexistent/path"));
-        assert!(result.is_err());
+    fn find_result_errors_when_nothing_matches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let csv = temp_dir.path().join("test.Sheet1.csv");
+        fs::write(&csv, "7,8\n").unwrap();
+
+        assert!(
+            TestRunner::find_result_in_csv(&[csv], None, None, true, parse_number, |v| (v - 42.0)
+                .abs()
+                < 0.0001)
+            .is_err()
+        );
     }
 
     #[test]
-    fn load_dir_with_yaml_files() {
+    fn find_result_reads_text_results_in_test_base() {
         let temp_dir = tempfile::tempdir().unwrap();
-        let yaml_content = r#"
-_forge_version: "1.0.0"
-assumptions:
-  test_one:
-    value: null
-    formula: "=1+1"
-    expected: 2
-"#;
-        fs::write(temp_dir.path().join("test.yaml"), yaml_content).unwrap();
+        let csv = temp_dir.path().join("test.Sheet1.csv");
+        fs::write(&csv, "inputs.test_result,FF\n").unwrap();
+        let case = TestCase {
+            base: Some(16),
+            ..sample_case()
+        };
 
-        let result = TestRunner::load_test_cases(temp_dir.path());
-        assert!(result.is_ok());
-        let (cases, _) = result.unwrap();
-        assert_eq!(cases.len(), 1);
+        let found = TestRunner::find_result_in_csv(
+            std::slice::from_ref(&csv),
+            None,
+            None,
+            true,
+            |cell| case.parse_actual(cell),
+            |_| false,
+        )
+        .unwrap();
+        assert!((found.value - 255.0).abs() < f64::EPSILON);
+        let err = TestRunner::find_result_in_csv(&[csv], None, None, true, parse_number, |_| false)
+            .unwrap_err();
+        assert!(err.contains("not numeric"));
     }
 
     #[test]
-    fn load_ignores_non_yaml_files() {
+    fn find_result_reads_explicit_result_cell() {
         let temp_dir = tempfile::tempdir().unwrap();
-        fs::write(temp_dir.path().join("readme.txt"), "not yaml").unwrap();
-        fs::write(temp_dir.path().join("config.json"), "{}").unwrap();
+        let inputs = temp_dir.path().join("test.Inputs.csv");
+        let scalars = temp_dir.path().join("test.Scalars.csv");
+        fs::write(
+            &inputs,
+            "name,value
+input,42
+",
+        )
+        .unwrap();
+        fs::write(
+            &scalars,
+            "name,value
+assumptions.test_result,41
+",
+        )
+        .unwrap();
+        let paths = [inputs, scalars];
+        let find = |cell| {
+            TestRunner::find_result_in_csv(&paths, Some(cell), None, true, parse_number, |_| true)
+        };
 
-        let result = TestRunner::load_test_cases(temp_dir.path());
-        assert!(result.is_ok());
-        let (cases, _) = result.unwrap();
-        assert!(cases.is_empty());
+        // The cell wins over both the label and matching values
+        let found = find("B2").unwrap();
+        assert!((found.value - 42.0).abs() < f64::EPSILON);
+        assert!(!found.heuristic);
+        let found = find("Scalars!$B$2").unwrap();
+        assert!((found.value - 41.0).abs() < f64::EPSILON);
+
+        assert!(find("A2").unwrap_err().contains("not numeric"));
+        assert!(find("C9").unwrap_err().contains("is empty"));
+        assert!(find("Missing!B2").unwrap_err().contains("is empty"));
+        assert!(find("2B").unwrap_err().contains("Invalid result_cell"));
     }
 }
 
@@ -694,10 +3409,10 @@ assumptions:
 #[cfg(test)]
 mod import_export_tests {
     use std::fs;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use std::process::Command;
 
-    use crate::excel;
+    use crate::excel::{self, ImportedEntry};
 
     /// Gets the forge-demo binary path (from bin/ or target/).
     fn forge_demo_binary() -> Option<PathBuf> {
@@ -724,6 +3439,90 @@ mod import_export_tests {
         forge_demo_binary()
     }
 
+    /// Runs `forge-demo import xlsx yaml`, asserts it succeeded, and returns the YAML.
+    fn import(binary: &Path, xlsx: &Path, yaml: &Path) -> String {
+        let output = Command::new(binary)
+            .arg("import")
+            .arg(xlsx)
+            .arg(yaml)
+            .output()
+            .expect("Failed to run forge-demo import");
+        assert!(
+            output.status.success(),
+            "Import failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        fs::read_to_string(yaml).expect("YAML file was not created")
+    }
+
+    /// Asserts imported `yaml` parses as a spec holding every `expected` entry.
+    fn assert_import(yaml: &str, expected: &[(&str, &str, ImportedEntry)]) {
+        let mismatches = excel::validate_import(yaml, expected).unwrap();
+        assert!(
+            mismatches.is_empty(),
+            "Import differs from the workbook:\n{}\n\n{yaml}",
+            mismatches
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
+    /// Expected import of [`excel::create_test_scalars_xlsx`].
+    fn scalars_expectation() -> Vec<(&'static str, &'static str, ImportedEntry)> {
+        vec![
+            ("scalars", "revenue", ImportedEntry::Value(100_000.0)),
+            ("scalars", "costs", ImportedEntry::Value(40_000.0)),
+            ("scalars", "profit", ImportedEntry::Formula),
+            ("scalars", "margin", ImportedEntry::Formula),
+        ]
+    }
+
+    fn strings(values: &[&str]) -> ImportedEntry {
+        ImportedEntry::Strings(values.iter().map(ToString::to_string).collect())
+    }
+
+    /// Directory of golden `forge-demo import` outputs.
+    const GOLDEN_DIR: &str = "tests/golden/import";
+
+    /// Environment variable that rewrites the goldens from the current
+    /// forge-demo's output instead of comparing against them.
+    const BLESS_ENV: &str = "FORGE_E2E_BLESS";
+
+    /// Asserts imported `yaml` matches `tests/golden/import/<name>.yaml`,
+    /// ignoring key order and number formatting.
+    ///
+    /// A missing golden fails the test; run with [`BLESS_ENV`] set to
+    /// record it.
+    fn assert_matches_golden(yaml: &str, name: &str) {
+        let path = Path::new(GOLDEN_DIR).join(format!("{name}.yaml"));
+        if std::env::var_os(BLESS_ENV).is_some() {
+            fs::create_dir_all(GOLDEN_DIR).unwrap();
+            fs::write(&path, yaml).unwrap();
+            return;
+        }
+        let Ok(golden) = fs::read_to_string(&path) else {
+            panic!(
+                "No golden at {}; run with {BLESS_ENV}=1 to record one",
+                path.display()
+            );
+        };
+        let differences = excel::golden_differences(yaml, &golden).unwrap();
+        assert!(
+            differences.is_empty(),
+            "Import differs from {} ({} differences, first shown):\n{}",
+            path.display(),
+            differences.len(),
+            differences
+                .iter()
+                .take(10)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Import Tests
     // ─────────────────────────────────────────────────────────────────────────
@@ -742,29 +3541,9 @@ mod import_export_tests {
         // Create test Excel file
         excel::create_test_scalars_xlsx(&xlsx_path).unwrap();
 
-        // Run import
-        let output = Command::new(&binary)
-            .arg("import")
-            .arg(&xlsx_path)
-            .arg(&yaml_path)
-            .output()
-            .expect("Failed to run forge-demo import");
-
-        // Check success
-        assert!(
-            output.status.success(),
-            "Import failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        assert!(yaml_path.exists(), "YAML file was not created");
-
-        // Verify YAML content has some data
-        let yaml_content = fs::read_to_string(&yaml_path).unwrap();
-        // Should have some content from the Excel file
-        assert!(
-            yaml_content.len() > 20,
-            "YAML should have meaningful content"
-        );
+        let yaml_content = import(&binary, &xlsx_path, &yaml_path);
+        assert_import(&yaml_content, &scalars_expectation());
+        assert_matches_golden(&yaml_content, "scalars");
     }
 
     #[test]
@@ -781,23 +3560,29 @@ mod import_export_tests {
         // Create test Excel file with table data
         excel::create_test_table_xlsx(&xlsx_path).unwrap();
 
-        // Run import
-        let output = Command::new(&binary)
-            .arg("import")
-            .arg(&xlsx_path)
-            .arg(&yaml_path)
-            .output()
-            .expect("Failed to run forge-demo import");
-
-        assert!(
-            output.status.success(),
-            "Import failed: {}",
-            String::from_utf8_lossy(&output.stderr)
+        // The computed `profit` column's representation is forge-demo's choice
+        let yaml_content = import(&binary, &xlsx_path, &yaml_path);
+        assert_import(
+            &yaml_content,
+            &[
+                (
+                    "quarterlydata",
+                    "quarter",
+                    strings(&["Q1", "Q2", "Q3", "Q4"]),
+                ),
+                (
+                    "quarterlydata",
+                    "revenue",
+                    ImportedEntry::Numbers(vec![100_000.0, 120_000.0, 130_000.0, 150_000.0]),
+                ),
+                (
+                    "quarterlydata",
+                    "costs",
+                    ImportedEntry::Numbers(vec![40_000.0, 48_000.0, 52_000.0, 60_000.0]),
+                ),
+            ],
         );
-        assert!(yaml_path.exists());
-
-        let yaml_content = fs::read_to_string(&yaml_path).unwrap();
-        assert!(yaml_content.contains("quarter"));
+        assert_matches_golden(&yaml_content, "table");
     }
 
     #[test]
@@ -814,20 +3599,21 @@ mod import_export_tests {
         // Create test Excel file with multiple sheets
         excel::create_multi_sheet_xlsx(&xlsx_path).unwrap();
 
-        // Run import
-        let output = Command::new(&binary)
-            .arg("import")
-            .arg(&xlsx_path)
-            .arg(&yaml_path)
-            .output()
-            .expect("Failed to run forge-demo import");
-
-        assert!(
-            output.status.success(),
-            "Import failed: {}",
-            String::from_utf8_lossy(&output.stderr)
+        let yaml_content = import(&binary, &xlsx_path, &yaml_path);
+        assert_import(
+            &yaml_content,
+            &[
+                ("scalars", "tax_rate", ImportedEntry::Value(0.25)),
+                ("scalars", "growth_rate", ImportedEntry::Value(0.1)),
+                ("revenue", "month", strings(&["Jan", "Feb", "Mar"])),
+                (
+                    "revenue",
+                    "amount",
+                    ImportedEntry::Numbers(vec![10_000.0, 12_000.0, 15_000.0]),
+                ),
+            ],
         );
-        assert!(yaml_path.exists());
+        assert_matches_golden(&yaml_content, "multi_sheet");
     }
 
     // ─────────────────────────────────────────────────────────────────────────
@@ -934,25 +3720,9 @@ scalars:
         // Create test Excel file
         excel::create_test_scalars_xlsx(&original_xlsx).unwrap();
 
-        // Import to YAML
-        let import_output = Command::new(&binary)
-            .arg("import")
-            .arg(&original_xlsx)
-            .arg(&yaml_path)
-            .output()
-            .expect("Failed to import");
-
-        assert!(
-            import_output.status.success(),
-            "Import failed: {}",
-            String::from_utf8_lossy(&import_output.stderr)
-        );
-
-        // Verify YAML was created
-        assert!(yaml_path.exists(), "YAML file was not created");
-        let yaml_content = fs::read_to_string(&yaml_path).unwrap();
-        // Should have meaningful content
-        assert!(yaml_content.len() > 20, "YAML should have content");
+        // Import to YAML, which must parse as a forge spec
+        let yaml_content = import(&binary, &original_xlsx, &yaml_path);
+        assert_import(&yaml_content, &scalars_expectation());
     }
 
     // ─────────────────────────────────────────────────────────────────────────
@@ -1012,15 +3782,42 @@ scalars:
             String::from_utf8_lossy(&import_output.stderr)
         );
 
-        // Verify imported YAML exists and has expected content
-        assert!(imported_yaml.exists());
+        // Verify imported YAML has the original scalars
         let imported_content = fs::read_to_string(&imported_yaml).unwrap();
-        assert!(imported_content.contains("price"));
-        assert!(imported_content.contains("quantity"));
+        assert_import(
+            &imported_content,
+            &[
+                ("scalars", "price", ImportedEntry::Value(100.0)),
+                ("scalars", "quantity", ImportedEntry::Value(5.0)),
+                ("scalars", "total", ImportedEntry::Formula),
+            ],
+        );
+
+        // Re-exporting the imported YAML must reproduce the first export
+        let reexported_xlsx = temp_dir.path().join("reexported.xlsx");
+        let reexport_output = Command::new(&binary)
+            .arg("export")
+            .arg(&imported_yaml)
+            .arg(&reexported_xlsx)
+            .output()
+            .expect("Failed to re-export");
+        assert!(reexport_output.status.success());
+
+        let mismatches = excel::compare_xlsx(&reexported_xlsx, &xlsx_path, 1e-9).unwrap();
+        assert!(
+            mismatches.is_empty(),
+            "Round-trip changed the workbook:\n{}",
+            mismatches
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
     }
 
     #[test]
     #[ignore = "forge-demo export requires tables section, not scalar-only models"]
+    #[allow(clippy::approx_constant)] // 3.14159 is test data, not PI
     fn roundtrip_preserves_numeric_values() {
         let Some(binary) = skip_if_no_binary() else {
             eprintln!("Skipping: forge-demo binary not found");
@@ -1051,20 +3848,15 @@ scalars:
             .expect("Failed to export");
         assert!(export_output.status.success());
 
-        // Import
-        let import_output = Command::new(&binary)
-            .arg("import")
-            .arg(&xlsx_path)
-            .arg(&imported_yaml)
-            .output()
-            .expect("Failed to import");
-        assert!(import_output.status.success());
-
-        // Verify values are preserved in imported YAML
-        let content = fs::read_to_string(&imported_yaml).unwrap();
-        // Should contain the scalar names
-        assert!(content.contains("integer_val"));
-        assert!(content.contains("float_val"));
-        assert!(content.contains("percent_val"));
+        // Values must survive the round trip, not just the names
+        let content = import(&binary, &xlsx_path, &imported_yaml);
+        assert_import(
+            &content,
+            &[
+                ("scalars", "integer_val", ImportedEntry::Value(42.0)),
+                ("scalars", "float_val", ImportedEntry::Value(3.14159)),
+                ("scalars", "percent_val", ImportedEntry::Value(0.25)),
+            ],
+        );
     }
 }
@@ -0,0 +1,96 @@
+//! Test selection by name.
+//!
+//! `--include`/`--exclude` take glob patterns matched against fully
+//! qualified test names (`math.*`, `*.test_sum*`). `*` also matches `.`,
+//! so a pattern can span the section and the test name.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Include/exclude glob patterns for test names.
+#[derive(Debug, Clone, Default)]
+pub struct NameFilter {
+    /// If set, only names matching one of these are selected.
+    include: Option<GlobSet>,
+    /// Names matching one of these are never selected.
+    exclude: Option<GlobSet>,
+}
+
+impl NameFilter {
+    /// Compiles the patterns; empty lists select everything.
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            include: glob_set(include)?,
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    /// Returns `true` if `name` is included and not excluded.
+    pub fn matches(&self, name: &str) -> bool {
+        self.include.as_ref().is_none_or(|set| set.is_match(name))
+            && !self.exclude.as_ref().is_some_and(|set| set.is_match(name))
+    }
+}
+
+/// Compiles `patterns` into one set, or `None` if there are none.
+fn glob_set(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| format!("Invalid pattern {pattern:?}: {e}"))?);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Invalid patterns: {e}"))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> NameFilter {
+        let owned =
+            |patterns: &[&str]| patterns.iter().map(ToString::to_string).collect::<Vec<_>>();
+        NameFilter::new(&owned(include), &owned(exclude)).unwrap()
+    }
+
+    #[test]
+    fn empty_filter_selects_everything() {
+        assert!(filter(&[], &[]).matches("math.test_abs"));
+    }
+
+    #[test]
+    fn include_selects_matching_names() {
+        let math = filter(&["math.*", "*.test_sum*"], &[]);
+        assert!(math.matches("math.test_abs"));
+        assert!(math.matches("aggregation.test_sumif"));
+        assert!(!math.matches("text.test_len"));
+    }
+
+    #[test]
+    fn exclude_drops_matching_names() {
+        let no_dates = filter(&[], &["date*.*"]);
+        assert!(!no_dates.matches("dates.test_today"));
+        assert!(no_dates.matches("math.test_abs"));
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let math_but_sqrt = filter(&["math.*"], &["*sqrt*"]);
+        assert!(math_but_sqrt.matches("math.test_abs"));
+        assert!(!math_but_sqrt.matches("math.test_sqrt_four"));
+        assert!(!math_but_sqrt.matches("text.test_len"));
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        let err = NameFilter::new(&["math.[".to_string()], &[]).unwrap_err();
+        assert!(err.contains("math.["), "{err}");
+    }
+}
@@ -0,0 +1,170 @@
+//! Result status symbols.
+//!
+//! `✓`/`✗`/`⊘` render as boxes on terminals without suitable fonts, so both
+//! the TUI and headless output take a [`SymbolSet`] with an ASCII fallback.
+//! With labels on (`--labels`), every symbol is followed by its status in
+//! words, so no status is told apart by color alone.
+
+use crate::types::TestResult;
+
+/// Locale variables consulted, in precedence order, to detect UTF-8 support.
+const LOCALE_VARS: &[&str] = &["LC_ALL", "LC_CTYPE", "LANG"];
+
+/// Symbols marking a passed, failed, or skipped test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolSet {
+    /// Marks a passed test.
+    pub pass: &'static str,
+    /// Marks a failed test.
+    pub fail: &'static str,
+    /// Marks a skipped test.
+    pub skip: &'static str,
+    /// Whether [`Self::marker`] spells out the status after the symbol.
+    pub labels: bool,
+}
+
+impl Default for SymbolSet {
+    fn default() -> Self {
+        Self::UNICODE
+    }
+}
+
+impl SymbolSet {
+    /// Unicode symbols (`✓`/`✗`/`⊘`).
+    pub const UNICODE: Self = Self {
+        pass: "✓",
+        fail: "✗",
+        skip: "⊘",
+        labels: false,
+    };
+
+    /// ASCII fallback (`[P]`/`[F]`/`[S]`).
+    pub const ASCII: Self = Self {
+        pass: "[P]",
+        fail: "[F]",
+        skip: "[S]",
+        labels: false,
+    };
+
+    /// Picks the symbols for this terminal.
+    ///
+    /// ASCII when `ascii` is set (`--ascii`) or the locale is set but not
+    /// UTF-8; Unicode otherwise.
+    pub fn detect(ascii: bool) -> Self {
+        if ascii || locale_is_utf8(|var| std::env::var(var).ok()) == Some(false) {
+            Self::ASCII
+        } else {
+            Self::UNICODE
+        }
+    }
+
+    /// Spells out statuses after their symbols when `labels` is set.
+    #[must_use]
+    pub const fn with_labels(mut self, labels: bool) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Returns the symbol for `result`'s status.
+    pub const fn for_result(&self, result: &TestResult) -> &'static str {
+        match result {
+            TestResult::Pass { .. } => self.pass,
+            TestResult::Fail { .. } => self.fail,
+            TestResult::Skip { .. } => self.skip,
+        }
+    }
+
+    /// Returns the marker for `result`: its symbol, followed by its
+    /// [`label`] when labels are on (`✓ PASS`).
+    pub fn marker(&self, result: &TestResult) -> String {
+        let symbol = self.for_result(result);
+        if self.labels {
+            format!("{symbol} {}", label(result))
+        } else {
+            symbol.to_string()
+        }
+    }
+}
+
+/// Returns `result`'s status in words (`PASS`, `FAIL` or `SKIP`).
+pub const fn label(result: &TestResult) -> &'static str {
+    match result {
+        TestResult::Pass { .. } => "PASS",
+        TestResult::Fail { .. } => "FAIL",
+        TestResult::Skip { .. } => "SKIP",
+    }
+}
+
+/// Returns whether the effective locale uses UTF-8, or `None` when no
+/// locale variable is set.
+///
+/// Like the C library, the first non-empty variable of [`LOCALE_VARS`] wins.
+fn locale_is_utf8(lookup: impl Fn(&str) -> Option<String>) -> Option<bool> {
+    let locale = LOCALE_VARS
+        .iter()
+        .filter_map(|var| lookup(var))
+        .find(|value| !value.is_empty())?;
+    let locale = locale.to_ascii_lowercase();
+    Some(locale.contains("utf-8") || locale.contains("utf8"))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| (*value).to_string())
+        }
+    }
+
+    #[test]
+    fn ascii_flag_selects_ascii_symbols() {
+        let symbols = SymbolSet::detect(true);
+        assert_eq!(symbols, SymbolSet::ASCII);
+        let skip = TestResult::Skip {
+            name: "a.test_one".to_string(),
+            id: None,
+            reason: "unsupported".to_string(),
+        };
+        assert_eq!(symbols.for_result(&skip), "[S]");
+        assert_eq!(SymbolSet::default().for_result(&skip), "⊘");
+    }
+
+    #[test]
+    fn labels_follow_every_symbol() {
+        let fail = TestResult::Fail {
+            name: "a.test_one".to_string(),
+            id: None,
+            formula: "=1".to_string(),
+            expected: 1.0,
+            actual: Some(2.0),
+            error: None,
+            duration: None,
+        };
+        assert_eq!(SymbolSet::UNICODE.marker(&fail), "✗");
+        assert_eq!(SymbolSet::UNICODE.with_labels(true).marker(&fail), "✗ FAIL");
+        assert_eq!(SymbolSet::ASCII.with_labels(true).marker(&fail), "[F] FAIL");
+    }
+
+    #[test]
+    fn locale_detection_follows_precedence() {
+        assert_eq!(locale_is_utf8(env(&[("LANG", "en_US.UTF-8")])), Some(true));
+        assert_eq!(locale_is_utf8(env(&[("LANG", "C.utf8")])), Some(true));
+        assert_eq!(
+            locale_is_utf8(env(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")])),
+            Some(false)
+        );
+        assert_eq!(
+            locale_is_utf8(env(&[("LC_ALL", ""), ("LANG", "en_US.UTF-8")])),
+            Some(true)
+        );
+        assert_eq!(locale_is_utf8(env(&[])), None);
+    }
+}
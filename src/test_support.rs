@@ -0,0 +1,18 @@
+//! Helpers shared by the unit tests of several modules.
+//!
+//! Tests stand in for forge-demo, ssconvert, ssh and scp with small shell
+//! scripts, so they run without any of those installed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Writes an executable shell script `name` into `dir` that runs `script`.
+#[cfg(unix)]
+pub fn fake_binary(dir: &Path, name: &str, script: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
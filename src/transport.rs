@@ -0,0 +1,290 @@
+//! How forge-demo invocations reach the binary under test.
+//!
+//! By default forge-demo runs as a local subprocess on files in a temp
+//! directory. [`SshTransport`] runs it on another machine instead, staging
+//! the files it reads with `scp` and pulling back the files it writes, so a
+//! build for another OS or architecture can be validated against the local
+//! Gnumeric.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::config::{Config, TODAY_ENV};
+use crate::runner::forge_command;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Transport
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// One argument of a forge-demo invocation.
+#[derive(Debug, Clone, Copy)]
+pub enum ForgeArg<'a> {
+    /// Passed through verbatim (subcommands, flags).
+    Plain(&'a str),
+    /// A local file forge-demo reads.
+    Input(&'a Path),
+    /// A local file forge-demo writes.
+    Output(&'a Path),
+}
+
+/// Runs forge-demo commands.
+///
+/// Implementations are shared across rayon workers, so they must be
+/// `Send + Sync`.
+pub trait Transport: Send + Sync {
+    /// Runs `forge_binary` with `args` and the environment `config` asks
+    /// for. Once this returns, every [`ForgeArg::Output`] forge-demo wrote
+    /// exists at its local path.
+    fn run(&self, forge_binary: &Path, config: &Config, args: &[ForgeArg]) -> io::Result<Output>;
+}
+
+/// Runs forge-demo as a local subprocess.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalTransport;
+
+impl Transport for LocalTransport {
+    fn run(&self, forge_binary: &Path, config: &Config, args: &[ForgeArg]) -> io::Result<Output> {
+        let mut cmd = forge_command(forge_binary, config);
+        for arg in args {
+            match arg {
+                ForgeArg::Plain(s) => cmd.arg(s),
+                ForgeArg::Input(path) | ForgeArg::Output(path) => cmd.arg(path),
+            };
+        }
+        cmd.output()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// SSH
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Runs forge-demo on a remote host over SSH.
+///
+/// Each invocation gets a fresh remote `mktemp -d` directory: inputs are
+/// copied in with `scp`, forge-demo runs there as
+/// `ssh HOST -- forge-demo ...`, outputs are copied back, and the
+/// directory is removed. The forge-demo path is a path on the remote host.
+#[derive(Debug, Clone)]
+pub struct SshTransport {
+    /// Destination passed to `ssh`/`scp` (`host` or `user@host`).
+    host: String,
+    /// `ssh` program.
+    ssh: PathBuf,
+    /// `scp` program.
+    scp: PathBuf,
+}
+
+impl SshTransport {
+    /// Creates a transport to `host` using the `ssh` and `scp` on the `PATH`.
+    pub fn new(host: String) -> Self {
+        Self {
+            host,
+            ssh: PathBuf::from("ssh"),
+            scp: PathBuf::from("scp"),
+        }
+    }
+
+    /// Replaces the `ssh` and `scp` programs, e.g. with local stand-ins.
+    #[cfg(test)]
+    pub fn with_programs(mut self, ssh: PathBuf, scp: PathBuf) -> Self {
+        self.ssh = ssh;
+        self.scp = scp;
+        self
+    }
+
+    /// Runs `command` through the remote shell.
+    fn remote(&self, command: &str) -> io::Result<Output> {
+        Command::new(&self.ssh)
+            .arg(&self.host)
+            .arg("--")
+            .arg(command)
+            .output()
+    }
+
+    /// Copies `from` to `to`, either of which may be `host:path`.
+    fn copy(&self, from: &str, to: &str) -> io::Result<()> {
+        let output = Command::new(&self.scp)
+            .arg("-q")
+            .arg(from)
+            .arg(to)
+            .output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "scp {from} {to} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+
+    /// Stages inputs into `remote_dir`, runs forge-demo there and fetches
+    /// its outputs.
+    fn run_in(
+        &self,
+        remote_dir: &str,
+        forge_binary: &Path,
+        config: &Config,
+        args: &[ForgeArg],
+    ) -> io::Result<Output> {
+        let mut command = config.today.map_or_else(String::new, |today| {
+            format!("env {TODAY_ENV}={} ", today.format("%Y-%m-%d"))
+        });
+        command.push_str(&shell_quote(&forge_binary.to_string_lossy()));
+
+        let mut outputs = Vec::new();
+        for arg in args {
+            let word = match arg {
+                ForgeArg::Plain(s) => (*s).to_string(),
+                ForgeArg::Input(path) => {
+                    let remote = remote_path(remote_dir, path)?;
+                    self.copy(&path.to_string_lossy(), &format!("{}:{remote}", self.host))?;
+                    remote
+                }
+                ForgeArg::Output(path) => {
+                    let remote = remote_path(remote_dir, path)?;
+                    outputs.push((remote.clone(), *path));
+                    remote
+                }
+            };
+            command.push(' ');
+            command.push_str(&shell_quote(&word));
+        }
+
+        let output = self.remote(&command)?;
+        // A failed command reports its own error; fetching would only mask it
+        if output.status.success() {
+            for (remote, local) in outputs {
+                self.copy(&format!("{}:{remote}", self.host), &local.to_string_lossy())?;
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl Transport for SshTransport {
+    fn run(&self, forge_binary: &Path, config: &Config, args: &[ForgeArg]) -> io::Result<Output> {
+        let mktemp = self.remote("mktemp -d")?;
+        if !mktemp.status.success() {
+            return Err(io::Error::other(format!(
+                "ssh {} failed: {}",
+                self.host,
+                String::from_utf8_lossy(&mktemp.stderr).trim()
+            )));
+        }
+        let remote_dir = String::from_utf8_lossy(&mktemp.stdout).trim().to_string();
+
+        let result = self.run_in(&remote_dir, forge_binary, config, args);
+        // Best effort: a leftover temp dir shouldn't fail the test
+        let _ = self.remote(&format!("rm -rf {}", shell_quote(&remote_dir)));
+        result
+    }
+}
+
+/// Returns where `local` is staged inside `remote_dir`.
+fn remote_path(remote_dir: &str, local: &Path) -> io::Result<String> {
+    let name = local.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} has no file name", local.display()),
+        )
+    })?;
+    Ok(format!("{remote_dir}/{}", name.to_string_lossy()))
+}
+
+/// Quotes `word` for a POSIX shell.
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
+    use chrono::NaiveDate;
+    use std::fs;
+
+    /// An SSH transport whose `ssh`/`scp` act on the local machine.
+    #[cfg(unix)]
+    fn loopback_ssh(dir: &Path) -> SshTransport {
+        // `ssh HOST -- CMD` runs CMD in a shell; `scp` drops the `HOST:` prefix
+        let ssh = fake_binary(dir, "ssh", r#"shift 2; exec sh -c "$1""#);
+        let scp = fake_binary(dir, "scp", r#"shift; cp "${1#remote:}" "${2#remote:}""#);
+        SshTransport::new("remote".to_string()).with_programs(ssh, scp)
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ssh_stages_inputs_and_fetches_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("remote-dir.log");
+        let forge = fake_binary(
+            dir.path(),
+            "forge-demo",
+            &format!(
+                r#"dirname "$2" > {log}; echo "$FORGE_TODAY" > "$3"; cat "$2" >> "$3""#,
+                log = log.display()
+            ),
+        );
+        let input = dir.path().join("test.yaml");
+        let output = dir.path().join("test.xlsx");
+        fs::write(&input, "spec").unwrap();
+        let config = Config {
+            today: NaiveDate::from_ymd_opt(2024, 6, 15),
+            ..Config::default()
+        };
+
+        let result = loopback_ssh(dir.path())
+            .run(
+                &forge,
+                &config,
+                &[
+                    ForgeArg::Plain("export"),
+                    ForgeArg::Input(&input),
+                    ForgeArg::Output(&output),
+                ],
+            )
+            .unwrap();
+
+        assert!(result.status.success());
+        assert_eq!(fs::read_to_string(&output).unwrap(), "2024-06-15\nspec");
+        // forge-demo ran in the staging dir, which is cleaned up afterwards
+        let remote_dir = PathBuf::from(fs::read_to_string(&log).unwrap().trim());
+        assert_ne!(remote_dir, dir.path());
+        assert!(!remote_dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ssh_reports_connection_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let ssh = fake_binary(
+            dir.path(),
+            "ssh",
+            "echo 'Could not resolve hostname' >&2; exit 255",
+        );
+        let transport =
+            SshTransport::new("nowhere".to_string()).with_programs(ssh, PathBuf::from("scp"));
+
+        let err = transport
+            .run(Path::new("forge-demo"), &Config::default(), &[])
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Could not resolve hostname"),
+            "{err}"
+        );
+    }
+}
@@ -1,22 +1,33 @@
 //! TUI application state - App struct and all its methods.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::fs;
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 use ratatui::widgets::ListState;
 
-use super::state::{ActivePanel, FilterMode, InputMode};
+use super::clipboard;
+use super::draw::format_detail_content;
+use super::state::{ActivePanel, DeltaMode, FilterMode, InputMode, ListRow, StatusGroup};
+use crate::config::DEFAULT_SECTION;
+use crate::coverage::FunctionCoverage;
+use crate::report::{exported_results, ReportMetadata};
+use crate::runner::{test_yaml, SpecFile};
+use crate::symbols::SymbolSet;
 use crate::types::TestResult;
 
 /// Duration to show status messages.
 const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(3);
 
-/// Demo mode function count (v1.0.0 schema).
-pub const DEMO_FUNCTION_COUNT: usize = 48;
-/// Full build function count (R&D, not for sale).
-pub const FULL_BUILD_FUNCTION_COUNT: usize = 173;
+/// Minimum number of identical errors before they are treated as an
+/// environment problem rather than individual test failures.
+const DOMINANT_ERROR_MIN_COUNT: usize = 3;
+
+/// Consecutive identical errors after which a running test run offers to
+/// abort, since the environment has most likely broken mid-run.
+const ABORT_PROMPT_STREAK: usize = 5;
 
 /// Main application state for the TUI.
 #[allow(clippy::struct_excessive_bools)]
@@ -41,10 +52,16 @@ pub struct App {
     pub(super) active_panel: ActivePanel,
     /// Current filter mode.
     pub(super) filter_mode: FilterMode,
+    /// Category shown alone (toggle with '.' key); `None` shows them all.
+    pub(super) category_filter: Option<String>,
     /// State for the results list (selection, scroll offset).
     pub(super) list_state: ListState,
     /// Cached filtered indices for the current filter mode.
     pub(super) filtered_indices: Vec<usize>,
+    /// Rows shown in the results list (indexed by `list_state`).
+    pub(super) rows: Vec<ListRow>,
+    /// Whether the list is grouped into Failed/Passed/Skipped sections.
+    pub(super) grouped: bool,
     /// Current input mode (normal or search).
     pub(super) input_mode: InputMode,
     /// Search query string.
@@ -55,14 +72,39 @@ pub struct App {
     pub(super) start_time: Option<Instant>,
     /// Total execution time after tests complete.
     pub(super) total_duration: Option<Duration>,
-    /// Function coverage by category (category -> set of function names).
-    function_coverage: HashMap<String, HashSet<String>>,
+    /// Functions exercised so far, by category.
+    function_coverage: FunctionCoverage,
     /// Whether comparison mode is active (toggle with 'c' key).
     pub(super) comparison_mode: bool,
+    /// Delta shown in comparison mode (toggle with 'd' key).
+    pub(super) delta_mode: DeltaMode,
+    /// Whether the selected test's details fill the screen (toggle with 'v' key).
+    pub(super) detail_overlay: bool,
+    /// Scroll offset, in lines, of the full-screen details.
+    pub(super) detail_scroll: u16,
+    /// Whether details wrap long lines (toggle with 'w' key); when off,
+    /// they scroll horizontally instead.
+    pub(super) detail_wrap: bool,
+    /// Horizontal scroll offset, in columns, of unwrapped details.
+    pub(super) detail_hscroll: u16,
     /// Whether perf mode is active (skip Gnumeric validation).
     pub(super) perf_mode: bool,
     /// Whether batch mode is active (single XLSX for all tests).
     pub(super) batch_mode: bool,
+    /// Section name used when generating YAML reproducers.
+    section: String,
+    /// Whether saved JSON lists skipped tests.
+    include_skips: bool,
+    /// Code versions embedded in saved JSON.
+    metadata: ReportMetadata,
+    /// Spec files the tests were loaded from, for failure report locations.
+    spec_files: Vec<SpecFile>,
+    /// First line of the latest error and how many results in a row hit it.
+    error_streak: Option<(String, usize)>,
+    /// Whether the user chose to continue despite the current streak.
+    abort_declined: bool,
+    /// Status symbols for result rows and details.
+    pub(super) symbols: SymbolSet,
 }
 
 impl App {
@@ -79,20 +121,70 @@ impl App {
             skipped: 0,
             active_panel: ActivePanel::default(),
             filter_mode: FilterMode::default(),
+            category_filter: None,
             list_state: ListState::default(),
             filtered_indices: Vec::new(),
+            rows: Vec::new(),
+            grouped: false,
             input_mode: InputMode::default(),
             search_query: String::new(),
             status_message: None,
             start_time: Some(Instant::now()),
             total_duration: None,
-            function_coverage: HashMap::new(),
+            function_coverage: FunctionCoverage::default(),
             comparison_mode: false,
+            delta_mode: DeltaMode::default(),
+            detail_overlay: false,
+            detail_scroll: 0,
+            detail_wrap: true,
+            detail_hscroll: 0,
             perf_mode: false,
             batch_mode: false,
+            section: DEFAULT_SECTION.to_string(),
+            include_skips: true,
+            metadata: ReportMetadata::default(),
+            spec_files: Vec::new(),
+            error_streak: None,
+            abort_declined: false,
+            symbols: SymbolSet::default(),
         }
     }
 
+    /// Sets the section name used for YAML reproducers.
+    #[must_use]
+    pub fn with_section(mut self, section: impl Into<String>) -> Self {
+        self.section = section.into();
+        self
+    }
+
+    /// Sets the code versions embedded in saved JSON.
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: ReportMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sets the spec files whose paths and lines go into failure reports.
+    #[must_use]
+    pub fn with_spec_files(mut self, spec_files: Vec<SpecFile>) -> Self {
+        self.spec_files = spec_files;
+        self
+    }
+
+    /// Sets the status symbols for result rows and details.
+    #[must_use]
+    pub const fn with_symbols(mut self, symbols: SymbolSet) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Sets whether saved JSON lists skipped tests.
+    #[must_use]
+    pub const fn with_skips(mut self, include_skips: bool) -> Self {
+        self.include_skips = include_skips;
+        self
+    }
+
     /// Resets the app for a new test run.
     pub fn reset(&mut self, perf_mode: bool, batch_mode: bool) {
         self.results.clear();
@@ -103,12 +195,15 @@ impl App {
         self.failed = 0;
         self.skipped = 0;
         self.filtered_indices.clear();
+        self.rows.clear();
         self.list_state.select(None);
         self.start_time = Some(Instant::now());
         self.total_duration = None;
         self.function_coverage.clear();
         self.perf_mode = perf_mode;
         self.batch_mode = batch_mode;
+        self.error_streak = None;
+        self.abort_declined = false;
     }
 
     /// Adds a test result and updates statistics.
@@ -118,54 +213,86 @@ impl App {
             TestResult::Fail { .. } => self.failed += 1,
             TestResult::Skip { .. } => self.skipped += 1,
         }
-        self.track_function_coverage(result.name());
+        self.function_coverage.record(result.name());
+        self.track_error_streak(&result);
         self.results.push(result);
         self.current_test += 1;
         self.update_filtered_indices();
-        if self.list_state.selected().is_none() && !self.filtered_indices.is_empty() {
-            self.list_state.select(Some(0));
+        if self.list_state.selected().is_none() {
+            self.select_first();
         }
     }
 
-    /// Marks the app as done running tests.
-    pub fn mark_done(&mut self) {
-        self.running = false;
-        self.done = true;
-        if let Some(start) = self.start_time {
-            self.total_duration = Some(start.elapsed());
+    /// Extends or resets the identical-error streak with `result`, asking
+    /// whether to abort once it reaches [`ABORT_PROMPT_STREAK`].
+    ///
+    /// Only failures without a value count: those never reached a result,
+    /// which is how a missing binary or engine shows up. As in
+    /// [`Self::dominant_error`], only first lines are compared.
+    fn track_error_streak(&mut self, result: &TestResult) {
+        let TestResult::Fail {
+            actual: None,
+            error: Some(error),
+            ..
+        } = result
+        else {
+            self.error_streak = None;
+            self.abort_declined = false;
+            return;
+        };
+        let error = error.lines().next().unwrap_or_default().trim();
+        let count = match &mut self.error_streak {
+            Some((last, count)) if last == error => {
+                *count += 1;
+                *count
+            }
+            streak => {
+                *streak = Some((error.to_string(), 1));
+                self.abort_declined = false;
+                1
+            }
+        };
+        // Perf and batch results arrive all at once, when there's nothing left to abort
+        let streaming = self.running && !self.perf_mode && !self.batch_mode;
+        if count == ABORT_PROMPT_STREAK && streaming && !self.abort_declined {
+            self.input_mode = InputMode::ConfirmAbort;
+            self.detail_overlay = false;
         }
     }
 
-    fn track_function_coverage(&mut self, name: &str) {
-        // Test names like "assumptions.test_sin_zero" -> extract "SIN"
-        let parts: Vec<&str> = name.split('.').collect();
-        if parts.len() >= 2 {
-            let category = parts[0].to_string();
-            let test_name = parts[1..].join(".");
-            // Extract function: "test_sin_zero" -> "sin" -> "SIN"
-            if let Some(func) = Self::extract_function_name(&test_name) {
-                self.function_coverage
-                    .entry(category)
-                    .or_default()
-                    .insert(func);
-            }
+    /// Returns the repeating error and its streak length while the abort
+    /// prompt is shown.
+    pub fn abort_prompt(&self) -> Option<(&str, usize)> {
+        if self.input_mode != InputMode::ConfirmAbort {
+            return None;
         }
+        self.error_streak
+            .as_ref()
+            .map(|(error, count)| (error.as_str(), *count))
     }
 
-    /// Extract Excel function name from test name.
-    /// `test_sin_zero` -> `SIN`, `test_roundup_pos` -> `ROUNDUP`
-    fn extract_function_name(test_name: &str) -> Option<String> {
-        let name = test_name.strip_prefix("test_")?;
-        // Find the function part (up to the next underscore that precedes a variant)
-        // e.g., "sin_zero" -> "sin", "roundup_positive" -> "roundup"
-        let parts: Vec<&str> = name.split('_').collect();
-        if parts.is_empty() {
-            return None;
+    /// Answers the abort prompt: stops the run, or keeps going without
+    /// asking again until the error changes.
+    ///
+    /// The caller stops the worker; this only updates the display state.
+    pub fn answer_abort_prompt(&mut self, abort: bool) {
+        self.input_mode = InputMode::Normal;
+        if abort {
+            let count = self.error_streak.as_ref().map_or(0, |(_, count)| *count);
+            self.mark_done();
+            self.set_status(format!("Run aborted after {count} identical errors"));
+        } else {
+            self.abort_declined = true;
+        }
+    }
+
+    /// Marks the app as done running tests.
+    pub fn mark_done(&mut self) {
+        self.running = false;
+        self.done = true;
+        if let Some(start) = self.start_time {
+            self.total_duration = Some(start.elapsed());
         }
-        // Handle multi-part function names like "round_up" vs single "sin"
-        // Check if combining first parts makes a known pattern
-        let func = parts[0].to_uppercase();
-        Some(func)
     }
 
     #[allow(clippy::cast_precision_loss)]
@@ -200,16 +327,61 @@ impl App {
     }
 
     pub fn unique_functions_tested(&self) -> usize {
-        self.function_coverage.values().map(HashSet::len).sum()
+        self.function_coverage.unique_functions_tested()
     }
 
     pub fn coverage_by_category(&self) -> Vec<(&str, usize)> {
-        let mut cats: Vec<_> = self
-            .function_coverage
-            .iter()
-            .map(|(k, v)| (k.as_str(), v.len()))
+        self.function_coverage.by_category()
+    }
+
+    /// Returns the error message shared by most failures, if any.
+    ///
+    /// When Gnumeric or forge-demo is misconfigured every test fails with
+    /// the same infrastructure error. This returns that error and how many
+    /// tests hit it when at least half of all failures share it. Only the
+    /// first line of each error is compared, since stderr output often
+    /// embeds per-test temp paths further down.
+    pub fn dominant_error(&self) -> Option<(&str, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for result in &self.results {
+            if let TestResult::Fail {
+                error: Some(error), ..
+            } = result
+            {
+                let first_line = error.lines().next().unwrap_or_default().trim();
+                *counts.entry(first_line).or_default() += 1;
+            }
+        }
+        let (error, count) = counts
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))?;
+        if count >= DOMINANT_ERROR_MIN_COUNT && count * 2 >= self.failed {
+            Some((error, count))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `(category, passed, failed)` counts per test category.
+    ///
+    /// Sorted by failure count (descending), then category name, so the
+    /// categories needing the most attention come first.
+    pub fn pass_rate_by_category(&self) -> Vec<(&str, usize, usize)> {
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for result in &self.results {
+            let category = result.name().split('.').next().unwrap_or_default();
+            let entry = counts.entry(category).or_default();
+            match result {
+                TestResult::Pass { .. } => entry.0 += 1,
+                TestResult::Fail { .. } => entry.1 += 1,
+                TestResult::Skip { .. } => {}
+            }
+        }
+        let mut cats: Vec<_> = counts
+            .into_iter()
+            .map(|(cat, (passed, failed))| (cat, passed, failed))
             .collect();
-        cats.sort_by(|a, b| b.1.cmp(&a.1));
+        cats.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)));
         cats
     }
 
@@ -219,6 +391,75 @@ impl App {
         self.set_status(format!("Comparison mode: {mode}"));
     }
 
+    pub fn toggle_delta_mode(&mut self) {
+        self.delta_mode = self.delta_mode.toggled();
+        let mode = match self.delta_mode {
+            DeltaMode::Absolute => "absolute",
+            DeltaMode::Relative => "relative",
+        };
+        self.set_status(format!("Comparison delta: {mode}"));
+    }
+
+    /// Shows the selected test's details full screen, scrolled to the top.
+    pub fn open_detail_overlay(&mut self) {
+        if self.selected_result().is_some() {
+            self.detail_overlay = true;
+            self.detail_scroll = 0;
+        }
+    }
+
+    pub const fn close_detail_overlay(&mut self) {
+        self.detail_overlay = false;
+    }
+
+    /// Scrolls the full-screen details by `lines` (negative scrolls up).
+    ///
+    /// The upper bound depends on the rendered area and is applied when drawing.
+    pub const fn scroll_detail(&mut self, lines: i16) {
+        self.detail_scroll = self.detail_scroll.saturating_add_signed(lines);
+    }
+
+    /// Switches details between wrapping long lines and scrolling them
+    /// horizontally, back at the first column.
+    pub fn toggle_detail_wrap(&mut self) {
+        self.detail_wrap = !self.detail_wrap;
+        self.detail_hscroll = 0;
+        let mode = if self.detail_wrap { "ON" } else { "OFF" };
+        self.set_status(format!("Detail wrap: {mode}"));
+    }
+
+    /// Scrolls unwrapped details by `columns` (negative scrolls left);
+    /// does nothing while details wrap.
+    ///
+    /// The upper bound depends on the rendered area and is applied when drawing.
+    pub const fn scroll_detail_horizontally(&mut self, columns: i16) {
+        if !self.detail_wrap {
+            self.detail_hscroll = self.detail_hscroll.saturating_add_signed(columns);
+        }
+    }
+
+    /// Explains an empty results list caused by the search or filter.
+    ///
+    /// Returns `None` while there are no results at all, or while some
+    /// match, so only a list emptied by the user's own criteria is called out.
+    pub fn empty_state_message(&self) -> Option<String> {
+        if self.results.is_empty() || !self.filtered_indices.is_empty() {
+            return None;
+        }
+        let filter = self.category_filter.as_ref().map_or_else(
+            || self.filter_mode.label().to_string(),
+            |category| format!("{} ({category})", self.filter_mode.label()),
+        );
+        Some(if self.search_query.is_empty() {
+            format!("No results in filter {filter}")
+        } else {
+            format!(
+                "No results match '{}' in filter {filter}",
+                self.search_query
+            )
+        })
+    }
+
     fn update_filtered_indices(&mut self) {
         let query_lower = self.search_query.to_lowercase();
         self.filtered_indices = self
@@ -231,13 +472,66 @@ impl App {
                     FilterMode::Passed => r.is_pass(),
                     FilterMode::Failed => r.is_fail(),
                 };
+                let passes_category = self
+                    .category_filter
+                    .as_deref()
+                    .is_none_or(|category| r.name().split('.').next() == Some(category));
                 let passes_search =
                     query_lower.is_empty() || r.name().to_lowercase().contains(&query_lower);
-                passes_filter && passes_search
+                passes_filter && passes_category && passes_search
             })
             .map(|(i, _)| i)
             .rev()
             .collect();
+        self.update_rows();
+    }
+
+    /// Rebuilds the list rows from the filtered indices.
+    fn update_rows(&mut self) {
+        self.rows = if self.grouped {
+            let mut rows =
+                Vec::with_capacity(self.filtered_indices.len() + StatusGroup::ORDER.len());
+            for group in StatusGroup::ORDER {
+                let members: Vec<usize> = self
+                    .filtered_indices
+                    .iter()
+                    .copied()
+                    .filter(|&i| StatusGroup::of(&self.results[i]) == group)
+                    .collect();
+                if !members.is_empty() {
+                    rows.push(ListRow::Header(group, members.len()));
+                    rows.extend(members.into_iter().map(ListRow::Result));
+                }
+            }
+            rows
+        } else {
+            self.filtered_indices
+                .iter()
+                .copied()
+                .map(ListRow::Result)
+                .collect()
+        };
+        // Never leave the selection on a header or past the end
+        if let Some(i) = self.list_state.selected() {
+            if self.rows.get(i).and_then(|r| r.result_index()).is_none() {
+                self.select_first();
+            }
+        }
+    }
+
+    /// Selects the first result row (skipping headers), if any.
+    fn select_first(&mut self) {
+        let first = self.rows.iter().position(|r| r.result_index().is_some());
+        self.list_state.select(first);
+    }
+
+    /// Toggles grouping the list into Failed/Passed/Skipped sections.
+    pub fn toggle_grouping(&mut self) {
+        self.grouped = !self.grouped;
+        self.update_rows();
+        self.select_first();
+        let mode = if self.grouped { "ON" } else { "OFF" };
+        self.set_status(format!("Group by status: {mode}"));
     }
 
     pub const fn enter_search_mode(&mut self) {
@@ -248,27 +542,19 @@ impl App {
         self.input_mode = InputMode::Normal;
         self.search_query.clear();
         self.update_filtered_indices();
-        if !self.filtered_indices.is_empty() {
-            self.list_state.select(Some(0));
-        }
+        self.select_first();
     }
 
     pub fn search_push(&mut self, c: char) {
         self.search_query.push(c);
         self.update_filtered_indices();
-        if self.filtered_indices.is_empty() {
-            self.list_state.select(None);
-        } else {
-            self.list_state.select(Some(0));
-        }
+        self.select_first();
     }
 
     pub fn search_pop(&mut self) {
         self.search_query.pop();
         self.update_filtered_indices();
-        if !self.filtered_indices.is_empty() {
-            self.list_state.select(Some(0));
-        }
+        self.select_first();
     }
 
     pub fn set_status(&mut self, message: impl Into<String>) {
@@ -291,64 +577,177 @@ impl App {
             chrono::Local::now().format("%Y%m%d-%H%M%S")
         );
         let path = PathBuf::from(&filename);
-        let output = serde_json::json!({
-            "timestamp": chrono::Local::now().to_rfc3339(),
-            "summary": { "total": self.results.len(), "passed": self.passed, "failed": self.failed, "skipped": self.skipped },
-            "results": &self.results,
-        });
-        let json = serde_json::to_string_pretty(&output)
+        let json = serde_json::to_string_pretty(&self.results_json())
             .map_err(|e| format!("Failed to serialize: {e}"))?;
         fs::write(&path, json).map_err(|e| format!("Failed to write file: {e}"))?;
         self.set_status(format!("Saved to {filename}"));
         Ok(path)
     }
 
+    /// Builds the saved results document.
+    ///
+    /// The summary always counts every result, even when skips are omitted
+    /// from the list.
+    fn results_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "timestamp": chrono::Local::now().to_rfc3339(),
+            "metadata": self.metadata.to_json(),
+            "summary": { "total": self.results.len(), "passed": self.passed, "failed": self.failed, "skipped": self.skipped },
+            "results": exported_results(&self.results, self.include_skips),
+        })
+    }
+
+    /// Returns the standalone YAML reproducer for the selected test.
+    ///
+    /// `None` when nothing is selected or the test was skipped (skips
+    /// carry no formula).
+    pub fn selected_yaml(&self) -> Option<String> {
+        match self.selected_result()? {
+            TestResult::Pass { formula, .. } | TestResult::Fail { formula, .. } => {
+                Some(test_yaml(&self.section, formula))
+            }
+            TestResult::Skip { .. } => None,
+        }
+    }
+
+    /// Copies the selected test's YAML reproducer to the clipboard.
+    pub fn copy_selected_yaml(&mut self) {
+        let Some(yaml) = self.selected_yaml() else {
+            self.set_status("No formula to copy for this selection");
+            return;
+        };
+        match clipboard::copy(&yaml) {
+            Ok(()) => self.set_status("Copied test YAML to clipboard"),
+            Err(e) => self.set_status(format!("Error: clipboard copy failed: {e}")),
+        }
+    }
+
+    /// Returns a plain-text report for the selected test, ready to paste
+    /// into chat or an issue: its details, category, source location (when
+    /// known) and the code versions it ran against.
+    pub fn selected_report(&self) -> Option<String> {
+        let result = self.selected_result()?;
+        let name = result.name();
+        let mut report = format_detail_content(result, self.symbols);
+        let category = name.split('.').next().unwrap_or_default();
+        let _ = write!(report, "\n\nCategory: {category}");
+        if let Some(file) = self
+            .spec_files
+            .iter()
+            .find(|f| f.tests.iter().chain(&f.skips).any(|t| t == name))
+        {
+            let _ = write!(report, "\nSource:   {}", file.path.display());
+            if let Some(line) = file.line_of(name) {
+                let _ = write!(report, ":{line}");
+            }
+        }
+        if let Some(version) = &self.metadata.forge_version {
+            let _ = write!(report, "\nForge:    {version}");
+        }
+        if let Some(commit) = &self.metadata.commit {
+            let _ = write!(report, "\nCommit:   {commit}");
+        }
+        Some(report)
+    }
+
+    /// Copies the selected test's report to the clipboard.
+    pub fn copy_selected_report(&mut self) {
+        let Some(report) = self.selected_report() else {
+            self.set_status("No test selected");
+            return;
+        };
+        match clipboard::copy(&report) {
+            Ok(()) => self.set_status("Copied test report to clipboard"),
+            Err(e) => self.set_status(format!("Error: clipboard copy failed: {e}")),
+        }
+    }
+
     pub fn set_filter(&mut self, mode: FilterMode) {
         if self.filter_mode != mode {
             self.filter_mode = mode;
             self.update_filtered_indices();
-            if self.filtered_indices.is_empty() {
-                self.list_state.select(None);
-            } else {
-                self.list_state.select(Some(0));
-            }
+            self.select_first();
+        }
+    }
+
+    /// Shows only the selected test's category (the name's section
+    /// prefix), or every category again if one is already shown alone.
+    ///
+    /// The selected test stays selected either way.
+    pub fn toggle_category_filter(&mut self) {
+        let selected = self
+            .list_state
+            .selected()
+            .and_then(|i| self.rows.get(i))
+            .and_then(|row| row.result_index());
+        if self.category_filter.take().is_some() {
+            self.set_status("Category filter: All");
+        } else if let Some(i) = selected {
+            let category = self.results[i]
+                .name()
+                .split('.')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            self.set_status(format!("Category filter: {category}"));
+            self.category_filter = Some(category);
+        } else {
+            self.set_status("No test selected");
+            return;
+        }
+        self.update_filtered_indices();
+        match self
+            .rows
+            .iter()
+            .position(|row| row.result_index() == selected)
+        {
+            Some(row) if selected.is_some() => self.list_state.select(Some(row)),
+            _ => self.select_first(),
         }
     }
 
+    /// Moves the selection up, skipping header rows.
     pub fn select_previous(&mut self) {
-        if self.filtered_indices.is_empty() {
+        let Some(current) = self.list_state.selected() else {
+            self.select_first();
             return;
+        };
+        if let Some(i) = (0..current)
+            .rev()
+            .find(|&i| self.rows[i].result_index().is_some())
+        {
+            self.list_state.select(Some(i));
         }
-        let i = self
-            .list_state
-            .selected()
-            .map_or(0, |i| i.saturating_sub(1));
-        self.list_state.select(Some(i));
     }
 
+    /// Moves the selection down, skipping header rows.
     pub fn select_next(&mut self) {
-        if self.filtered_indices.is_empty() {
+        let Some(current) = self.list_state.selected() else {
+            self.select_first();
             return;
+        };
+        if let Some(i) =
+            (current + 1..self.rows.len()).find(|&i| self.rows[i].result_index().is_some())
+        {
+            self.list_state.select(Some(i));
         }
-        let max_idx = self.filtered_indices.len().saturating_sub(1);
-        let i = self
-            .list_state
-            .selected()
-            .map_or(0, |i| (i + 1).min(max_idx));
-        self.list_state.select(Some(i));
     }
 
     pub fn selected_result(&self) -> Option<&TestResult> {
         self.list_state
             .selected()
-            .and_then(|i| self.filtered_indices.get(i))
-            .and_then(|&idx| self.results.get(idx))
+            .and_then(|i| self.rows.get(i))
+            .and_then(|row| row.result_index())
+            .and_then(|idx| self.results.get(idx))
     }
 
+    /// Returns the visible results in display order (headers excluded).
+    #[cfg(test)]
     pub fn filtered_results(&self) -> Vec<&TestResult> {
-        self.filtered_indices
+        self.rows
             .iter()
-            .filter_map(|&i| self.results.get(i))
+            .filter_map(|row| row.result_index())
+            .filter_map(|i| self.results.get(i))
             .collect()
     }
 
@@ -361,15 +760,10 @@ impl App {
 
     #[allow(clippy::cast_possible_truncation)]
     pub const fn progress_percent(&self) -> u16 {
-        if self.total_tests > 0 {
-            let percent = (self.current_test * 100) / self.total_tests;
-            if percent > 100 {
-                100
-            } else {
-                percent as u16
-            }
-        } else {
-            0
+        match (self.current_test * 100).checked_div(self.total_tests) {
+            Some(percent) if percent > 100 => 100,
+            Some(percent) => percent as u16,
+            None => 0,
         }
     }
 }
@@ -377,31 +771,54 @@ impl App {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::BTreeMap;
 
     fn make_pass_result(name: &str) -> TestResult {
         TestResult::Pass {
             name: name.to_string(),
+            id: None,
             formula: "=TEST()".to_string(),
             expected: 42.0,
             actual: 42.0,
+            heuristic: false,
+            forge: None,
+            duration: None,
         }
     }
     fn make_fail_result(name: &str) -> TestResult {
         TestResult::Fail {
             name: name.to_string(),
+            id: None,
             formula: "=FAIL()".to_string(),
             expected: 42.0,
             actual: Some(0.0),
             error: None,
+            duration: None,
         }
     }
     fn make_skip_result(name: &str) -> TestResult {
         TestResult::Skip {
             name: name.to_string(),
+            id: None,
             reason: "not implemented".to_string(),
         }
     }
 
+    #[test]
+    fn results_json_respects_include_skips() {
+        let mut app = App::new(2);
+        app.add_result(make_pass_result("a.test_one"));
+        app.add_result(make_skip_result("a.test_two"));
+
+        let json = app.results_json();
+        assert_eq!(json["results"].as_array().unwrap().len(), 2);
+
+        let json = app.with_skips(false).results_json();
+        assert_eq!(json["results"].as_array().unwrap().len(), 1);
+        assert_eq!(json["summary"]["total"], 2);
+        assert_eq!(json["summary"]["skipped"], 1);
+    }
+
     #[test]
     fn app_new_initializes_correctly() {
         let app = App::new(10);
@@ -450,6 +867,29 @@ mod tests {
         assert_eq!(app.filtered_results().len(), 1);
     }
     #[test]
+    fn app_empty_state_distinguishes_filtered_from_no_results() {
+        let mut app = App::new(2);
+        assert_eq!(app.empty_state_message(), None);
+
+        app.add_result(make_pass_result("t1"));
+        assert_eq!(app.empty_state_message(), None);
+
+        app.set_filter(FilterMode::Failed);
+        assert_eq!(
+            app.empty_state_message().as_deref(),
+            Some("No results in filter Failed")
+        );
+
+        app.set_filter(FilterMode::All);
+        for c in "zzz".chars() {
+            app.search_push(c);
+        }
+        assert_eq!(
+            app.empty_state_message().as_deref(),
+            Some("No results match 'zzz' in filter All")
+        );
+    }
+    #[test]
     fn app_navigation() {
         let mut app = App::new(3);
         app.add_result(make_pass_result("t1"));
@@ -460,12 +900,79 @@ mod tests {
         assert_eq!(app.list_state.selected(), Some(0));
     }
     #[test]
+    fn app_grouped_layout_puts_failures_first_with_headers() {
+        let mut app = App::new(4);
+        app.add_result(make_pass_result("t0"));
+        app.add_result(make_fail_result("t1"));
+        app.add_result(make_skip_result("t2"));
+        app.add_result(make_fail_result("t3"));
+        app.toggle_grouping();
+
+        assert_eq!(
+            app.rows,
+            vec![
+                ListRow::Header(StatusGroup::Failed, 2),
+                ListRow::Result(3),
+                ListRow::Result(1),
+                ListRow::Header(StatusGroup::Passed, 1),
+                ListRow::Result(0),
+                ListRow::Header(StatusGroup::Skipped, 1),
+                ListRow::Result(2),
+            ]
+        );
+        // Selection starts on the first result, not the header
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+    #[test]
+    fn app_grouped_navigation_skips_headers() {
+        let mut app = App::new(2);
+        app.add_result(make_pass_result("t0"));
+        app.add_result(make_fail_result("t1"));
+        app.toggle_grouping();
+        assert_eq!(app.selected_result().map(TestResult::name), Some("t1"));
+
+        app.select_next();
+        assert_eq!(app.list_state.selected(), Some(3));
+        assert_eq!(app.selected_result().map(TestResult::name), Some("t0"));
+        app.select_next();
+        assert_eq!(app.list_state.selected(), Some(3));
+
+        app.select_previous();
+        assert_eq!(app.list_state.selected(), Some(1));
+        app.select_previous();
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+    #[test]
     fn app_panel_switching() {
         let mut app = App::new(0);
         app.next_panel();
         assert_eq!(app.active_panel, ActivePanel::Details);
     }
     #[test]
+    fn app_category_filter_toggles() {
+        let mut app = App::new(3);
+        app.toggle_category_filter();
+        assert_eq!(app.category_filter, None);
+        assert_eq!(app.status_message(), Some("No test selected"));
+
+        app.add_result(make_pass_result("date.test_today"));
+        app.add_result(make_fail_result("math.test_abs"));
+        app.add_result(make_pass_result("date.test_year"));
+        while app.selected_result().unwrap().name() != "date.test_today" {
+            app.select_next();
+        }
+        app.toggle_category_filter();
+        assert_eq!(app.category_filter.as_deref(), Some("date"));
+        let names: Vec<&str> = app.filtered_results().iter().map(|r| r.name()).collect();
+        assert_eq!(names, ["date.test_year", "date.test_today"]);
+        assert_eq!(app.selected_result().unwrap().name(), "date.test_today");
+
+        app.toggle_category_filter();
+        assert_eq!(app.category_filter, None);
+        assert_eq!(app.filtered_results().len(), 3);
+        assert_eq!(app.selected_result().unwrap().name(), "date.test_today");
+    }
+    #[test]
     fn app_search_mode() {
         let mut app = App::new(2);
         app.add_result(make_pass_result("math.ABS"));
@@ -497,6 +1004,195 @@ mod tests {
         app.add_result(make_pass_result("text.test_concat_two"));
         assert_eq!(app.unique_functions_tested(), 3); // ABS, SQRT, CONCAT
     }
+    fn make_error_result(name: &str, error: &str) -> TestResult {
+        TestResult::Fail {
+            name: name.to_string(),
+            id: None,
+            formula: "=FAIL()".to_string(),
+            expected: 42.0,
+            actual: None,
+            error: Some(error.to_string()),
+            duration: None,
+        }
+    }
+    #[test]
+    fn app_dominant_error_detected() {
+        let mut app = App::new(5);
+        for i in 0..4 {
+            app.add_result(make_error_result(
+                &format!("t{i}"),
+                &format!("ssconvert failed: crash\n/tmp/run{i}/test.xlsx"),
+            ));
+        }
+        app.add_result(make_fail_result("t5"));
+        assert_eq!(app.dominant_error(), Some(("ssconvert failed: crash", 4)));
+    }
+    #[test]
+    fn app_offers_abort_after_identical_error_streak() {
+        let mut app = App::new(20);
+        for i in 0..ABORT_PROMPT_STREAK - 1 {
+            app.add_result(make_error_result(
+                &format!("t{i}"),
+                "Failed to run forge-demo",
+            ));
+        }
+        assert_eq!(app.abort_prompt(), None);
+
+        app.add_result(make_error_result("t_last", "Failed to run forge-demo"));
+        assert_eq!(app.input_mode, InputMode::ConfirmAbort);
+        assert_eq!(
+            app.abort_prompt(),
+            Some(("Failed to run forge-demo", ABORT_PROMPT_STREAK))
+        );
+
+        app.answer_abort_prompt(true);
+        assert!(app.done);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+    #[test]
+    fn app_abort_prompt_respects_decline_until_error_changes() {
+        let mut app = App::new(30);
+        let add = |app: &mut App, n: usize, error: &str| {
+            for i in 0..n {
+                app.add_result(make_error_result(&format!("t{i}"), error));
+            }
+        };
+        add(&mut app, ABORT_PROMPT_STREAK, "ssconvert missing");
+        app.answer_abort_prompt(false);
+        add(&mut app, ABORT_PROMPT_STREAK, "ssconvert missing");
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(!app.done);
+
+        // A different error starts a new streak, and a pass breaks one
+        add(&mut app, ABORT_PROMPT_STREAK - 1, "forge-demo missing");
+        app.add_result(make_pass_result("t_pass"));
+        add(&mut app, ABORT_PROMPT_STREAK - 1, "forge-demo missing");
+        assert_eq!(app.input_mode, InputMode::Normal);
+        add(&mut app, 1, "forge-demo missing");
+        assert_eq!(app.input_mode, InputMode::ConfirmAbort);
+    }
+    #[test]
+    fn app_abort_prompt_skipped_outside_streaming_runs() {
+        let mut app = App::new(10);
+        app.reset(true, false);
+        for i in 0..ABORT_PROMPT_STREAK {
+            app.add_result(make_error_result(&format!("t{i}"), "same"));
+        }
+        assert_eq!(app.abort_prompt(), None);
+    }
+    #[test]
+    fn app_dominant_error_ignores_varied_errors() {
+        let mut app = App::new(4);
+        app.add_result(make_error_result("t1", "error a"));
+        app.add_result(make_error_result("t2", "error b"));
+        app.add_result(make_error_result("t3", "error c"));
+        app.add_result(make_fail_result("t4"));
+        assert_eq!(app.dominant_error(), None);
+    }
+    #[test]
+    fn app_dominant_error_needs_minimum_count() {
+        let mut app = App::new(2);
+        app.add_result(make_error_result("t1", "same"));
+        app.add_result(make_error_result("t2", "same"));
+        assert_eq!(app.dominant_error(), None);
+    }
+    #[test]
+    fn app_pass_rate_by_category_ranks_failures_first() {
+        let mut app = App::new(6);
+        app.add_result(make_pass_result("math.test_abs"));
+        app.add_result(make_fail_result("math.test_sqrt"));
+        app.add_result(make_fail_result("text.test_len"));
+        app.add_result(make_fail_result("text.test_mid"));
+        app.add_result(make_pass_result("date.test_year"));
+        app.add_result(make_skip_result("date.test_datedif"));
+        assert_eq!(
+            app.pass_rate_by_category(),
+            vec![("text", 0, 2), ("math", 1, 1), ("date", 1, 0)]
+        );
+    }
+    #[test]
+    fn app_selected_yaml_reproduces_formula() {
+        let mut app = App::new(2);
+        app.add_result(make_skip_result("t1"));
+        assert_eq!(app.selected_yaml(), None);
+        app.add_result(make_fail_result("t2"));
+        // Newest results are listed first
+        let yaml = app.selected_yaml().unwrap();
+        assert!(yaml.contains("formula: \"=FAIL()\""));
+        assert!(yaml.starts_with("_forge_version"));
+    }
+    #[test]
+    fn app_selected_report_includes_location_and_versions() {
+        let mut app = App::new(1)
+            .with_spec_files(vec![SpecFile {
+                path: PathBuf::from("tests/e2e/math.yaml"),
+                tests: vec!["math.test_abs".to_string()],
+                skips: Vec::new(),
+                lines: BTreeMap::from([("math.test_abs".to_string(), 7)]),
+            }])
+            .with_metadata(ReportMetadata {
+                commit: Some("abc1234".to_string()),
+                forge_version: None,
+            });
+        assert_eq!(app.selected_report(), None);
+        app.add_result(make_fail_result("math.test_abs"));
+        let report = app.selected_report().unwrap();
+        assert!(report.starts_with("Test: math.test_abs"));
+        assert!(report.contains("FAILED"));
+        assert!(report.contains("=FAIL()"));
+        assert!(
+            report.ends_with("Category: math\nSource:   tests/e2e/math.yaml:7\nCommit:   abc1234")
+        );
+    }
+    #[test]
+    fn detail_overlay_needs_selection_and_resets_scroll() {
+        let mut app = App::new(1);
+        app.open_detail_overlay();
+        assert!(!app.detail_overlay);
+
+        app.add_result(make_fail_result("math.test_abs"));
+        app.mark_done();
+        app.open_detail_overlay();
+        assert!(app.detail_overlay);
+        app.scroll_detail(-1);
+        assert_eq!(app.detail_scroll, 0);
+        app.scroll_detail(5);
+        assert_eq!(app.detail_scroll, 5);
+
+        app.close_detail_overlay();
+        app.open_detail_overlay();
+        assert_eq!(app.detail_scroll, 0);
+    }
+
+    #[test]
+    fn detail_wrap_toggle_enables_horizontal_scroll() {
+        let mut app = App::new(1);
+        assert!(app.detail_wrap);
+        app.scroll_detail_horizontally(4);
+        assert_eq!(app.detail_hscroll, 0);
+
+        app.toggle_detail_wrap();
+        assert!(!app.detail_wrap);
+        app.scroll_detail_horizontally(4);
+        app.scroll_detail_horizontally(4);
+        app.scroll_detail_horizontally(-2);
+        assert_eq!(app.detail_hscroll, 6);
+
+        // Wrapping again starts back at the first column
+        app.toggle_detail_wrap();
+        app.toggle_detail_wrap();
+        assert_eq!(app.detail_hscroll, 0);
+    }
+
+    #[test]
+    fn app_toggle_delta_mode() {
+        let mut app = App::new(0);
+        assert_eq!(app.delta_mode, DeltaMode::Absolute);
+        app.toggle_delta_mode();
+        assert_eq!(app.delta_mode, DeltaMode::Relative);
+        assert_eq!(app.status_message(), Some("Comparison delta: relative"));
+    }
+
     #[test]
     fn app_comparison_mode() {
         let mut app = App::new(0);
@@ -0,0 +1,14 @@
+//! Clipboard integration via OSC 52 terminal escape sequences.
+//!
+//! The terminal emulator performs the copy, so this works over SSH and
+//! without platform clipboard libraries.
+
+use std::io::{self, stdout};
+
+use crossterm::{clipboard::CopyToClipboard, ExecutableCommand};
+
+/// Copies `text` to the system clipboard.
+pub fn copy(text: &str) -> io::Result<()> {
+    stdout().execute(CopyToClipboard::to_clipboard_from(text))?;
+    Ok(())
+}
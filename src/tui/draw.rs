@@ -1,11 +1,18 @@
 //! TUI drawing functions - renders all UI components.
 
-use super::app::{App, DEMO_FUNCTION_COUNT, FULL_BUILD_FUNCTION_COUNT};
-use super::state::{category_color, ActivePanel, FilterMode, InputMode};
+use super::app::App;
+use super::state::{
+    category_color, ActivePanel, DeltaMode, FilterMode, InputMode, ListRow, StatusGroup,
+};
+use crate::coverage::{DEMO_FUNCTION_COUNT, FULL_BUILD_FUNCTION_COUNT};
+use crate::report::heuristic_note;
+use crate::symbols::{self, SymbolSet};
 use crate::types::TestResult;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Cell, Clear, Gauge, List, ListItem, Paragraph, Row, Table, TableState, Wrap,
+    },
 };
 use std::fmt::Write as _;
 
@@ -22,7 +29,7 @@ pub fn draw_ui(frame: &mut Frame, app: &mut App) {
         ])
         .split(area);
 
-    draw_title(frame, main_chunks[0]);
+    draw_title(frame, main_chunks[0], app);
     draw_progress(frame, main_chunks[1], app);
 
     let content_chunks = Layout::default()
@@ -32,7 +39,11 @@ pub fn draw_ui(frame: &mut Frame, app: &mut App) {
 
     let right_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(8), Constraint::Length(5)])
+        .constraints([
+            Constraint::Min(6),
+            Constraint::Length(6),
+            Constraint::Length(5),
+        ])
         .split(content_chunks[1]);
 
     if app.comparison_mode {
@@ -41,12 +52,32 @@ pub fn draw_ui(frame: &mut Frame, app: &mut App) {
         draw_results_list(frame, content_chunks[0], app);
     }
     draw_details(frame, right_chunks[0], app);
-    draw_stats(frame, right_chunks[1], app);
+    draw_summary(frame, right_chunks[1], app);
+    draw_stats(frame, right_chunks[2], app);
     draw_coverage_bar(frame, main_chunks[3], app);
     draw_footer(frame, main_chunks[4], app);
+
+    if app.detail_overlay {
+        draw_detail_overlay(frame, area, app);
+    }
 }
 
-fn draw_title(frame: &mut Frame, area: Rect) {
+fn draw_title(frame: &mut Frame, area: Rect, app: &App) {
+    if let Some((error, count)) = app.dominant_error() {
+        let banner = Paragraph::new(format!(
+            "{count} tests failed with the same error: {error}; likely an environment problem"
+        ))
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title(" Environment Error ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red)),
+        );
+        frame.render_widget(banner, area);
+        return;
+    }
     let title = Paragraph::new("forge-e2e: E2E Validation Suite")
         .style(
             Style::default()
@@ -83,8 +114,12 @@ fn draw_results_list(frame: &mut Frame, area: Rect, app: &mut App) {
             mode.label().to_string()
         }
     };
+    let category = app
+        .category_filter
+        .as_ref()
+        .map_or_else(String::new, |category| format!(" .:{category}"));
     let filter_label = format!(
-        " Results [{}:{} {}:{} {}:{}] ",
+        " Results [{}:{} {}:{} {}:{}{category}] ",
         FilterMode::All.shortcut(),
         format_filter(FilterMode::All),
         FilterMode::Passed.shortcut(),
@@ -92,62 +127,231 @@ fn draw_results_list(frame: &mut Frame, area: Rect, app: &mut App) {
         FilterMode::Failed.shortcut(),
         format_filter(FilterMode::Failed),
     );
-    let items: Vec<ListItem> = app
-        .filtered_results()
+    let rows: Vec<Row> = app
+        .rows
         .iter()
-        .map(|r| format_result_item(r))
+        .map(|row| match *row {
+            ListRow::Header(group, count) => format_group_row(group, count),
+            ListRow::Result(i) => format_result_row(&app.results[i], app.symbols),
+        })
         .collect();
-    let list = List::new(items)
+    let widths = [
+        Constraint::Fill(1),
+        Constraint::Length(status_width(app.symbols)),
+        Constraint::Length(12),
+        Constraint::Length(16),
+        Constraint::Length(u16::try_from(ERROR_BAR_STEPS.len()).unwrap_or(u16::MAX)),
+    ];
+    let header = Row::new(["Name", "Status", "Expected", "Actual", "Error"]).style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
+    let table = Table::new(rows, widths)
+        .header(header)
         .block(
             Block::default()
                 .title(filter_label)
                 .borders(Borders::ALL)
                 .border_style(border_style),
         )
-        .highlight_style(
+        .row_highlight_style(
             Style::default()
                 .add_modifier(Modifier::REVERSED)
                 .add_modifier(Modifier::BOLD),
         )
         .highlight_symbol("▶ ");
-    frame.render_stateful_widget(list, area, &mut app.list_state);
+    // Selection lives in `list_state`; mirror it into a table state for rendering
+    let mut table_state = TableState::default()
+        .with_selected(app.list_state.selected())
+        .with_offset(app.list_state.offset());
+    frame.render_stateful_widget(table, area, &mut table_state);
+    *app.list_state.offset_mut() = table_state.offset();
+
+    // Below the header row, so the columns stay visible for context
+    if let Some(message) = app.empty_state_message() {
+        let inner = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+        let body = Rect {
+            y: inner.y + 2,
+            height: inner.height.saturating_sub(2),
+            ..inner
+        };
+        let empty = Paragraph::new(message)
+            .style(Style::default().fg(Color::Yellow))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(empty, body);
+    }
+}
+
+/// Returns the color a status group is drawn in.
+const fn group_color(group: StatusGroup) -> Color {
+    match group {
+        StatusGroup::Failed => Color::Red,
+        StatusGroup::Passed => Color::Green,
+        StatusGroup::Skipped => Color::Yellow,
+    }
+}
+
+fn format_group_header(group: StatusGroup, count: usize) -> ListItem<'static> {
+    ListItem::new(Line::from(Span::styled(
+        format!("── {} ({count}) ──", group.label()),
+        Style::default()
+            .fg(group_color(group))
+            .add_modifier(Modifier::BOLD),
+    )))
+}
+
+fn format_group_row(group: StatusGroup, count: usize) -> Row<'static> {
+    Row::new([format!("── {} ({count}) ──", group.label())]).style(
+        Style::default()
+            .fg(group_color(group))
+            .add_modifier(Modifier::BOLD),
+    )
 }
 
-fn format_result_item(result: &TestResult) -> ListItem<'static> {
-    let name = result.name();
-    let cat_color = category_color(name);
-    let (symbol, symbol_color, detail) = match result {
-        TestResult::Pass { actual, .. } => ("✓", Color::Green, format!("= {actual}")),
+/// Returns the width of the Status column: a symbol, a space, and a label.
+fn status_width(symbols: SymbolSet) -> u16 {
+    let symbol = [symbols.pass, symbols.fail, symbols.skip]
+        .iter()
+        .map(|s| s.chars().count())
+        .max()
+        .unwrap_or_default();
+    // `~` marks a heuristic pass
+    u16::try_from(1 + symbol + " PASS".len()).unwrap_or(u16::MAX)
+}
+
+/// Returns the Name/Status/Expected/Actual cell text for a result row.
+///
+/// For failures without a value and for skips, the Actual column holds
+/// the error or skip reason.
+fn result_cells(result: &TestResult, symbols: SymbolSet) -> [String; 4] {
+    let name = result.name().to_string();
+    match result {
+        TestResult::Pass {
+            expected,
+            actual,
+            heuristic,
+            ..
+        } => [
+            name,
+            format!(
+                "{}{} {}",
+                if *heuristic { "~" } else { "" },
+                symbols.pass,
+                symbols::label(result)
+            ),
+            expected.to_string(),
+            actual.to_string(),
+        ],
         TestResult::Fail {
             expected,
             actual,
             error,
             ..
         } => {
-            let err_detail = actual.map_or_else(
+            let actual = actual.map_or_else(
                 || {
                     error
                         .as_ref()
                         .map_or_else(|| "unknown error".to_string(), Clone::clone)
                 },
-                |a| format!("expected {expected}, got {a}"),
+                |a| a.to_string(),
             );
-            ("✗", Color::Red, err_detail)
+            [
+                name,
+                format!("{} {}", symbols.fail, symbols::label(result)),
+                expected.to_string(),
+                actual,
+            ]
         }
-        TestResult::Skip { reason, .. } => ("⊘", Color::Yellow, reason.clone()),
+        TestResult::Skip { reason, .. } => [
+            name,
+            format!("{} {}", symbols.skip, symbols::label(result)),
+            "-".to_string(),
+            reason.clone(),
+        ],
+    }
+}
+
+fn format_result_row(result: &TestResult, symbols: SymbolSet) -> Row<'static> {
+    let status_color = match result {
+        TestResult::Pass { .. } => Color::Green,
+        TestResult::Fail { .. } => Color::Red,
+        TestResult::Skip { .. } => Color::Yellow,
     };
-    let line = Line::from(vec![
-        Span::raw(" "),
-        Span::styled(symbol, Style::default().fg(symbol_color)),
-        Span::raw(" "),
-        Span::styled(name.to_string(), Style::default().fg(cat_color)),
-        Span::raw(" "),
-        Span::styled(detail, Style::default().fg(Color::DarkGray)),
-    ]);
-    ListItem::new(line)
+    let status_style = if result.is_heuristic_pass() {
+        Style::default()
+            .fg(status_color)
+            .add_modifier(Modifier::DIM)
+    } else {
+        Style::default().fg(status_color)
+    };
+    let cat_color = category_color(result.name());
+    let [name, status, expected, actual] = result_cells(result, symbols);
+    let error = match result {
+        TestResult::Fail {
+            expected,
+            actual: Some(actual),
+            ..
+        } => {
+            let filled = error_bar_fill(*expected, *actual);
+            Cell::from(failure_bar(
+                filled,
+                ERROR_BAR_STEPS.len(),
+                ERROR_BAR_STEPS.len(),
+            ))
+            .style(Style::default().fg(error_bar_color(filled)))
+        }
+        _ => Cell::default(),
+    };
+    Row::new([
+        Cell::from(name).style(Style::default().fg(cat_color)),
+        Cell::from(status).style(status_style),
+        Cell::from(expected).style(Style::default().fg(Color::DarkGray)),
+        Cell::from(actual).style(Style::default().fg(Color::DarkGray)),
+        error,
+    ])
+}
+
+/// Relative errors past which each cell of a failure's error bar fills:
+/// one cell per decade, full from 10% off.
+const ERROR_BAR_STEPS: [f64; 6] = [0.0, 1e-5, 1e-4, 1e-3, 1e-2, 1e-1];
+
+/// Returns how many cells of the error bar a failure with `actual` fills,
+/// from its error relative to `expected` (absolute when `expected` is 0).
+///
+/// Non-finite errors (e.g. NaN against a number) fill the whole bar.
+fn error_bar_fill(expected: f64, actual: f64) -> usize {
+    let diff = (actual - expected).abs();
+    let relative = if expected.abs() > 0.0 {
+        diff / expected.abs()
+    } else {
+        diff
+    };
+    if relative.is_finite() {
+        ERROR_BAR_STEPS
+            .iter()
+            .filter(|&&step| relative > step)
+            .count()
+    } else {
+        ERROR_BAR_STEPS.len()
+    }
+}
+
+/// Returns the color of an error bar with `filled` cells, by severity.
+const fn error_bar_color(filled: usize) -> Color {
+    match filled {
+        0..=2 => Color::Yellow,
+        3..=4 => Color::LightRed,
+        _ => Color::Red,
+    }
 }
 
-fn draw_details(frame: &mut Frame, area: Rect, app: &App) {
+fn draw_details(frame: &mut Frame, area: Rect, app: &mut App) {
     let is_active = app.active_panel == ActivePanel::Details;
     let border_style = if is_active {
         Style::default().fg(Color::Cyan)
@@ -155,32 +359,204 @@ fn draw_details(frame: &mut Frame, area: Rect, app: &App) {
         Style::default().fg(Color::DarkGray)
     };
     let content = app.selected_result().map_or_else(
-        || "No test selected.\n\nUse ↑/↓ or j/k to navigate.".to_string(),
-        format_detail_content,
+        || {
+            app.empty_state_message().map_or_else(
+                || "No test selected.\n\nUse ↑/↓ or j/k to navigate.".to_string(),
+                |message| format!("{message}.\n\nEdit the search (/) or change the filter (1-3)."),
+            )
+        },
+        |result| format_detail_content(result, app.symbols),
     );
-    let detail = Paragraph::new(content).wrap(Wrap { trim: false }).block(
+    let title = if app.detail_wrap {
+        " Details "
+    } else {
+        " Details │ ←/→:scroll "
+    };
+    let detail = detail_paragraph(content, area.width.saturating_sub(2), 0, app).block(
         Block::default()
-            .title(" Details ")
+            .title(title)
             .borders(Borders::ALL)
             .border_style(border_style),
     );
     frame.render_widget(detail, area);
 }
 
-fn format_detail_content(result: &TestResult) -> String {
+/// Builds the details paragraph, scrolled down by `scroll` lines.
+///
+/// Wraps to the `width` columns available when `app.detail_wrap` is set;
+/// otherwise scrolls by `app.detail_hscroll` columns, clamped here so the
+/// longest line stays in view.
+fn detail_paragraph(content: String, width: u16, scroll: u16, app: &mut App) -> Paragraph<'static> {
+    if app.detail_wrap {
+        return Paragraph::new(content)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0));
+    }
+    app.detail_hscroll = app.detail_hscroll.min(max_hscroll(&content, width));
+    Paragraph::new(content).scroll((scroll, app.detail_hscroll))
+}
+
+/// Returns how far `text` can scroll right before its longest line ends
+/// within `width` columns.
+fn max_hscroll(text: &str, width: u16) -> u16 {
+    let longest = text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    u16::try_from(longest.saturating_sub(usize::from(width))).unwrap_or(u16::MAX)
+}
+
+/// Draws the selected test's details over the whole screen, scrolled by
+/// `app.detail_scroll` (clamped here, where the visible height is known).
+fn draw_detail_overlay(frame: &mut Frame, area: Rect, app: &mut App) {
+    let Some(content) = app
+        .selected_result()
+        .map(|result| format_detail_content(result, app.symbols))
+    else {
+        return;
+    };
+    let area = area.inner(Margin {
+        horizontal: 2,
+        vertical: 1,
+    });
+    let inner_width = area.width.saturating_sub(2);
+    let inner_height = area.height.saturating_sub(2);
+    let rows = if app.detail_wrap {
+        wrapped_line_count(&content, inner_width)
+    } else {
+        u16::try_from(content.lines().count()).unwrap_or(u16::MAX)
+    };
+    app.detail_scroll = app.detail_scroll.min(rows.saturating_sub(inner_height));
+
+    let title = if app.detail_wrap {
+        " Details │ ↑/↓ PgUp/PgDn:scroll │ w:unwrap │ Esc:close "
+    } else {
+        " Details │ ↑/↓ ←/→ PgUp/PgDn:scroll │ w:wrap │ Esc:close "
+    };
+    let scroll = app.detail_scroll;
+    let detail = detail_paragraph(content, inner_width, scroll, app).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(detail, area);
+}
+
+/// Estimates how many rows `text` occupies when wrapped to `width` columns.
+fn wrapped_line_count(text: &str, width: u16) -> u16 {
+    let width = usize::from(width.max(1));
+    let rows: usize = text
+        .lines()
+        .map(|line| line.chars().count().max(1).div_ceil(width))
+        .sum();
+    u16::try_from(rows).unwrap_or(u16::MAX)
+}
+
+pub(super) fn format_detail_content(result: &TestResult, symbols: SymbolSet) -> String {
     match result {
-        TestResult::Pass { name, formula, expected, actual } =>
-            format!("Test: {name}\n\nStatus: ✓ PASSED\n\nFormula:\n  {formula}\n\nExpected: {expected}\nActual:   {actual}"),
-        TestResult::Fail { name, formula, expected, actual, error } => {
-            let mut s = format!("Test: {name}\n\nStatus: ✗ FAILED\n\nFormula:\n  {formula}\n\nExpected: {expected}");
-            if let Some(a) = actual { let _ = write!(s, "\nActual:   {a}"); }
-            if let Some(e) = error { let _ = write!(s, "\n\nError:\n  {e}"); }
+        TestResult::Pass {
+            name,
+            formula,
+            expected,
+            actual,
+            heuristic,
+            forge,
+            ..
+        } => {
+            let mut s = format!(
+                "Test: {name}\n\nStatus: {} PASSED\n\nFormula:\n  {formula}\n\nExpected: {expected}\nActual:   {actual}",
+                symbols.pass
+            );
+            if let Some(forge) = forge {
+                let _ = write!(s, "\nForge:    {forge} (forge calculate disagrees)");
+            }
+            if *heuristic {
+                s.push_str("\n\nWarning:\n  No labeled result cell; matched by value");
+            }
             s
         }
-        TestResult::Skip { name, reason } => format!("Test: {name}\n\nStatus: ⊘ SKIPPED\n\nReason: {reason}"),
+        TestResult::Fail {
+            name,
+            formula,
+            expected,
+            actual,
+            error,
+            ..
+        } => {
+            let mut s = format!(
+                "Test: {name}\n\nStatus: {} FAILED\n\nFormula:\n  {formula}\n\nExpected: {expected}",
+                symbols.fail
+            );
+            if let Some(a) = actual {
+                let _ = write!(s, "\nActual:   {a}");
+            }
+            if let Some(e) = error {
+                let _ = write!(s, "\n\nError:\n  {e}");
+            }
+            s
+        }
+        TestResult::Skip { name, reason, .. } => {
+            format!(
+                "Test: {name}\n\nStatus: {} SKIPPED\n\nReason: {reason}",
+                symbols.skip
+            )
+        }
     }
 }
 
+fn draw_summary(frame: &mut Frame, area: Rect, app: &App) {
+    let is_active = app.active_panel == ActivePanel::Summary;
+    let border_style = if is_active {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+    let categories = app.pass_rate_by_category();
+    let max_failed = categories.iter().map(|c| c.2).max().unwrap_or(0);
+    let name_width = categories.iter().map(|c| c.0.len()).max().unwrap_or(0);
+    let lines: Vec<Line> = if max_failed == 0 {
+        vec![Line::styled(
+            "No failures",
+            Style::default().fg(Color::Green),
+        )]
+    } else {
+        categories
+            .iter()
+            .filter(|c| c.2 > 0)
+            .take(area.height.saturating_sub(2).into())
+            .map(|&(category, passed, failed)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{category:<name_width$} "),
+                        Style::default().fg(category_color(category)),
+                    ),
+                    Span::styled(
+                        failure_bar(failed, max_failed, 10),
+                        Style::default().fg(Color::Red),
+                    ),
+                    Span::raw(format!(" {failed} fail / {}", passed + failed)),
+                ])
+            })
+            .collect()
+    };
+    let widget = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Top Failing Categories ")
+            .borders(Borders::ALL)
+            .border_style(border_style),
+    );
+    frame.render_widget(widget, area);
+}
+
+/// Renders a bar of `width` cells filled in proportion to `count / max`.
+fn failure_bar(count: usize, max: usize, width: usize) -> String {
+    let filled = (count * width).checked_div(max).unwrap_or(0).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
 fn draw_stats(frame: &mut Frame, area: Rect, app: &App) {
     let is_active = app.active_panel == ActivePanel::Stats;
     let border_style = if is_active {
@@ -191,12 +567,9 @@ fn draw_stats(frame: &mut Frame, area: Rect, app: &App) {
     let run_state = if app.done { "Done" } else { "Running" };
     let total = app.passed + app.failed + app.skipped;
     let bar_width = 20_usize;
-    let (pass_chars, fail_chars) = if total > 0 {
-        let pass_w = (app.passed * bar_width) / total;
-        (pass_w, bar_width - pass_w)
-    } else {
-        (0, bar_width)
-    };
+    let (pass_chars, fail_chars) = (app.passed * bar_width)
+        .checked_div(total)
+        .map_or((0, bar_width), |pass_w| (pass_w, bar_width - pass_w));
     let bar = format!("[{}{}]", "█".repeat(pass_chars), "░".repeat(fail_chars));
     let perf_info = app.tests_per_second().map_or_else(String::new, |tps| {
         format!(" | {:.1} tests/sec | {}", tps, app.elapsed_time())
@@ -215,14 +588,19 @@ fn draw_stats(frame: &mut Frame, area: Rect, app: &App) {
         Span::styled(perf_info, Style::default().fg(Color::DarkGray)),
     ]);
     let line2 = Line::from(vec![Span::styled(bar, Style::default().fg(Color::Green))]);
-    let widget = Paragraph::new(vec![line1, line2])
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .title(" Stats ")
-                .borders(Borders::ALL)
-                .border_style(border_style),
-        );
+    let mut content = vec![line1, line2];
+    if let Some(note) = heuristic_note(&app.results) {
+        content.push(Line::from(Span::styled(
+            note,
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    let widget = Paragraph::new(content).alignment(Alignment::Center).block(
+        Block::default()
+            .title(" Stats ")
+            .borders(Borders::ALL)
+            .border_style(border_style),
+    );
     frame.render_widget(widget, area);
 }
 
@@ -232,11 +610,9 @@ fn draw_coverage_bar(frame: &mut Frame, area: Rect, app: &App) {
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
         .split(area);
     let unique_funcs = app.unique_functions_tested();
-    let coverage_pct = if DEMO_FUNCTION_COUNT > 0 {
-        (unique_funcs * 100) / DEMO_FUNCTION_COUNT
-    } else {
-        0
-    };
+    let coverage_pct = (unique_funcs * 100)
+        .checked_div(DEMO_FUNCTION_COUNT)
+        .unwrap_or(0);
     let categories = app.coverage_by_category();
     let cat_summary: String = categories
         .iter()
@@ -302,37 +678,19 @@ fn draw_comparison_view(frame: &mut Frame, area: Rect, app: &App) {
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(area);
-    let items: Vec<(String, String, String, bool)> = app
-        .filtered_results()
+    let (forge_items, gnumeric_items): (Vec<ListItem>, Vec<ListItem>) = app
+        .rows
         .iter()
-        .map(|r| {
-            let name = r.name().to_string();
-            match r {
-                TestResult::Pass {
-                    expected, actual, ..
-                } => (name, format!("{expected}"), format!("{actual}"), true),
-                TestResult::Fail {
-                    expected, actual, ..
-                } => {
-                    let actual_str = actual.map_or_else(|| "ERR".to_string(), |a| format!("{a}"));
-                    (name, format!("{expected}"), actual_str, false)
-                }
-                TestResult::Skip { reason, .. } => (name, "—".to_string(), reason.clone(), false),
+        .map(|row| match *row {
+            ListRow::Header(group, count) => (
+                format_group_header(group, count),
+                format_group_header(group, count),
+            ),
+            ListRow::Result(i) => {
+                format_comparison_items(&app.results[i], app.delta_mode, app.symbols)
             }
         })
-        .collect();
-    let forge_items: Vec<ListItem> = items
-        .iter()
-        .map(|(name, expected, _, passed)| {
-            let color = if *passed { Color::Green } else { Color::Red };
-            let symbol = if *passed { "✓" } else { "✗" };
-            ListItem::new(Line::from(vec![
-                Span::styled(format!("{symbol} "), Style::default().fg(color)),
-                Span::raw(format!("{name}: ")),
-                Span::styled(expected, Style::default().fg(Color::Cyan)),
-            ]))
-        })
-        .collect();
+        .unzip();
     let forge_list = List::new(forge_items)
         .block(
             Block::default()
@@ -343,20 +701,13 @@ fn draw_comparison_view(frame: &mut Frame, area: Rect, app: &App) {
         .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
     let mut list_state = app.list_state.clone();
     frame.render_stateful_widget(forge_list, chunks[0], &mut list_state);
-    let gnumeric_items: Vec<ListItem> = items
-        .iter()
-        .map(|(name, _, actual, passed)| {
-            let color = if *passed { Color::Green } else { Color::Red };
-            ListItem::new(Line::from(vec![
-                Span::raw(format!("{name}: ")),
-                Span::styled(actual, Style::default().fg(color)),
-            ]))
-        })
-        .collect();
     let gnumeric_list = List::new(gnumeric_items)
         .block(
             Block::default()
-                .title(" Actual (Gnumeric) ")
+                .title(format!(
+                    " Actual (Gnumeric) │ Δ {} ",
+                    app.delta_mode.label()
+                ))
                 .borders(Borders::ALL)
                 .border_style(border_style),
         )
@@ -365,7 +716,83 @@ fn draw_comparison_view(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_stateful_widget(gnumeric_list, chunks[1], &mut gnumeric_state);
 }
 
+/// Returns the (expected, actual) list items for one result in comparison mode.
+///
+/// The actual item ends with the delta from expected, in `delta_mode`.
+fn format_comparison_items(
+    result: &TestResult,
+    delta_mode: DeltaMode,
+    symbols: SymbolSet,
+) -> (ListItem<'static>, ListItem<'static>) {
+    let name = result.name().to_string();
+    let delta = match result {
+        TestResult::Pass {
+            expected, actual, ..
+        }
+        | TestResult::Fail {
+            expected,
+            actual: Some(actual),
+            ..
+        } => Some(delta_mode.format(*expected, *actual)),
+        _ => None,
+    };
+    let (expected, actual, passed) = match result {
+        TestResult::Pass {
+            expected, actual, ..
+        } => (format!("{expected}"), format!("{actual}"), true),
+        TestResult::Fail {
+            expected, actual, ..
+        } => {
+            let actual_str = actual.map_or_else(|| "ERR".to_string(), |a| format!("{a}"));
+            (format!("{expected}"), actual_str, false)
+        }
+        TestResult::Skip { reason, .. } => ("—".to_string(), reason.clone(), false),
+    };
+    let color = if passed { Color::Green } else { Color::Red };
+    let symbol = symbols.marker(result);
+    let forge = ListItem::new(Line::from(vec![
+        Span::styled(format!("{symbol} "), Style::default().fg(color)),
+        Span::raw(format!("{name}: ")),
+        Span::styled(expected, Style::default().fg(Color::Cyan)),
+    ]));
+    let mut gnumeric_spans = vec![
+        Span::raw(format!("{name}: ")),
+        Span::styled(actual, Style::default().fg(color)),
+    ];
+    if let Some(delta) = delta {
+        gnumeric_spans.push(Span::styled(
+            format!(" Δ {delta}"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    let gnumeric = ListItem::new(Line::from(gnumeric_spans));
+    (forge, gnumeric)
+}
+
 fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
+    if let Some((error, count)) = app.abort_prompt() {
+        let prompt = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("{count} tests in a row failed with: "),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(error.to_string()),
+            Span::styled(
+                " │ Abort run? y:abort │ n:continue",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+        frame.render_widget(prompt, area);
+        return;
+    }
     if let Some(status) = app.status_message() {
         let footer = Paragraph::new(status)
             .style(
@@ -385,7 +812,7 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
             Span::styled("█", Style::default().fg(Color::Cyan)),
             Span::raw(" │ Enter:confirm │ Esc:cancel"),
         ]),
-        InputMode::Normal => {
+        InputMode::Normal | InputMode::ConfirmAbort => {
             let mode_indicator = if app.perf_mode {
                 " [PERF]"
             } else if app.batch_mode {
@@ -395,10 +822,10 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
             };
             let hints = if app.done {
                 format!(
-                    "↑/↓:nav │ 1/2/3:filter │ c:compare │ p:perf │ b:batch │ s:save │ q:exit{mode_indicator}"
+                    "↑/↓:nav │ 1/2/3:filter │ .:category │ v:view │ w:wrap │ c:compare │ d:delta │ g:group │ y/Y:yaml/report │ p:perf │ b:batch │ s:save │ q:exit{mode_indicator}"
                 )
             } else {
-                "↑/↓:nav │ 1/2/3:filter │ c:compare │ q:quit".to_string()
+                "↑/↓:nav │ 1/2/3:filter │ .:category │ c:compare │ q:quit".to_string()
             };
             Line::from(hints)
         }
@@ -420,58 +847,186 @@ mod tests {
     use super::*;
 
     #[test]
-    fn format_result_item_pass() {
+    fn result_cells_pass() {
+        let result = TestResult::Pass {
+            name: "math.test_abs".to_string(),
+            id: None,
+            formula: "=ABS(-1.5)".to_string(),
+            expected: 1.5,
+            actual: 1.5,
+            heuristic: false,
+            forge: None,
+            duration: None,
+        };
+        assert_eq!(
+            result_cells(&result, SymbolSet::UNICODE),
+            ["math.test_abs", "✓ PASS", "1.5", "1.5"].map(String::from)
+        );
+    }
+    #[test]
+    fn result_cells_mark_heuristic_pass() {
         let result = TestResult::Pass {
+            name: "math.test_abs".to_string(),
+            id: None,
+            formula: "=ABS(-1.5)".to_string(),
+            expected: 1.5,
+            actual: 1.5,
+            heuristic: true,
+            forge: None,
+            duration: None,
+        };
+        assert_eq!(result_cells(&result, SymbolSet::UNICODE)[1], "~✓ PASS");
+        assert_eq!(result_cells(&result, SymbolSet::ASCII)[1], "~[P] PASS");
+    }
+    #[test]
+    fn result_cells_fail() {
+        let mut result = TestResult::Fail {
             name: "test".to_string(),
+            id: None,
             formula: "=1".to_string(),
             expected: 1.0,
-            actual: 1.0,
+            actual: Some(2.0),
+            error: None,
+            duration: None,
+        };
+        assert_eq!(
+            result_cells(&result, SymbolSet::UNICODE),
+            ["test", "✗ FAIL", "1", "2"].map(String::from)
+        );
+        if let TestResult::Fail { actual, error, .. } = &mut result {
+            *actual = None;
+            *error = Some("export failed".to_string());
+        }
+        assert_eq!(
+            result_cells(&result, SymbolSet::UNICODE)[3],
+            "export failed"
+        );
+    }
+    #[test]
+    fn error_bar_reflects_error_magnitude() {
+        assert_eq!(error_bar_fill(1.0, 1.000_000_1), 1);
+        assert_eq!(error_bar_fill(1.0, 1.002), 4);
+        assert_eq!(error_bar_fill(-100.0, -101.5), 5);
+        assert_eq!(error_bar_fill(1.0, 2.0), 6);
+        assert_eq!(error_bar_fill(0.0, 0.5), 6);
+        assert_eq!(error_bar_fill(1.0, f64::NAN), 6);
+        assert_eq!(error_bar_fill(f64::INFINITY, 1.0), 6);
+        assert_eq!(error_bar_color(1), Color::Yellow);
+        assert_eq!(error_bar_color(6), Color::Red);
+        assert_eq!(failure_bar(4, 6, 6), "████░░");
+    }
+    #[test]
+    fn result_cells_skip() {
+        let result = TestResult::Skip {
+            name: "test".to_string(),
+            id: None,
+            reason: "unsupported".to_string(),
         };
-        let item = format_result_item(&result);
-        assert!(format!("{item:?}").contains("test"));
+        assert_eq!(
+            result_cells(&result, SymbolSet::UNICODE),
+            ["test", "⊘ SKIP", "-", "unsupported"].map(String::from)
+        );
     }
     #[test]
-    fn format_result_item_fail() {
+    fn result_cells_use_ascii_symbols_when_selected() {
+        let result = TestResult::Skip {
+            name: "test".to_string(),
+            id: None,
+            reason: "unsupported".to_string(),
+        };
+        assert_eq!(result_cells(&result, SymbolSet::ASCII)[1], "[S] SKIP");
+        assert!(format_detail_content(&result, SymbolSet::ASCII).contains("Status: [S] SKIPPED"));
+        assert_eq!(status_width(SymbolSet::ASCII), 9);
+        assert_eq!(status_width(SymbolSet::UNICODE), 7);
+    }
+    #[test]
+    fn wrapped_line_count_accounts_for_long_lines() {
+        assert_eq!(wrapped_line_count("a\n\nbc", 10), 3);
+        assert_eq!(wrapped_line_count(&"x".repeat(25), 10), 3);
+        assert_eq!(wrapped_line_count("abc", 0), 3);
+    }
+    #[test]
+    fn comparison_items_show_delta_in_selected_mode() {
         let result = TestResult::Fail {
             name: "test".to_string(),
+            id: None,
             formula: "=1".to_string(),
-            expected: 1.0,
-            actual: Some(2.0),
+            expected: 4.0,
+            actual: Some(5.0),
+            error: None,
+            duration: None,
+        };
+        let (_, actual) = format_comparison_items(&result, DeltaMode::Absolute, SymbolSet::UNICODE);
+        assert!(format!("{actual:?}").contains("Δ +1"));
+        let (_, actual) = format_comparison_items(&result, DeltaMode::Relative, SymbolSet::UNICODE);
+        assert!(format!("{actual:?}").contains("Δ +25.00%"));
+    }
+    #[test]
+    fn comparison_items_spell_out_status_with_labels() {
+        let result = TestResult::Fail {
+            name: "test".to_string(),
+            id: None,
+            formula: "=1".to_string(),
+            expected: 4.0,
+            actual: Some(5.0),
             error: None,
+            duration: None,
         };
-        let item = format_result_item(&result);
-        assert!(format!("{item:?}").contains("test"));
+        let (forge, _) = format_comparison_items(&result, DeltaMode::Absolute, SymbolSet::UNICODE);
+        assert!(!format!("{forge:?}").contains("FAIL"));
+        let symbols = SymbolSet::UNICODE.with_labels(true);
+        let (forge, _) = format_comparison_items(&result, DeltaMode::Absolute, symbols);
+        assert!(format!("{forge:?}").contains("✗ FAIL "));
+    }
+    #[test]
+    fn max_hscroll_keeps_longest_line_in_view() {
+        assert_eq!(max_hscroll("short\na much longer line", 10), 8);
+        assert_eq!(max_hscroll("fits", 10), 0);
+        assert_eq!(max_hscroll("", 10), 0);
+    }
+    #[test]
+    fn failure_bar_scales_to_max() {
+        assert_eq!(failure_bar(8, 8, 10), "██████████");
+        assert_eq!(failure_bar(4, 8, 10), "█████░░░░░");
+        assert_eq!(failure_bar(0, 0, 4), "░░░░");
     }
     #[test]
     fn format_detail_content_pass() {
         let result = TestResult::Pass {
             name: "test".to_string(),
+            id: None,
             formula: "=1".to_string(),
             expected: 1.0,
             actual: 1.0,
+            heuristic: false,
+            forge: None,
+            duration: None,
         };
-        let content = format_detail_content(&result);
+        let content = format_detail_content(&result, SymbolSet::UNICODE);
         assert!(content.contains("PASSED"));
     }
     #[test]
     fn format_detail_content_fail() {
         let result = TestResult::Fail {
             name: "test".to_string(),
+            id: None,
             formula: "=1".to_string(),
             expected: 1.0,
             actual: Some(2.0),
             error: None,
+            duration: None,
         };
-        let content = format_detail_content(&result);
+        let content = format_detail_content(&result, SymbolSet::UNICODE);
         assert!(content.contains("FAILED"));
     }
     #[test]
     fn format_detail_content_skip() {
         let result = TestResult::Skip {
             name: "test".to_string(),
+            id: None,
             reason: "reason".to_string(),
         };
-        let content = format_detail_content(&result);
+        let content = format_detail_content(&result, SymbolSet::UNICODE);
         assert!(content.contains("SKIPPED"));
     }
 }
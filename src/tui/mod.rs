@@ -7,8 +7,11 @@
 //! - v1.9.0: Side-by-side comparison mode (toggle with `c` key)
 //! - v2.1.0: Perf mode (p key - parallel forge calculate, skip Gnumeric)
 //! - v2.1.0: Batch mode (b key - single XLSX, one Gnumeric call)
+//! - Full-screen details (v key - scrollable, Esc to close)
+//! - Normal mode runs on a background thread, so the UI stays responsive
 
 mod app;
+mod clipboard;
 mod draw;
 mod state;
 
@@ -16,7 +19,10 @@ pub use app::App;
 pub use state::{FilterMode, InputMode};
 
 use std::io::{self, stdout};
-use std::time::Duration;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
@@ -25,27 +31,67 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 
+use crate::report::ReportMetadata;
 use crate::runner::TestRunner;
+use crate::symbols::SymbolSet;
+use crate::types::TestResult;
 use draw::draw_ui;
 
+/// Columns unwrapped details move per ←/→ key press.
+const DETAIL_HSCROLL_STEP: i16 = 4;
+
+/// How long the UI waits for input before redrawing anyway.
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Minimum time between frames, so bursts of results or repeated keys are
+/// batched into one redraw instead of flickering.
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(30);
+
+/// Limits how often the UI redraws.
+#[derive(Debug, Default)]
+struct FrameThrottle {
+    /// When the last frame was drawn (`None` before the first).
+    last_frame: Option<Instant>,
+}
+
+impl FrameThrottle {
+    /// Returns how long until the next frame may be drawn (zero if now).
+    fn wait(&self) -> Duration {
+        self.last_frame.map_or(Duration::ZERO, |t| {
+            MIN_FRAME_INTERVAL.saturating_sub(t.elapsed())
+        })
+    }
+
+    /// Records that a frame was just drawn.
+    fn drawn(&mut self) {
+        self.last_frame = Some(Instant::now());
+    }
+}
+
 /// Runs the TUI interface.
-pub fn run(runner: &TestRunner) -> anyhow::Result<bool> {
+///
+/// `include_skips` controls whether saved JSON lists skipped tests.
+pub fn run(runner: TestRunner, include_skips: bool, symbols: SymbolSet) -> anyhow::Result<bool> {
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
-    let result = run_app(&mut terminal, runner);
+    let result = run_app(&mut terminal, &Arc::new(runner), include_skips, symbols);
     let _ = disable_raw_mode();
     let _ = stdout().execute(LeaveAlternateScreen);
     result
 }
 
+/// Starts a run in the selected mode.
+///
+/// Perf and batch modes finish before this returns. Normal mode runs on a
+/// background thread whose results arrive on the returned channel.
 fn run_tests(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    runner: &TestRunner,
+    runner: &Arc<TestRunner>,
     app: &mut App,
     perf_mode: bool,
     batch_mode: bool,
-) -> anyhow::Result<bool> {
+) -> anyhow::Result<Option<Receiver<TestResult>>> {
     if batch_mode {
         // Batch mode: single XLSX for all tests
         terminal.draw(|frame| draw_ui(frame, app))?;
@@ -53,73 +99,109 @@ fn run_tests(
         for result in results {
             app.add_result(result);
         }
-        terminal.draw(|frame| draw_ui(frame, app))?;
         app.mark_done();
-        return Ok(true);
+        terminal.draw(|frame| draw_ui(frame, app))?;
+        return Ok(None);
     }
 
     if perf_mode {
         // Perf mode: parallel execution with rayon
         terminal.draw(|frame| draw_ui(frame, app))?;
-        let results = runner.run_perf_parallel();
+        let results = runner.run_perf();
         for result in results {
             app.add_result(result);
         }
-        terminal.draw(|frame| draw_ui(frame, app))?;
         app.mark_done();
-        return Ok(true);
-    }
-
-    // Normal mode: sequential with Gnumeric validation
-    // First, add all skip results
-    for skip_case in runner.skip_cases() {
-        app.add_result(crate::types::TestResult::Skip {
-            name: skip_case.name.clone(),
-            reason: skip_case.reason.clone(),
-        });
         terminal.draw(|frame| draw_ui(frame, app))?;
+        return Ok(None);
     }
 
-    // Then run actual tests
-    let test_cases = runner.test_cases().to_vec();
-    for test_case in test_cases {
-        if event::poll(Duration::from_millis(10))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('q') {
-                    return Ok(false);
-                }
-            }
+    // Normal mode: sequential with Gnumeric validation, off the UI thread.
+    // Quitting drops the receiver, which stops the worker after its current test.
+    let (tx, rx) = mpsc::channel();
+    let runner = Arc::clone(runner);
+    thread::spawn(move || runner.stream_all(&tx));
+    Ok(Some(rx))
+}
+
+/// Adds every result received so far to `app`.
+///
+/// Marks the run done and clears `pending` once the worker has finished.
+fn drain_results(app: &mut App, pending: &mut Option<Receiver<TestResult>>) {
+    let Some(rx) = pending.as_ref() else {
+        return;
+    };
+    loop {
+        match rx.try_recv() {
+            Ok(result) => app.add_result(result),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => break,
         }
-        terminal.draw(|frame| draw_ui(frame, app))?;
-        let result = runner.run_test(&test_case);
-        app.add_result(result);
-        terminal.draw(|frame| draw_ui(frame, app))?;
     }
-
     app.mark_done();
-    Ok(true)
+    *pending = None;
 }
 
+#[allow(clippy::too_many_lines)]
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    runner: &TestRunner,
+    runner: &Arc<TestRunner>,
+    include_skips: bool,
+    symbols: SymbolSet,
 ) -> anyhow::Result<bool> {
     let total = runner.total_tests();
-    let mut app = App::new(total);
+    let mut app = App::new(total)
+        .with_section(runner.section())
+        .with_skips(include_skips)
+        .with_symbols(symbols)
+        .with_spec_files(runner.spec_files().to_vec())
+        .with_metadata(ReportMetadata::collect(runner.forge_binary()));
     let mut perf_mode = false;
     let mut batch_mode = false;
 
     // Initial run (full validation)
-    run_tests(terminal, runner, &mut app, perf_mode, batch_mode)?;
+    let mut pending = run_tests(terminal, runner, &mut app, perf_mode, batch_mode)?;
 
+    let mut throttle = FrameThrottle::default();
     loop {
-        terminal.draw(|frame| draw_ui(frame, &mut app))?;
-        if event::poll(Duration::from_millis(50))? {
+        let running = pending.is_some();
+        drain_results(&mut app, &mut pending);
+        // The frame showing a finished run is never held back
+        let finished = running && pending.is_none();
+        let wait = throttle.wait();
+        let timeout = if finished || wait.is_zero() {
+            terminal.draw(|frame| draw_ui(frame, &mut app))?;
+            throttle.drawn();
+            EVENT_POLL_INTERVAL
+        } else {
+            wait
+        };
+        if event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
+                    if app.detail_overlay {
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('v' | 'q') => app.close_detail_overlay(),
+                            KeyCode::Up | KeyCode::Char('k') => app.scroll_detail(-1),
+                            KeyCode::Down | KeyCode::Char('j') => app.scroll_detail(1),
+                            KeyCode::PageUp => app.scroll_detail(-10),
+                            KeyCode::PageDown => app.scroll_detail(10),
+                            KeyCode::Char('w') => app.toggle_detail_wrap(),
+                            KeyCode::Left | KeyCode::Char('h') => {
+                                app.scroll_detail_horizontally(-DETAIL_HSCROLL_STEP);
+                            }
+                            KeyCode::Right | KeyCode::Char('l') => {
+                                app.scroll_detail_horizontally(DETAIL_HSCROLL_STEP);
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
                     match app.input_mode {
                         InputMode::Normal => match key.code {
-                            KeyCode::Char('q') | KeyCode::Esc => return Ok(app.failed == 0),
+                            KeyCode::Char('q') | KeyCode::Esc => {
+                                return Ok(app.done && app.failed == 0)
+                            }
                             KeyCode::Enter if app.done => return Ok(app.failed == 0),
                             KeyCode::Char('/') => app.enter_search_mode(),
                             KeyCode::Char('s') if app.done => {
@@ -134,7 +216,8 @@ fn run_app(
                                 app.reset(perf_mode, batch_mode);
                                 let mode_name = if perf_mode { "PERF" } else { "FULL" };
                                 app.set_status(format!("Rerunning in {mode_name} mode..."));
-                                run_tests(terminal, runner, &mut app, perf_mode, batch_mode)?;
+                                pending =
+                                    run_tests(terminal, runner, &mut app, perf_mode, batch_mode)?;
                             }
                             KeyCode::Char('b') if app.done => {
                                 // Toggle batch mode and rerun
@@ -143,9 +226,22 @@ fn run_app(
                                 app.reset(perf_mode, batch_mode);
                                 let mode_name = if batch_mode { "BATCH" } else { "FULL" };
                                 app.set_status(format!("Rerunning in {mode_name} mode..."));
-                                run_tests(terminal, runner, &mut app, perf_mode, batch_mode)?;
+                                pending =
+                                    run_tests(terminal, runner, &mut app, perf_mode, batch_mode)?;
                             }
                             KeyCode::Char('c') => app.toggle_comparison_mode(),
+                            KeyCode::Char('d') => app.toggle_delta_mode(),
+                            KeyCode::Char('g') => app.toggle_grouping(),
+                            KeyCode::Char('y') => app.copy_selected_yaml(),
+                            KeyCode::Char('Y') => app.copy_selected_report(),
+                            KeyCode::Char('v') => app.open_detail_overlay(),
+                            KeyCode::Char('w') => app.toggle_detail_wrap(),
+                            KeyCode::Left | KeyCode::Char('h') => {
+                                app.scroll_detail_horizontally(-DETAIL_HSCROLL_STEP);
+                            }
+                            KeyCode::Right | KeyCode::Char('l') => {
+                                app.scroll_detail_horizontally(DETAIL_HSCROLL_STEP);
+                            }
                             KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
                             KeyCode::Down | KeyCode::Char('j') => app.select_next(),
                             KeyCode::Tab => app.next_panel(),
@@ -153,6 +249,16 @@ fn run_app(
                             KeyCode::Char('1') => app.set_filter(FilterMode::All),
                             KeyCode::Char('2') => app.set_filter(FilterMode::Passed),
                             KeyCode::Char('3') => app.set_filter(FilterMode::Failed),
+                            KeyCode::Char('.') => app.toggle_category_filter(),
+                            _ => {}
+                        },
+                        InputMode::ConfirmAbort => match key.code {
+                            KeyCode::Char('y') => {
+                                // Dropping the receiver stops the worker
+                                pending = None;
+                                app.answer_abort_prompt(true);
+                            }
+                            KeyCode::Char('n') | KeyCode::Esc => app.answer_abort_prompt(false),
                             _ => {}
                         },
                         InputMode::Search => match key.code {
@@ -177,3 +283,52 @@ fn run_app(
         }
     }
 }
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skip(name: &str) -> TestResult {
+        TestResult::Skip {
+            name: name.to_string(),
+            id: None,
+            reason: "not yet".to_string(),
+        }
+    }
+
+    #[test]
+    fn drain_results_adds_results_until_worker_finishes() {
+        let mut app = App::new(3);
+        let (tx, rx) = mpsc::channel();
+        let mut pending = Some(rx);
+
+        tx.send(skip("a.test_one")).unwrap();
+        tx.send(skip("a.test_two")).unwrap();
+        drain_results(&mut app, &mut pending);
+        assert_eq!(app.skipped, 2);
+        assert!(!app.done);
+        assert!(pending.is_some());
+
+        tx.send(skip("a.test_three")).unwrap();
+        drop(tx);
+        drain_results(&mut app, &mut pending);
+        assert_eq!(app.skipped, 3);
+        assert!(app.done);
+        assert!(pending.is_none());
+    }
+
+    #[test]
+    fn frame_throttle_spaces_out_frames() {
+        let mut throttle = FrameThrottle::default();
+        assert_eq!(throttle.wait(), Duration::ZERO);
+        throttle.drawn();
+        let wait = throttle.wait();
+        assert!(wait > Duration::ZERO && wait <= MIN_FRAME_INTERVAL);
+        thread::sleep(MIN_FRAME_INTERVAL);
+        assert_eq!(throttle.wait(), Duration::ZERO);
+    }
+}
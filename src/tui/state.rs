@@ -2,6 +2,8 @@
 
 use ratatui::style::Color;
 
+use crate::types::TestResult;
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Input Mode
 // ─────────────────────────────────────────────────────────────────────────────
@@ -14,6 +16,8 @@ pub enum InputMode {
     Normal,
     /// Search mode - typing filters results.
     Search,
+    /// Asking whether to abort a run stuck on a repeating error.
+    ConfirmAbort,
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -69,6 +73,116 @@ impl FilterMode {
     }
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Status Grouping
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Status sections of the grouped results view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusGroup {
+    /// Failed tests (shown first - the actionable ones).
+    Failed,
+    /// Passed tests.
+    Passed,
+    /// Skipped tests.
+    Skipped,
+}
+
+impl StatusGroup {
+    /// Groups in display order.
+    pub const ORDER: [Self; 3] = [Self::Failed, Self::Passed, Self::Skipped];
+
+    /// Returns the group a result belongs to.
+    pub const fn of(result: &TestResult) -> Self {
+        match result {
+            TestResult::Fail { .. } => Self::Failed,
+            TestResult::Pass { .. } => Self::Passed,
+            TestResult::Skip { .. } => Self::Skipped,
+        }
+    }
+
+    /// Returns the display label for this group.
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Failed => "Failed",
+            Self::Passed => "Passed",
+            Self::Skipped => "Skipped",
+        }
+    }
+}
+
+/// A row of the results list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListRow {
+    /// Group separator with the number of results under it.
+    Header(StatusGroup, usize),
+    /// Index into the app's results.
+    Result(usize),
+}
+
+impl ListRow {
+    /// Returns the result index, or `None` for header rows.
+    pub const fn result_index(self) -> Option<usize> {
+        match self {
+            Self::Header(..) => None,
+            Self::Result(i) => Some(i),
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Delta Mode
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// How comparison mode shows the difference between actual and expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeltaMode {
+    /// `actual - expected`.
+    #[default]
+    Absolute,
+    /// `(actual - expected) / |expected|`, as a percentage.
+    Relative,
+}
+
+impl DeltaMode {
+    /// Returns the other mode.
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Absolute => Self::Relative,
+            Self::Relative => Self::Absolute,
+        }
+    }
+
+    /// Returns the short label shown in the comparison header.
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Absolute => "abs",
+            Self::Relative => "rel",
+        }
+    }
+
+    /// Formats the difference of `actual` from `expected`.
+    ///
+    /// A relative delta from an expected value of zero is reported as `±∞`
+    /// (or `+0%` when both are zero).
+    pub fn format(self, expected: f64, actual: f64) -> String {
+        let diff = actual - expected;
+        match self {
+            Self::Absolute => format!("{diff:+}"),
+            Self::Relative if expected == 0.0 => {
+                if diff == 0.0 {
+                    "+0%".to_string()
+                } else if diff > 0.0 {
+                    "+∞".to_string()
+                } else {
+                    "-∞".to_string()
+                }
+            }
+            Self::Relative => format!("{:+.2}%", diff / expected.abs() * 100.0),
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Active Panel
 // ─────────────────────────────────────────────────────────────────────────────
@@ -81,6 +195,8 @@ pub enum ActivePanel {
     Results,
     /// The detail pane showing selected test info.
     Details,
+    /// The summary panel ranking categories by failure count.
+    Summary,
     /// The statistics panel.
     Stats,
 }
@@ -90,7 +206,8 @@ impl ActivePanel {
     pub const fn next(self) -> Self {
         match self {
             Self::Results => Self::Details,
-            Self::Details => Self::Stats,
+            Self::Details => Self::Summary,
+            Self::Summary => Self::Stats,
             Self::Stats => Self::Results,
         }
     }
@@ -100,7 +217,8 @@ impl ActivePanel {
         match self {
             Self::Results => Self::Stats,
             Self::Details => Self::Results,
-            Self::Stats => Self::Details,
+            Self::Summary => Self::Details,
+            Self::Stats => Self::Summary,
         }
     }
 }
@@ -113,6 +231,17 @@ impl ActivePanel {
 mod tests {
     use super::*;
 
+    #[test]
+    fn delta_mode_formats_absolute_and_relative() {
+        assert_eq!(DeltaMode::Absolute.format(2.0, 2.5), "+0.5");
+        assert_eq!(DeltaMode::Absolute.format(2.0, 1.0), "-1");
+        assert_eq!(DeltaMode::Relative.format(200.0, 201.0), "+0.50%");
+        assert_eq!(DeltaMode::Relative.format(-4.0, -5.0), "-25.00%");
+        assert_eq!(DeltaMode::Relative.format(0.0, 0.0), "+0%");
+        assert_eq!(DeltaMode::Relative.format(0.0, -1.0), "-∞");
+        assert_eq!(DeltaMode::Absolute.toggled(), DeltaMode::Relative);
+    }
+
     #[test]
     fn filter_mode_labels() {
         assert_eq!(FilterMode::All.label(), "All");
@@ -132,10 +261,19 @@ mod tests {
         assert_eq!(FilterMode::default(), FilterMode::All);
     }
 
+    #[test]
+    fn status_group_order_puts_failures_first() {
+        assert_eq!(StatusGroup::ORDER[0], StatusGroup::Failed);
+        assert_eq!(StatusGroup::Skipped.label(), "Skipped");
+        assert_eq!(ListRow::Header(StatusGroup::Passed, 2).result_index(), None);
+        assert_eq!(ListRow::Result(4).result_index(), Some(4));
+    }
+
     #[test]
     fn active_panel_next_cycles() {
         assert_eq!(ActivePanel::Results.next(), ActivePanel::Details);
-        assert_eq!(ActivePanel::Details.next(), ActivePanel::Stats);
+        assert_eq!(ActivePanel::Details.next(), ActivePanel::Summary);
+        assert_eq!(ActivePanel::Summary.next(), ActivePanel::Stats);
         assert_eq!(ActivePanel::Stats.next(), ActivePanel::Results);
     }
 
@@ -143,7 +281,21 @@ mod tests {
     fn active_panel_prev_cycles() {
         assert_eq!(ActivePanel::Results.prev(), ActivePanel::Stats);
         assert_eq!(ActivePanel::Details.prev(), ActivePanel::Results);
-        assert_eq!(ActivePanel::Stats.prev(), ActivePanel::Details);
+        assert_eq!(ActivePanel::Summary.prev(), ActivePanel::Details);
+        assert_eq!(ActivePanel::Stats.prev(), ActivePanel::Summary);
+    }
+
+    #[test]
+    fn active_panel_cycle_visits_every_panel() {
+        let mut panel = ActivePanel::Results;
+        for _ in 0..4 {
+            panel = panel.next();
+        }
+        assert_eq!(panel, ActivePanel::Results);
+        for _ in 0..4 {
+            panel = panel.prev();
+        }
+        assert_eq!(panel, ActivePanel::Results);
     }
 
     #[test]
@@ -5,8 +5,11 @@
 // Allow dead code for serde types that are deserialized but not all fields used
 #![allow(dead_code)]
 
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Test Specification Types
@@ -21,15 +24,91 @@ pub struct TestSpec {
     #[serde(rename = "_forge_version")]
     pub forge_version: String,
 
+    /// Section that tests written at the top level belong to, so a spec
+    /// of small tests needn't nest them all under one section.
+    #[serde(rename = "_default_section")]
+    pub default_section: Option<String>,
+
     /// Named sections containing test definitions.
     #[serde(flatten)]
     pub sections: HashMap<String, Section>,
 }
 
+/// Which spec file formats to load from the tests directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum InputFormat {
+    /// Load both, choosing the parser by file extension.
+    #[default]
+    Auto,
+    /// Load only `.yaml`/`.yml` specs.
+    Yaml,
+    /// Load only `.json` specs.
+    Json,
+}
+
+impl InputFormat {
+    /// Returns the concrete format of `path`, or `None` if it shouldn't be loaded.
+    pub fn format_of(self, path: &Path) -> Option<Self> {
+        let format = match path.extension()?.to_str()? {
+            "yaml" | "yml" => Self::Yaml,
+            "json" => Self::Json,
+            _ => return None,
+        };
+        (self == Self::Auto || self == format).then_some(format)
+    }
+}
+
+impl TestSpec {
+    /// Parses a spec in the given concrete format (`Yaml` or `Json`).
+    pub fn parse(content: &str, format: InputFormat) -> Result<Self, String> {
+        match format {
+            InputFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            InputFormat::Yaml | InputFormat::Auto => {
+                serde_yaml_ng::from_str(content).map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// Returns every test in the spec as `(section, name, scalar)`.
+    ///
+    /// Sections starting with `_` and `scenarios` hold no tests. Tests
+    /// written at the top level belong to `_default_section`; a spec
+    /// without one fails to load (see [`spec_errors`]).
+    fn tests(&self) -> impl Iterator<Item = (&str, &str, &Scalar)> {
+        let sections = self
+            .sections
+            .iter()
+            .filter(|(name, _)| !name.starts_with('_') && *name != "scenarios");
+        let grouped = sections
+            .clone()
+            .filter_map(|(name, section)| match section {
+                Section::ScalarGroup(scalars) => Some((name, scalars)),
+                Section::Test(_) | Section::Table(_) => None,
+            })
+            .flat_map(|(name, scalars)| {
+                scalars
+                    .iter()
+                    .map(move |(test, scalar)| (name.as_str(), test.as_str(), scalar))
+            });
+        let flat = sections.filter_map(move |(name, section)| match section {
+            Section::Test(scalar) => {
+                let default = self.default_section.as_deref()?;
+                Some((default, name.as_str(), &**scalar))
+            }
+            Section::ScalarGroup(_) | Section::Table(_) => None,
+        });
+        grouped.chain(flat)
+    }
+}
+
 /// A section in the test spec (e.g., "assumptions", "projections").
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 pub enum Section {
+    /// A test written at the top level, outside any section (see
+    /// [`TestSpec::default_section`]).
+    #[serde(deserialize_with = "deserialize_flat_test")]
+    Test(Box<Scalar>),
     /// A group of scalar values with optional formulas.
     ScalarGroup(HashMap<String, Scalar>),
     /// A table with columns of data.
@@ -44,9 +123,110 @@ pub struct Scalar {
     /// The Excel formula to evaluate.
     pub formula: Option<String>,
     /// Expected value for E2E validation (forge-e2e specific).
-    pub expected: Option<f64>,
+    ///
+    /// May be text (`"FF"`) when `base` is set, a non-finite value
+    /// (`.inf`, `-.inf`, `.nan`, or text such as `"inf"`), or a duration
+    /// (`"1:30:00"`), compared as Excel's fraction of a day.
+    pub expected: Option<Expected>,
+    /// Acceptable values, any of which passes the test.
+    ///
+    /// For implementation-defined results (e.g. rounding half-to-even vs
+    /// half-away-from-zero) where forge and Gnumeric may legitimately
+    /// disagree. May replace `expected` or add to it.
+    pub expected_any: Option<Vec<f64>>,
+    /// Reference XLSX cell holding the expected value, as
+    /// `file.xlsx!Sheet!A1` (file path relative to the spec file).
+    pub expected_from_xlsx: Option<String>,
     /// Skip reason (if set, test is skipped with this message).
     pub skip: Option<String>,
+    /// Tests that must pass before this one runs: names in the same
+    /// section (`test_a`) or fully qualified (`other.test_a`).
+    pub depends_on: Option<Vec<String>>,
+    /// Factor the actual value is multiplied by before comparison, for
+    /// models whose output differs from `expected` only by units (e.g.
+    /// `1000` when forge reports thousands).
+    pub scale: Option<f64>,
+    /// Absolute tolerance for this test, overriding the category and
+    /// global defaults.
+    pub tolerance: Option<f64>,
+    /// Tolerance in basis points, for rates (`5` accepts ±0.0005). A spec
+    /// setting both this and `tolerance` fails to load.
+    pub tolerance_bps: Option<f64>,
+    /// Tolerance in units in the last place (`2` accepts the two nearest
+    /// doubles either side of `expected`). Ignored when `tolerance` or
+    /// `tolerance_bps` is set.
+    pub tolerance_ulps: Option<u64>,
+    /// Radix of a text result (e.g. `16` for `DEC2HEX`): the result cell
+    /// and a text `expected` are both parsed as integers in this base.
+    pub base: Option<u32>,
+    /// Cell the exported result lands in (`B2`, or `Sheet!B2` for one
+    /// sheet), read instead of searching the CSV for the result label.
+    pub result_cell: Option<String>,
+    /// Stable identifier that survives renames of the test; see
+    /// [`TestCase::key`].
+    pub id: Option<String>,
+    /// Named outputs asserted from one export, each a scalar with its own
+    /// `formula` and `expected` (`outputs: {net: {...}, gross: {...}}`).
+    ///
+    /// Each becomes a test named `<section>.<test>.<output>`; the test's
+    /// own `formula` and `expected` are not used, but its `skip` and
+    /// `depends_on` apply to every output.
+    pub outputs: Option<HashMap<String, Self>>,
+    /// Whether `forge-demo export` must reject the formula: the test
+    /// passes when the export fails and fails when it succeeds. No
+    /// `expected` value is needed.
+    pub expect_export_error: Option<bool>,
+}
+
+/// Deserializes a top-level test, rejecting maps with none of a test's
+/// keys so that sections fall through to the other [`Section`] variants.
+fn deserialize_flat_test<'de, D>(deserializer: D) -> Result<Box<Scalar>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let scalar = Scalar::deserialize(deserializer)?;
+    let is_test = scalar.formula.is_some()
+        || scalar.value.is_some()
+        || scalar.expected.is_some()
+        || scalar.expected_any.is_some()
+        || scalar.expected_from_xlsx.is_some()
+        || scalar.outputs.is_some()
+        || scalar.expect_export_error.is_some();
+    if is_test {
+        Ok(Box::new(scalar))
+    } else {
+        Err(serde::de::Error::custom("not a test"))
+    }
+}
+
+/// One basis point, as an absolute difference between rates.
+const BASIS_POINT: f64 = 0.0001;
+
+impl Scalar {
+    /// Returns the test's absolute tolerance, from `tolerance` or else
+    /// `tolerance_bps`.
+    fn tolerance(&self) -> Option<f64> {
+        self.tolerance
+            .or_else(|| self.tolerance_bps.map(|bps| bps * BASIS_POINT))
+    }
+
+    /// Returns the test's ULP tolerance, unless an absolute one is set.
+    fn tolerance_ulps(&self) -> Option<u64> {
+        self.tolerance_ulps.filter(|_| self.tolerance().is_none())
+    }
+}
+
+/// An inline expected value: a number, an integer written in the test's
+/// `base`, non-finite text (`"inf"`, `"-inf"`, `"nan"`), or a duration
+/// (`"1:30:00"`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Expected {
+    /// A plain numeric value.
+    Number(f64),
+    /// Digits in the test's `base` (e.g. `"FF"`), non-finite text, or a
+    /// duration.
+    Text(String),
 }
 
 /// A table column (array of values or formula).
@@ -66,7 +246,7 @@ pub enum TableColumn {
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Individual test case extracted from a spec.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TestCase {
     /// Fully qualified name (e.g., `assumptions.test_abs`).
     pub name: String,
@@ -74,6 +254,253 @@ pub struct TestCase {
     pub formula: String,
     /// The expected result value.
     pub expected: f64,
+    /// Further acceptable values (`expected_any`); empty for most tests.
+    pub expected_any: Vec<f64>,
+    /// Fully qualified names of tests that must pass first.
+    pub depends_on: Vec<String>,
+    /// Factor applied to the actual value before comparison (`scale`).
+    pub scale: Option<f64>,
+    /// Per-test tolerance; see [`crate::config::Config::tolerance_for`].
+    pub tolerance: Option<f64>,
+    /// Per-test tolerance in ULPs (`tolerance_ulps`); replaces the
+    /// absolute tolerance when set.
+    pub tolerance_ulps: Option<u64>,
+    /// Radix the result is written in (`base`); `None` for numeric results.
+    pub base: Option<u32>,
+    /// Explicit result cell (`result_cell`); `None` to search by label.
+    ///
+    /// Only single-test runs use it: batch mode lays many results out in
+    /// one workbook and always finds them by label.
+    pub result_cell: Option<String>,
+    /// Stable identifier from the spec (`id`), if any.
+    pub id: Option<String>,
+    /// 1-based line of the spec file defining the test, if it could be
+    /// found; see [`source_line`].
+    pub line: Option<usize>,
+    /// Multi-output test this case is an output of (`<section>.<test>`);
+    /// its outputs run from one export in normal mode.
+    pub output_of: Option<String>,
+    /// Whether the test passes only if forge-demo rejects the formula
+    /// (`expect_export_error`); `expected` is then NaN and unused.
+    pub expect_export_error: bool,
+    /// Whether `expected` was written as a duration (`"1:30:00"`), so
+    /// results are read as durations too.
+    pub duration: bool,
+}
+
+impl TestCase {
+    /// Returns `true` if `actual` is within `tolerance` of `expected` or any
+    /// `expected_any` value.
+    ///
+    /// `actual` is scaled first, so the tolerance applies in the units of
+    /// `expected`. Tests with `tolerance_ulps` are compared in ULPs instead.
+    pub fn accepts(&self, actual: f64, tolerance: f64) -> bool {
+        let actual = self.scaled(actual);
+        self.acceptable_values()
+            .any(|v| self.values_agree(actual, v, tolerance))
+    }
+
+    /// Returns `true` if `actual` is within `tolerance` of `expected`, or
+    /// within `tolerance_ulps` ULPs for tests that set it. Neither value is
+    /// scaled.
+    pub fn values_agree(&self, actual: f64, expected: f64, tolerance: f64) -> bool {
+        self.tolerance_ulps.map_or_else(
+            || values_match(actual, expected, tolerance),
+            |ulps| values_match_ulps(actual, expected, ulps),
+        )
+    }
+
+    /// Returns `actual` converted to the units of `expected`.
+    pub fn scaled(&self, actual: f64) -> f64 {
+        self.scale.map_or(actual, |scale| actual * scale)
+    }
+
+    /// Returns the key identifying this test across runs: its `id`, or its
+    /// name when it has none.
+    pub fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Returns every value this test accepts, `expected` first.
+    pub fn acceptable_values(&self) -> impl Iterator<Item = f64> + '_ {
+        std::iter::once(self.expected).chain(self.expected_any.iter().copied())
+    }
+
+    /// Parses a result cell: as an integer in `base` when set, otherwise
+    /// as a number, or as a duration for cells formatted as times when the
+    /// test expects a duration.
+    pub fn parse_actual(&self, cell: &str) -> Option<f64> {
+        self.base.map_or_else(
+            || {
+                parse_number(cell).or_else(|| {
+                    self.duration
+                        .then(|| parse_duration_to_days(cell))
+                        .flatten()
+                })
+            },
+            |base| parse_in_base(cell, base),
+        )
+    }
+}
+
+/// Returns `true` if `actual` is within `tolerance` of `expected`.
+///
+/// Non-finite values only match themselves: an infinite `expected` needs
+/// the same infinity, and a NaN `expected` needs a NaN (which `==` never
+/// reports).
+pub fn values_match(actual: f64, expected: f64, tolerance: f64) -> bool {
+    if expected.is_nan() {
+        actual.is_nan()
+    } else if expected.is_infinite() {
+        actual.is_infinite() && actual.is_sign_positive() == expected.is_sign_positive()
+    } else {
+        (actual - expected).abs() <= tolerance
+    }
+}
+
+/// Returns `true` if `actual` is at most `ulps` representable doubles
+/// away from `expected`.
+///
+/// Non-finite values match as in [`values_match`]: MAX and infinity are
+/// adjacent bit patterns, but never within any ULP tolerance.
+pub fn values_match_ulps(actual: f64, expected: f64, ulps: u64) -> bool {
+    if actual.is_finite() && expected.is_finite() {
+        ulp_distance(actual, expected) <= ulps
+    } else {
+        values_match(actual, expected, 0.0)
+    }
+}
+
+/// Returns the number of representable doubles between `a` and `b`
+/// (`0.0` and `-0.0` are the same value).
+pub fn ulp_distance(a: f64, b: f64) -> u64 {
+    // Maps the sign-magnitude bit patterns onto one monotonic integer line
+    let ordered = |x: f64| {
+        let bits = x.to_bits().cast_signed();
+        if bits < 0 {
+            i64::MIN - bits
+        } else {
+            bits
+        }
+    };
+    ordered(a).abs_diff(ordered(b))
+}
+
+/// Parses non-finite text: `inf`, `infinity` or `nan`, case-insensitively,
+/// with an optional sign and YAML's leading `.` (`-.inf`).
+pub fn parse_non_finite(text: &str) -> Option<f64> {
+    let text = text.trim().to_ascii_lowercase();
+    let (sign, rest) = text.strip_prefix('-').map_or_else(
+        || (1.0, text.strip_prefix('+').unwrap_or(&text)),
+        |rest| (-1.0, rest),
+    );
+    match rest.strip_prefix('.').unwrap_or(rest) {
+        "inf" | "infinity" => Some(sign * f64::INFINITY),
+        "nan" => Some(f64::NAN),
+        _ => None,
+    }
+}
+
+/// Parses a numeric cell, ignoring thousands separators.
+pub fn parse_number(cell: &str) -> Option<f64> {
+    cell.replace(',', "").parse().ok()
+}
+
+/// Parses `text` as a signed integer in `base` (2 to 36), case-insensitively.
+///
+/// Returns `None` for an invalid base or digits outside it.
+#[allow(clippy::cast_precision_loss)] // Engineering functions stay far below 2^53
+pub fn parse_in_base(text: &str, base: u32) -> Option<f64> {
+    if !(2..=36).contains(&base) {
+        return None;
+    }
+    i64::from_str_radix(text.trim(), base)
+        .ok()
+        .map(|v| v as f64)
+}
+
+/// Parses a duration as Excel stores it, in days: `[-]H:MM[:SS[.fff]]`
+/// (hours may exceed 24, so `36:00:00` is 1.5) or a plain day fraction
+/// (`0.0625`).
+///
+/// Returns `None` for anything else, including minutes or seconds of 60
+/// or more.
+pub fn parse_duration_to_days(text: &str) -> Option<f64> {
+    const SECONDS_PER_DAY: f64 = 86_400.0;
+
+    let text = text.trim();
+    let (sign, body) = text
+        .strip_prefix('-')
+        .map_or((1.0, text), |rest| (-1.0, rest));
+    if !body.contains(':') {
+        let days: f64 = body.parse().ok()?;
+        return days.is_finite().then_some(sign * days);
+    }
+    let (hours, minutes, seconds) = match body.split(':').collect::<Vec<_>>()[..] {
+        [h, m] => (h, m, "0"),
+        [h, m, s] => (h, m, s),
+        _ => return None,
+    };
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok().filter(|m| *m < 60)?;
+    let seconds: f64 = seconds.parse().ok().filter(|s| (0.0..60.0).contains(s))?;
+    let total = f64::from(hours).mul_add(3600.0, f64::from(minutes) * 60.0) + seconds;
+    Some(sign * total / SECONDS_PER_DAY)
+}
+
+/// Formats an integral `value` in `base` with uppercase digits, as Excel's
+/// `DEC2HEX` family does. Non-integral values are printed as-is.
+#[allow(clippy::cast_possible_truncation)] // Checked integral and in range first
+pub fn format_in_base(value: f64, base: u32) -> String {
+    if value.fract() != 0.0 || value.abs() >= 2f64.powi(53) || !(2..=36).contains(&base) {
+        return value.to_string();
+    }
+    let mut n = (value as i64).unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        digits.push(char::from_digit((n % u64::from(base)) as u32, base).unwrap_or('?'));
+        n /= u64::from(base);
+        if n == 0 {
+            break;
+        }
+    }
+    if value < 0.0 {
+        digits.push('-');
+    }
+    digits.iter().rev().collect::<String>().to_uppercase()
+}
+
+/// Functions whose result depends on the clock or a random source.
+pub const VOLATILE_FUNCTIONS: &[&str] = &["NOW", "TODAY", "RAND", "RANDBETWEEN"];
+
+/// Returns the volatile functions `formula` calls, in order of first use.
+///
+/// Names are matched case-insensitively when followed by `(`; text inside
+/// string literals and longer identifiers (`RANDARRAY`, `MY_NOW`) are ignored.
+pub fn volatile_functions(formula: &str) -> Vec<&'static str> {
+    let mut found = Vec::new();
+    let mut in_string = false;
+    let mut ident = String::new();
+    for c in formula.chars() {
+        if c == '"' {
+            in_string = !in_string;
+            ident.clear();
+        } else if in_string {
+            // Literal text can't call functions
+        } else if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            ident.push(c.to_ascii_uppercase());
+        } else {
+            if c == '(' {
+                if let Some(&func) = VOLATILE_FUNCTIONS.iter().find(|&&f| f == ident) {
+                    if !found.contains(&func) {
+                        found.push(func);
+                    }
+                }
+            }
+            ident.clear();
+        }
+    }
+    found
 }
 
 /// A test case that should be skipped.
@@ -83,6 +510,40 @@ pub struct SkipCase {
     pub name: String,
     /// Reason for skipping.
     pub reason: String,
+    /// The test as it would run without `skip`, if it has a formula and
+    /// an inline expected value.
+    pub case: Option<TestCase>,
+    /// Stable identifier from the spec (`id`), if any.
+    pub id: Option<String>,
+    /// Line of the spec file defining the test, if found.
+    pub line: Option<usize>,
+}
+
+/// A test case whose expected value lives in a reference XLSX.
+///
+/// Resolved into a [`TestCase`] once the referenced cell has been read.
+#[derive(Debug, Clone)]
+pub struct XlsxExpectation {
+    /// Fully qualified name (e.g., `assumptions.test_npv_model`).
+    pub name: String,
+    /// The Excel formula to evaluate.
+    pub formula: String,
+    /// Cell reference (`file.xlsx!Sheet!A1`).
+    pub cell_ref: String,
+    /// Fully qualified names of tests that must pass first.
+    pub depends_on: Vec<String>,
+    /// Factor applied to the actual value before comparison.
+    pub scale: Option<f64>,
+    /// Per-test tolerance.
+    pub tolerance: Option<f64>,
+    /// Per-test tolerance in ULPs.
+    pub tolerance_ulps: Option<u64>,
+    /// Radix the result is written in.
+    pub base: Option<u32>,
+    /// Explicit result cell.
+    pub result_cell: Option<String>,
+    /// Stable identifier.
+    pub id: Option<String>,
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -90,24 +551,46 @@ pub struct SkipCase {
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Result of running a test.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "lowercase")]
 pub enum TestResult {
     /// Test passed - actual matches expected.
     Pass {
         /// Test name.
         name: String,
+        /// Stable test identifier (`id` in the spec), if any.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
         /// Formula evaluated.
         formula: String,
         /// Expected value.
         expected: f64,
         /// Actual value from spreadsheet engine.
         actual: f64,
+        /// `true` if the result was found by matching the expected value
+        /// rather than via the labeled result cell (low confidence).
+        heuristic: bool,
+        /// What `forge calculate` gave when it disagreed with the engine
+        /// (`--annotate-forge`); the test still passes on the engine's value.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        forge: Option<f64>,
+        /// Wall-clock time to evaluate the test (if measured).
+        #[serde(
+            rename = "duration_secs",
+            default,
+            serialize_with = "serialize_secs",
+            deserialize_with = "deserialize_secs",
+            skip_serializing_if = "Option::is_none"
+        )]
+        duration: Option<Duration>,
     },
     /// Test failed - mismatch or error.
     Fail {
         /// Test name.
         name: String,
+        /// Stable test identifier (`id` in the spec), if any.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
         /// Formula evaluated.
         formula: String,
         /// Expected value.
@@ -116,22 +599,104 @@ pub enum TestResult {
         actual: Option<f64>,
         /// Error message (if any).
         error: Option<String>,
+        /// Wall-clock time to evaluate the test (if measured).
+        #[serde(
+            rename = "duration_secs",
+            default,
+            serialize_with = "serialize_secs",
+            deserialize_with = "deserialize_secs",
+            skip_serializing_if = "Option::is_none"
+        )]
+        duration: Option<Duration>,
     },
     /// Test was skipped.
     Skip {
         /// Test name.
         name: String,
+        /// Stable test identifier (`id` in the spec), if any.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
         /// Reason for skipping.
         reason: String,
     },
 }
 
 impl TestResult {
+    /// Builds a pass for `case`.
+    pub fn pass(case: &TestCase, actual: f64, heuristic: bool) -> Self {
+        Self::Pass {
+            name: case.name.clone(),
+            id: case.id.clone(),
+            formula: case.formula.clone(),
+            expected: case.expected,
+            actual,
+            heuristic,
+            forge: None,
+            duration: None,
+        }
+    }
+
+    /// Builds a pass or failure for `case` depending on whether it accepts `actual`.
+    ///
+    /// The result records the scaled actual, so it can be read directly
+    /// against `expected`.
+    pub fn compare(case: &TestCase, actual: f64, heuristic: bool, tolerance: f64) -> Self {
+        let scaled = case.scaled(actual);
+        if case.accepts(actual, tolerance) {
+            return Self::pass(case, scaled, heuristic);
+        }
+        let mut notes = Vec::new();
+        if !case.expected_any.is_empty() {
+            let values: Vec<String> = case.acceptable_values().map(|v| v.to_string()).collect();
+            notes.push(format!(
+                "Expected any of [{}], got {scaled}",
+                values.join(", ")
+            ));
+        }
+        if let Some(scale) = case.scale {
+            notes.push(format!("forge returned {actual}, scaled by {scale}"));
+        }
+        if let Some(base) = case.base {
+            notes.push(format!(
+                "In base {base}: expected {}, got {}",
+                format_in_base(case.expected, base),
+                format_in_base(scaled, base)
+            ));
+        }
+        let error = (!notes.is_empty()).then(|| notes.join("; "));
+        Self::fail(case, Some(scaled), error)
+    }
+
+    /// Builds a failure for `case`.
+    pub fn fail(case: &TestCase, actual: Option<f64>, error: Option<String>) -> Self {
+        Self::Fail {
+            name: case.name.clone(),
+            id: case.id.clone(),
+            formula: case.formula.clone(),
+            expected: case.expected,
+            actual,
+            error,
+            duration: None,
+        }
+    }
+
     /// Returns `true` if this result is a pass.
     pub const fn is_pass(&self) -> bool {
         matches!(self, Self::Pass { .. })
     }
 
+    /// Returns `true` if this result is a pass found by the fallback
+    /// value match rather than a labeled result cell.
+    pub const fn is_heuristic_pass(&self) -> bool {
+        matches!(
+            self,
+            Self::Pass {
+                heuristic: true,
+                ..
+            }
+        )
+    }
+
     /// Returns `true` if this result is a failure.
     pub const fn is_fail(&self) -> bool {
         matches!(self, Self::Fail { .. })
@@ -143,6 +708,71 @@ impl TestResult {
             Self::Pass { name, .. } | Self::Fail { name, .. } | Self::Skip { name, .. } => name,
         }
     }
+
+    /// Returns the stable test identifier (`id` in the spec), if any.
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Self::Pass { id, .. } | Self::Fail { id, .. } | Self::Skip { id, .. } => id.as_deref(),
+        }
+    }
+
+    /// Returns the key identifying the test across runs; see [`TestCase::key`].
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Pass { name, id, .. }
+            | Self::Fail { name, id, .. }
+            | Self::Skip { name, id, .. } => id.as_deref().unwrap_or(name),
+        }
+    }
+
+    /// Returns how long the test took, if it was measured.
+    pub const fn duration(&self) -> Option<Duration> {
+        match self {
+            Self::Pass { duration, .. } | Self::Fail { duration, .. } => *duration,
+            Self::Skip { .. } => None,
+        }
+    }
+
+    /// Records the value `forge calculate` gave for a pass that disagrees
+    /// with it (ignored for failures and skips).
+    #[must_use]
+    pub const fn with_forge_value(mut self, value: f64) -> Self {
+        if let Self::Pass { forge, .. } = &mut self {
+            *forge = Some(value);
+        }
+        self
+    }
+
+    /// Records how long the test took (ignored for skips).
+    #[must_use]
+    pub const fn with_duration(mut self, elapsed: Duration) -> Self {
+        match &mut self {
+            Self::Pass { duration, .. } | Self::Fail { duration, .. } => *duration = Some(elapsed),
+            Self::Skip { .. } => {}
+        }
+        self
+    }
+}
+
+/// Serializes a duration as fractional seconds.
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+fn serialize_secs<S: serde::Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(d) => serializer.serialize_some(&d.as_secs_f64()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserializes a duration written by [`serialize_secs`].
+fn deserialize_secs<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Option::<f64>::deserialize(deserializer)?
+        .map(|secs| Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom))
+        .transpose()
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -152,35 +782,199 @@ impl TestResult {
 /// Extracts test cases from a test spec.
 ///
 /// Scans all sections for scalar values that have both a formula and
-/// an expected value defined. Tests with `skip` field are excluded.
+/// an expected value defined. Tests with `skip` field are excluded, as are
+/// tests reading their expected value from an XLSX (see
+/// [`extract_xlsx_expectations`]).
 pub fn extract_test_cases(spec: &TestSpec) -> Vec<TestCase> {
     let mut cases = Vec::new();
 
-    for (section_name, section) in &spec.sections {
-        // Skip non-test sections
-        if section_name.starts_with('_') || section_name == "scenarios" {
+    for (section_name, name, scalar) in spec.tests() {
+        // Skip tests marked with skip field
+        if scalar.skip.is_some() || scalar.expected_from_xlsx.is_some() {
             continue;
         }
+        if let Some(outputs) = &scalar.outputs {
+            cases.extend(output_test_cases(section_name, name, scalar, outputs));
+            continue;
+        }
+        cases.extend(scalar_test_case(section_name, name, scalar));
+    }
+    // Table tests not yet implemented
 
-        if let Section::ScalarGroup(scalars) = section {
-            for (name, scalar) in scalars {
-                // Skip tests marked with skip field
-                if scalar.skip.is_some() {
-                    continue;
-                }
-                if let (Some(formula), Some(expected)) = (&scalar.formula, scalar.expected) {
-                    cases.push(TestCase {
-                        name: format!("{section_name}.{name}"),
-                        formula: formula.clone(),
-                        expected,
-                    });
+    cases
+}
+
+/// Builds the test case for `scalar`, ignoring `skip`.
+///
+/// Returns `None` without a formula or an inline expected value, or when a
+/// text `expected` is neither a valid integer in `base` nor non-finite nor
+/// a duration. Tests expecting an export error need no expected value.
+fn scalar_test_case(section_name: &str, name: &str, scalar: &Scalar) -> Option<TestCase> {
+    let expect_export_error = scalar.expect_export_error == Some(true);
+    let duration = matches!(
+        &scalar.expected,
+        Some(Expected::Text(text)) if scalar.base.is_none() && text.contains(':')
+    );
+    // Without `expected`, the first acceptable value stands in for it
+    let mut expected_any = scalar.expected_any.clone().unwrap_or_default();
+    let expected = match &scalar.expected {
+        // A rejected formula has no value to compare
+        _ if expect_export_error => {
+            expected_any.clear();
+            f64::NAN
+        }
+        Some(Expected::Number(expected)) => *expected,
+        Some(Expected::Text(text)) => match scalar.base {
+            // Unparseable text is reported by `spec_errors` before extraction
+            Some(base) => parse_in_base(text, base)?,
+            None => parse_non_finite(text).or_else(|| parse_duration_to_days(text))?,
+        },
+        None if !expected_any.is_empty() => expected_any.remove(0),
+        None => return None,
+    };
+    Some(TestCase {
+        name: format!("{section_name}.{name}"),
+        formula: scalar.formula.clone()?,
+        expected,
+        expected_any,
+        depends_on: qualify_dependencies(section_name, scalar),
+        scale: scalar.scale,
+        tolerance: scalar.tolerance(),
+        tolerance_ulps: scalar.tolerance_ulps(),
+        base: scalar.base,
+        result_cell: scalar.result_cell.clone(),
+        id: scalar.id.clone(),
+        expect_export_error,
+        duration,
+        ..TestCase::default()
+    })
+}
+
+/// Builds one test case per named output of the test `name`, sorted by
+/// output name so the outputs run (and report) in a stable order.
+///
+/// Outputs without a formula or expected value are left out, as for
+/// plain tests.
+fn output_test_cases(
+    section_name: &str,
+    name: &str,
+    scalar: &Scalar,
+    outputs: &HashMap<String, Scalar>,
+) -> Vec<TestCase> {
+    let mut names: Vec<&String> = outputs.keys().collect();
+    names.sort();
+    let depends_on = qualify_dependencies(section_name, scalar);
+    names
+        .into_iter()
+        .filter_map(|output| {
+            let mut case =
+                scalar_test_case(section_name, &format!("{name}.{output}"), &outputs[output])?;
+            case.depends_on.extend(depends_on.iter().cloned());
+            case.output_of = Some(format!("{section_name}.{name}"));
+            Some(case)
+        })
+        .collect()
+}
+
+/// Returns `scalar`'s dependencies as fully qualified test names.
+///
+/// Unqualified names refer to tests in the same section.
+fn qualify_dependencies(section_name: &str, scalar: &Scalar) -> Vec<String> {
+    scalar
+        .depends_on
+        .iter()
+        .flatten()
+        .map(|dep| {
+            if dep.contains('.') {
+                dep.clone()
+            } else {
+                format!("{section_name}.{dep}")
+            }
+        })
+        .collect()
+}
+
+/// Extracts tests that have a formula but no expected value yet, as
+/// `(name, scalar)`, for `--record` to fill in.
+///
+/// Tests with any other source of expectation (`expected_any`,
+/// `expected_from_xlsx`, `outputs`, `expect_export_error`) or a `skip` are
+/// left alone.
+pub fn extract_unrecorded_tests(spec: &TestSpec) -> Vec<(String, &Scalar)> {
+    spec.tests()
+        .filter(|(_, _, scalar)| {
+            scalar.expected.is_none()
+                && scalar.expected_any.is_none()
+                && scalar.expected_from_xlsx.is_none()
+                && scalar.outputs.is_none()
+                && scalar.expect_export_error.is_none()
+                && scalar.skip.is_none()
+                && scalar.formula.is_some()
+        })
+        .map(|(section_name, name, scalar)| (format!("{section_name}.{name}"), scalar))
+        .collect()
+}
+
+/// Returns what makes tests in `spec` invalid, one `section.test: reason`
+/// line each, sorted (empty for a valid spec).
+///
+/// Loading fails on any of these rather than dropping the test: a test
+/// written at the top level of a spec without `_default_section`, one
+/// setting both `tolerance` and `tolerance_bps`, a text `expected` that
+/// isn't an integer in the test's `base`, or, without a base, neither a
+/// non-finite value nor a duration, or an output with a `result_cell`.
+pub fn spec_errors(spec: &TestSpec) -> Vec<String> {
+    let mut errors = Vec::new();
+    if spec.default_section.is_none() {
+        for (name, section) in &spec.sections {
+            if matches!(section, Section::Test(_)) && !name.starts_with('_') && name != "scenarios"
+            {
+                errors.push(format!(
+                    "{name}: written at the top level, but the spec has no _default_section"
+                ));
+            }
+        }
+    }
+    for (section_name, name, scalar) in spec.tests() {
+        let mut check = |name: &str, scalar: &Scalar, output: bool| {
+            if scalar.tolerance.is_some() && scalar.tolerance_bps.is_some() {
+                errors.push(format!(
+                    "{section_name}.{name}: set tolerance or tolerance_bps, not both"
+                ));
+            }
+            if let Some(Expected::Text(text)) = &scalar.expected {
+                if let Some(reason) = expected_text_error(text, scalar.base) {
+                    errors.push(format!("{section_name}.{name}: expected {text:?} {reason}"));
                 }
             }
+            // Outputs share one export, where each is found by its label
+            if output && scalar.result_cell.is_some() {
+                errors.push(format!(
+                    "{section_name}.{name}: result_cell is not supported on outputs"
+                ));
+            }
+        };
+        check(name, scalar, false);
+        let mut outputs: Vec<_> = scalar.outputs.iter().flatten().collect();
+        outputs.sort_by_key(|(output, _)| *output);
+        for (output, scalar) in outputs {
+            check(&format!("{name}.{output}"), scalar, true);
         }
-        // Table tests not yet implemented
     }
+    errors.sort();
+    errors
+}
 
-    cases
+/// Returns why a text `expected` can't be read, if it can't.
+fn expected_text_error(text: &str, base: Option<u32>) -> Option<String> {
+    if let Some(base) = base {
+        return parse_in_base(text, base)
+            .is_none()
+            .then(|| format!("is not an integer in base {base}"));
+    }
+    (parse_non_finite(text).is_none() && parse_duration_to_days(text).is_none()).then(|| {
+        "is not a number, a non-finite value (inf, -inf, nan) or a duration (h:mm:ss)".to_string()
+    })
 }
 
 /// Extracts skip cases from a test spec.
@@ -189,26 +983,108 @@ pub fn extract_test_cases(spec: &TestSpec) -> Vec<TestCase> {
 pub fn extract_skip_cases(spec: &TestSpec) -> Vec<SkipCase> {
     let mut cases = Vec::new();
 
-    for (section_name, section) in &spec.sections {
-        if section_name.starts_with('_') || section_name == "scenarios" {
-            continue;
+    for (section_name, name, scalar) in spec.tests() {
+        if let Some(reason) = &scalar.skip {
+            cases.push(SkipCase {
+                name: format!("{section_name}.{name}"),
+                reason: reason.clone(),
+                case: scalar_test_case(section_name, name, scalar),
+                id: scalar.id.clone(),
+                line: None,
+            });
         }
+    }
 
-        if let Section::ScalarGroup(scalars) = section {
-            for (name, scalar) in scalars {
-                if let Some(reason) = &scalar.skip {
-                    cases.push(SkipCase {
-                        name: format!("{section_name}.{name}"),
-                        reason: reason.clone(),
-                    });
-                }
-            }
+    cases
+}
+
+/// Extracts tests whose expected value comes from a reference XLSX.
+///
+/// Returns tests with both a formula and `expected_from_xlsx` set.
+pub fn extract_xlsx_expectations(spec: &TestSpec) -> Vec<XlsxExpectation> {
+    let mut cases = Vec::new();
+
+    for (section_name, name, scalar) in spec.tests() {
+        if scalar.skip.is_some() {
+            continue;
+        }
+        if let (Some(formula), Some(cell_ref)) = (&scalar.formula, &scalar.expected_from_xlsx) {
+            cases.push(XlsxExpectation {
+                name: format!("{section_name}.{name}"),
+                formula: formula.clone(),
+                cell_ref: cell_ref.clone(),
+                depends_on: qualify_dependencies(section_name, scalar),
+                scale: scalar.scale,
+                tolerance: scalar.tolerance(),
+                tolerance_ulps: scalar.tolerance_ulps(),
+                base: scalar.base,
+                result_cell: scalar.result_cell.clone(),
+                id: scalar.id.clone(),
+            });
         }
     }
 
     cases
 }
 
+/// Returns the 1-based line of `content` that defines the test `name`
+/// (`section.test`), for pointing at it in CI annotations.
+///
+/// A textual search rather than a parse: the first key `test` indented
+/// under the top-level key `section`, or a top-level key `test` when
+/// `section` is the spec's `_default_section`. Keys may be quoted, so this
+/// also finds tests in pretty-printed JSON specs.
+pub fn source_line(content: &str, name: &str) -> Option<usize> {
+    let (section, test) = name.split_once('.')?;
+    // A named output is defined by its test
+    let test = test.split('.').next().unwrap_or(test);
+    let key = |line: &str| {
+        let (key, _) = line.trim().split_once(':')?;
+        Some(key.trim().trim_matches(['"', '\'']).to_string())
+    };
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let mut lines = content.lines().enumerate();
+    // JSON nests every section one level deeper than YAML
+    let Some(section_indent) = lines
+        .by_ref()
+        .find(|(_, line)| key(line).as_deref() == Some(section))
+        .map(|(_, line)| indent(line))
+    else {
+        // A test written at the top level, beside `_default_section`
+        let default = content
+            .lines()
+            .find(|line| key(line).as_deref() == Some("_default_section"))?;
+        let (_, value) = default.split_once(':')?;
+        if value.trim().trim_end_matches(',').trim_matches(['"', '\'']) != section {
+            return None;
+        }
+        return content
+            .lines()
+            .position(|line| key(line).as_deref() == Some(test) && indent(line) == indent(default))
+            .map(|index| index + 1);
+    };
+    lines
+        .take_while(|(_, line)| line.trim().is_empty() || indent(line) > section_indent)
+        .find(|(_, line)| key(line).as_deref() == Some(test))
+        .map(|(index, _)| index + 1)
+}
+
+/// Sets the `line` of `cases` and `skips` extracted from `content`.
+///
+/// `serde_yaml_ng` keeps no spans, so this is a pass over the raw text
+/// after parsing.
+pub fn attach_source_lines(content: &str, cases: &mut [TestCase], skips: &mut [SkipCase]) {
+    for case in cases.iter_mut() {
+        case.line = source_line(content, &case.name);
+    }
+    for skip in skips.iter_mut() {
+        skip.line = source_line(content, &skip.name);
+        if let Some(case) = &mut skip.case {
+            case.line = skip.line;
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────────────────
@@ -216,6 +1092,24 @@ pub fn extract_skip_cases(spec: &TestSpec) -> Vec<SkipCase> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::DEFAULT_TOLERANCE;
+
+    #[test]
+    fn volatile_functions_finds_clock_and_random_calls() {
+        assert_eq!(volatile_functions("=YEAR(NOW())"), vec!["NOW"]);
+        assert_eq!(
+            volatile_functions("=today()+RANDBETWEEN(1,6)+Rand()+NOW()+TODAY()"),
+            vec!["TODAY", "RANDBETWEEN", "RAND", "NOW"]
+        );
+        assert!(volatile_functions("=SUM(1,2)").is_empty());
+    }
+
+    #[test]
+    fn volatile_functions_ignores_strings_and_longer_names() {
+        assert!(volatile_functions(r#"=LEN("NOW()")"#).is_empty());
+        assert!(volatile_functions("=RANDARRAY(2)+MY_NOW(1)+NOW").is_empty());
+        assert_eq!(volatile_functions(r#"=IF("a"="a",RAND(),0)"#), vec!["RAND"]);
+    }
 
     #[test]
     fn parse_spec_extracts_test_cases() {
@@ -236,13 +1130,619 @@ assumptions:
         assert!((cases[0].expected - 42.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn ids_are_carried_to_cases_skips_and_results() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+math:
+  test_abs:
+    formula: "=ABS(-1)"
+    expected: 1
+    id: abs-001
+  test_sqrt:
+    formula: "=SQRT(4)"
+    expected: 2
+  test_rand:
+    formula: "=RAND()"
+    skip: volatile
+    id: rand-001
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let mut cases = extract_test_cases(&spec);
+        cases.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(cases[0].key(), "abs-001");
+        // Without an id, the name is the key
+        assert_eq!(cases[1].key(), "math.test_sqrt");
+
+        let result = TestResult::compare(&cases[0], 1.0, false, DEFAULT_TOLERANCE);
+        assert_eq!(result.key(), "abs-001");
+        assert_eq!(result.name(), "math.test_abs");
+        let failed = TestResult::fail(&cases[1], None, None);
+        assert_eq!(failed.key(), "math.test_sqrt");
+
+        let skips = extract_skip_cases(&spec);
+        assert_eq!(skips[0].id.as_deref(), Some("rand-001"));
+    }
+
+    #[test]
+    fn base_tests_parse_text_expectations() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+engineering:
+  test_dec2hex:
+    formula: "=DEC2HEX(255)"
+    expected: "FF"
+    base: 16
+  test_dec2bin:
+    formula: "=DEC2BIN(5)"
+    expected: 5
+    base: 2
+  test_hex2dec:
+    formula: "=HEX2DEC(\"ff\")"
+    expected: 255
+  test_no_base:
+    formula: "=DEC2HEX(255)"
+    expected: "FF"
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let mut cases = extract_test_cases(&spec);
+        cases.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "engineering.test_dec2bin",
+                "engineering.test_dec2hex",
+                "engineering.test_hex2dec"
+            ]
+        );
+        // A bad digit fails loading rather than dropping the test
+        let bad: TestSpec =
+            serde_yaml_ng::from_str(&yaml.replace("\"FF\"\n    base", "\"FG\"\n    base")).unwrap();
+        let errors = spec_errors(&bad);
+        assert_eq!(
+            errors[0],
+            r#"engineering.test_dec2hex: expected "FG" is not an integer in base 16"#
+        );
+        // So does hex text without a base
+        assert!(errors[1].starts_with(r#"engineering.test_no_base: expected "FF" is not a number"#));
+        assert_eq!(spec_errors(&spec).len(), 1);
+
+        let (dec2bin, dec2hex, hex2dec) = (&cases[0], &cases[1], &cases[2]);
+        assert!((dec2hex.expected - 255.0).abs() < f64::EPSILON);
+        assert_eq!(dec2hex.parse_actual("ff"), Some(255.0));
+        assert_eq!(dec2hex.parse_actual("FG"), None);
+        // A binary result must not be read as the decimal 101
+        assert_eq!(dec2bin.parse_actual("101"), Some(5.0));
+        assert_eq!(hex2dec.parse_actual("1,024"), Some(1024.0));
+        assert!(TestResult::compare(dec2hex, 255.0, false, DEFAULT_TOLERANCE).is_pass());
+
+        let TestResult::Fail { error, .. } =
+            TestResult::compare(dec2hex, 254.0, false, DEFAULT_TOLERANCE)
+        else {
+            panic!("254 is not FF");
+        };
+        assert_eq!(error.as_deref(), Some("In base 16: expected FF, got FE"));
+    }
+
+    #[test]
+    fn format_in_base_matches_excel_digits() {
+        assert_eq!(format_in_base(255.0, 16), "FF");
+        assert_eq!(format_in_base(5.0, 2), "101");
+        assert_eq!(format_in_base(0.0, 8), "0");
+        assert_eq!(format_in_base(-10.0, 16), "-A");
+        assert_eq!(format_in_base(2.5, 16), "2.5");
+        assert_eq!(parse_in_base("-a", 16), Some(-10.0));
+        assert_eq!(parse_in_base("1", 1), None);
+    }
+
+    #[test]
+    fn expected_any_alone_or_with_expected() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+math:
+  test_round_half:
+    formula: "=ROUND(2.5, 0)"
+    expected_any: [2, 3]
+  test_round_mixed:
+    formula: "=ROUND(0.5, 0)"
+    expected: 1
+    expected_any: [0]
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let mut cases = extract_test_cases(&spec);
+        cases.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(cases[0].name, "math.test_round_half");
+        assert!((cases[0].expected - 2.0).abs() < f64::EPSILON);
+        assert!(
+            cases[0].accepts(2.0, DEFAULT_TOLERANCE) && cases[0].accepts(3.0, DEFAULT_TOLERANCE)
+        );
+        assert!(!cases[0].accepts(2.5, DEFAULT_TOLERANCE));
+        assert!(
+            cases[1].accepts(1.0, DEFAULT_TOLERANCE) && cases[1].accepts(0.0, DEFAULT_TOLERANCE)
+        );
+    }
+
+    #[test]
+    fn non_finite_expected_values_deserialize() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+math:
+  test_inf:
+    formula: "=1/0"
+    expected: .inf
+  test_neg_inf:
+    formula: "=-1/0"
+    expected: "-Infinity"
+  test_nan:
+    formula: "=0/0"
+    expected: .nan
+  test_word:
+    formula: "=1"
+    expected: "one"
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let mut cases = extract_test_cases(&spec);
+        cases.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // Text that isn't non-finite (and has no base) is a spec error
+        assert_eq!(spec_errors(&spec).len(), 1);
+        assert!(
+            spec_errors(&spec)[0].starts_with(r#"math.test_word: expected "one" is not a number"#)
+        );
+        let names: Vec<_> = cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["math.test_inf", "math.test_nan", "math.test_neg_inf"]
+        );
+        assert!(cases[0].expected.is_infinite() && cases[0].expected > 0.0);
+        assert!(cases[1].expected.is_nan());
+        assert!(cases[2].expected.is_infinite() && cases[2].expected < 0.0);
+    }
+
+    #[test]
+    fn durations_parse_to_fractions_of_a_day() {
+        let close = |text, days: f64| {
+            parse_duration_to_days(text).is_some_and(|d| (d - days).abs() < 1e-12)
+        };
+        assert!(close("1:30:00", 0.0625));
+        assert!(close("12:00", 0.5));
+        assert!(close("36:00:00", 1.5));
+        assert!(close("0:00:01.5", 1.5 / 86_400.0));
+        assert!(close("-6:00:00", -0.25));
+        assert!(close(" 0.0625 ", 0.0625));
+        assert!(close("2", 2.0));
+
+        assert_eq!(parse_duration_to_days("1:60:00"), None);
+        assert_eq!(parse_duration_to_days("1:00:60"), None);
+        assert_eq!(parse_duration_to_days("1:2:3:4"), None);
+        assert_eq!(parse_duration_to_days("inf"), None);
+        assert_eq!(parse_duration_to_days("soon"), None);
+    }
+
+    #[test]
+    fn duration_expected_matches_time_formatted_result() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+time:
+  test_duration:
+    formula: "=TIME(1,30,0)"
+    expected: "1:30:00"
+    tolerance: 0.00001
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let cases = extract_test_cases(&spec);
+        assert!((cases[0].expected - 0.0625).abs() < f64::EPSILON);
+
+        let tolerance = cases[0].tolerance.unwrap();
+        let actual = cases[0].parse_actual("01:30:00").unwrap();
+        assert!(cases[0].accepts(actual, tolerance));
+        let actual = cases[0].parse_actual("0.0625").unwrap();
+        assert!(cases[0].accepts(actual, tolerance));
+        let actual = cases[0].parse_actual("1:31:00").unwrap();
+        assert!(!cases[0].accepts(actual, tolerance));
+
+        // Only tests expecting a duration read time-formatted cells
+        let plain = TestCase {
+            duration: false,
+            ..cases[0].clone()
+        };
+        assert_eq!(plain.parse_actual("1:30"), None);
+        assert_eq!(plain.parse_actual("0.0625"), Some(0.0625));
+    }
+
+    #[test]
+    fn source_line_finds_test_under_its_section() {
+        let yaml = r#"_forge_version: "1.0.0"
+math:
+  test_abs:
+    formula: "=ABS(-1)"
+    expected: 1
+  test_sqrt:
+    formula: "=SQRT(4)"
+    expected: 2
+text:
+  test_sqrt:
+    formula: "=LEN(\"ab\")"
+    expected: 2
+"#;
+        assert_eq!(source_line(yaml, "math.test_abs"), Some(3));
+        assert_eq!(source_line(yaml, "math.test_sqrt"), Some(6));
+        assert_eq!(source_line(yaml, "text.test_sqrt"), Some(10));
+        assert_eq!(source_line(yaml, "text.test_abs"), None);
+        assert_eq!(source_line(yaml, "dates.test_abs"), None);
+
+        let json = r#"{
+  "math": {
+    "test_abs": { "formula": "=ABS(-1)", "expected": 1 }
+  }
+}"#;
+        assert_eq!(source_line(json, "math.test_abs"), Some(3));
+    }
+
+    #[test]
+    fn attach_source_lines_sets_case_and_skip_lines() {
+        let yaml = r#"_forge_version: "1.0.0"
+math:
+  test_abs:
+    formula: "=ABS(-1)"
+    expected: 1
+
+  test_rand:
+    formula: "=RAND()"
+    expected: 0.5
+    skip: "volatile"
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let mut cases = extract_test_cases(&spec);
+        let mut skips = extract_skip_cases(&spec);
+        attach_source_lines(yaml, &mut cases, &mut skips);
+        assert_eq!(cases[0].line, Some(3));
+        assert_eq!(skips[0].line, Some(7));
+        assert_eq!(skips[0].case.as_ref().and_then(|c| c.line), Some(7));
+    }
+
+    #[test]
+    fn expect_export_error_needs_no_expected_value() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+errors:
+  test_unknown_function:
+    formula: "=NOSUCHFUNC(1)"
+    expect_export_error: true
+  test_no_expected:
+    formula: "=1+1"
+    expect_export_error: false
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let cases = extract_test_cases(&spec);
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].name, "errors.test_unknown_function");
+        assert!(cases[0].expect_export_error);
+        assert!(cases[0].expected.is_nan());
+    }
+
+    #[test]
+    fn outputs_become_one_case_per_output() {
+        let yaml = r#"_forge_version: "1.0.0"
+model:
+  test_base:
+    formula: "=120"
+    expected: 120
+  test_pnl:
+    depends_on: [test_base]
+    outputs:
+      net:
+        formula: "=120-20"
+        expected: 100
+      gross:
+        formula: "=120"
+        expected: 120
+        tolerance: 0.5
+      note:
+        formula: "=1"
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let mut cases = extract_test_cases(&spec);
+        attach_source_lines(yaml, &mut cases, &mut []);
+        cases.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = cases.iter().map(|c| c.name.as_str()).collect();
+        // An output without an expected value is not a test
+        assert_eq!(
+            names,
+            vec![
+                "model.test_base",
+                "model.test_pnl.gross",
+                "model.test_pnl.net"
+            ]
+        );
+        let gross = &cases[1];
+        assert_eq!(gross.output_of.as_deref(), Some("model.test_pnl"));
+        assert_eq!(gross.depends_on, vec!["model.test_base"]);
+        assert_eq!(gross.tolerance, Some(0.5));
+        assert!((cases[2].expected - 100.0).abs() < f64::EPSILON);
+        // Outputs point at the test defining them
+        assert_eq!(gross.line, Some(6));
+        assert_eq!(cases[0].output_of, None);
+    }
+
+    #[test]
+    fn outputs_take_durations_but_not_result_cells() {
+        let yaml = r#"_forge_version: "1.0.0"
+model:
+  test_shift:
+    outputs:
+      length:
+        formula: "=TIME(1,30,0)"
+        expected: "1:30:00"
+      start:
+        formula: "=TIME(9,0,0)"
+        expected: 0.375
+        result_cell: "Scalars!B2"
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let cases = extract_test_cases(&spec);
+        let length = cases
+            .iter()
+            .find(|c| c.name == "model.test_shift.length")
+            .unwrap();
+        assert!(length.duration);
+        assert!((length.expected - 1.5 / 24.0).abs() < 1e-12);
+        // Outputs share one export, where a result cell can't apply
+        assert_eq!(
+            spec_errors(&spec),
+            ["model.test_shift.start: result_cell is not supported on outputs"]
+        );
+    }
+
+    #[test]
+    fn tolerance_bps_converts_to_absolute_tolerance() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+rates:
+  test_rate:
+    formula: "=RATE(10,-100,800)"
+    expected: 0.0414
+    tolerance_bps: 5
+  test_both:
+    formula: "=0.05"
+    expected: 0.05
+    tolerance: 0.01
+    tolerance_bps: 5
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let cases = extract_test_cases(&spec);
+        let rate = cases.iter().find(|c| c.name == "rates.test_rate").unwrap();
+        let tolerance = rate.tolerance.unwrap();
+        assert!((tolerance - 0.0005).abs() < 1e-12);
+        assert!(rate.accepts(0.0418, tolerance));
+        assert!(rate.accepts(0.0410, tolerance));
+        assert!(!rate.accepts(0.0420, tolerance));
+
+        // Two absolute tolerances are ambiguous
+        assert_eq!(
+            spec_errors(&spec),
+            ["rates.test_both: set tolerance or tolerance_bps, not both"]
+        );
+    }
+
+    #[test]
+    fn tolerance_ulps_compares_in_representable_steps() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+math:
+  test_third:
+    formula: "=1/3"
+    expected: 0.3333333333333333
+    tolerance_ulps: 2
+  test_both:
+    formula: "=0.1"
+    expected: 0.1
+    tolerance: 0.01
+    tolerance_ulps: 2
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let cases = extract_test_cases(&spec);
+        let third = cases.iter().find(|c| c.name == "math.test_third").unwrap();
+        assert_eq!(third.tolerance_ulps, Some(2));
+        let expected = third.expected;
+        let step = |x: f64, n: u64| f64::from_bits(x.to_bits() + n);
+        // The global tolerance no longer applies, however loose
+        assert!(third.accepts(step(expected, 2), 1.0));
+        assert!(!third.accepts(step(expected, 3), 1.0));
+
+        // An absolute tolerance takes precedence
+        let both = cases.iter().find(|c| c.name == "math.test_both").unwrap();
+        assert_eq!(both.tolerance_ulps, None);
+    }
+
+    #[test]
+    fn ulp_distance_counts_representable_doubles() {
+        assert_eq!(ulp_distance(1.0, 1.0), 0);
+        assert_eq!(ulp_distance(1.0, 1.0 + f64::EPSILON), 1);
+        assert_eq!(ulp_distance(1.0, 1.0 - f64::EPSILON / 2.0), 1);
+        assert_eq!(ulp_distance(-1.0, -1.0 - f64::EPSILON), 1);
+
+        // Zeros are equal, and the denormals either side are one step out
+        let denorm = f64::from_bits(1);
+        assert_eq!(ulp_distance(0.0, -0.0), 0);
+        assert_eq!(ulp_distance(0.0, denorm), 1);
+        assert_eq!(ulp_distance(-0.0, -denorm), 1);
+        assert_eq!(ulp_distance(-denorm, denorm), 2);
+        assert_eq!(
+            ulp_distance(
+                f64::MIN_POSITIVE,
+                f64::from_bits(f64::MIN_POSITIVE.to_bits() - 1)
+            ),
+            1
+        );
+
+        // Crossing zero counts the steps on both sides
+        assert!(values_match_ulps(denorm, -denorm, 2));
+        assert!(!values_match_ulps(denorm, -denorm, 1));
+        assert!(!values_match_ulps(1e-300, -1e-300, 1_000));
+
+        // Non-finite values only match themselves
+        assert!(!values_match_ulps(f64::INFINITY, f64::MAX, u64::MAX));
+        assert!(values_match_ulps(f64::INFINITY, f64::INFINITY, 0));
+        assert!(values_match_ulps(f64::NAN, f64::NAN, 0));
+        assert!(!values_match_ulps(f64::NAN, 1.0, u64::MAX));
+    }
+
+    #[test]
+    fn non_finite_values_only_match_themselves() {
+        // NaN never equals itself under `==`, but a NaN expectation is met
+        assert!(values_match(f64::NAN, f64::NAN, DEFAULT_TOLERANCE));
+        assert!(!values_match(0.0, f64::NAN, DEFAULT_TOLERANCE));
+        assert!(!values_match(f64::INFINITY, f64::NAN, DEFAULT_TOLERANCE));
+
+        // inf - inf is NaN, so a plain tolerance check would reject this
+        assert!(values_match(
+            f64::INFINITY,
+            f64::INFINITY,
+            DEFAULT_TOLERANCE
+        ));
+        assert!(!values_match(
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            DEFAULT_TOLERANCE
+        ));
+        assert!(!values_match(f64::MAX, f64::INFINITY, f64::MAX));
+
+        // Finite expectations reject non-finite actuals, whatever the tolerance
+        assert!(!values_match(f64::NAN, 1.0, f64::INFINITY));
+        assert!(!values_match(f64::INFINITY, 1.0, 1e300));
+        assert!(values_match(1.0 + 1e-9, 1.0, 1e-6));
+
+        assert_eq!(parse_non_finite("-.INF"), Some(f64::NEG_INFINITY));
+        assert_eq!(parse_non_finite("+inf"), Some(f64::INFINITY));
+        assert!(parse_non_finite("NaN").is_some_and(f64::is_nan));
+        assert_eq!(parse_non_finite("infinite"), None);
+    }
+
+    #[test]
+    fn result_cell_is_carried_into_test_case() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+math:
+  test_sum:
+    formula: "=SUM(1, 2)"
+    expected: 3
+    result_cell: "Scalars!B2"
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let cases = extract_test_cases(&spec);
+        assert_eq!(cases[0].result_cell.as_deref(), Some("Scalars!B2"));
+    }
+
+    #[test]
+    fn depends_on_is_qualified_by_section() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+model:
+  test_total:
+    formula: "=1+2"
+    expected: 3
+    depends_on: [test_base, other.test_rate]
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let cases = extract_test_cases(&spec);
+        assert_eq!(
+            cases[0].depends_on,
+            vec!["model.test_base", "other.test_rate"]
+        );
+    }
+
+    #[test]
+    fn compare_passes_on_any_listed_value() {
+        let case = TestCase {
+            name: "math.test_round_half".to_string(),
+            formula: "=ROUND(2.5, 0)".to_string(),
+            expected: 2.0,
+            expected_any: vec![3.0],
+            ..TestCase::default()
+        };
+        assert!(TestResult::compare(&case, 3.0, false, DEFAULT_TOLERANCE).is_pass());
+
+        let TestResult::Fail { actual, error, .. } =
+            TestResult::compare(&case, 4.0, false, DEFAULT_TOLERANCE)
+        else {
+            panic!("expected failure");
+        };
+        assert_eq!(actual, Some(4.0));
+        assert_eq!(error.as_deref(), Some("Expected any of [2, 3], got 4"));
+    }
+
+    #[test]
+    fn skip_cases_keep_runnable_test() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+dates:
+  test_datedif:
+    formula: "=DATEDIF(1, 400, \"Y\")"
+    expected: 1
+    skip: "Gnumeric & Excel differ"
+  test_note:
+    skip: "placeholder"
+"#;
+        let spec = TestSpec::parse(yaml, InputFormat::Yaml).unwrap();
+        let mut skips = extract_skip_cases(&spec);
+        skips.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let case = skips[0].case.as_ref().unwrap();
+        assert_eq!(case.name, "dates.test_datedif");
+        assert!(case.accepts(1.0, DEFAULT_TOLERANCE));
+        assert!(skips[1].case.is_none());
+    }
+
+    #[test]
+    fn scale_converts_actual_before_comparison() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+model:
+  test_in_thousands:
+    formula: "=1500/1000"
+    expected: 1500
+    scale: 1000
+  test_in_percent:
+    formula: "=25"
+    expected: 0.25
+    scale: 0.01
+"#;
+        let spec = TestSpec::parse(yaml, InputFormat::Yaml).unwrap();
+        let mut cases = extract_test_cases(&spec);
+        cases.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // Scaling up: forge reports thousands, expected is in units
+        let thousands = &cases[1];
+        assert_eq!(thousands.scale, Some(1000.0));
+        assert!(thousands.accepts(1.5, DEFAULT_TOLERANCE));
+        assert!(!thousands.accepts(1500.0, DEFAULT_TOLERANCE));
+
+        // Scaling down: forge reports percent, expected is a fraction
+        let percent = &cases[0];
+        assert!(percent.accepts(25.0, DEFAULT_TOLERANCE));
+        let TestResult::Fail { actual, error, .. } =
+            TestResult::compare(percent, 30.0, false, DEFAULT_TOLERANCE)
+        else {
+            panic!("expected failure");
+        };
+        assert!((actual.unwrap() - 0.3).abs() < f64::EPSILON);
+        assert_eq!(error.as_deref(), Some("forge returned 30, scaled by 0.01"));
+    }
+
     #[test]
     fn test_result_is_pass() {
         let pass = TestResult::Pass {
             name: "test".to_string(),
+            id: None,
             formula: "=1".to_string(),
             expected: 1.0,
             actual: 1.0,
+            heuristic: false,
+            forge: None,
+            duration: None,
         };
         assert!(pass.is_pass());
         assert!(!pass.is_fail());
@@ -252,10 +1752,12 @@ assumptions:
     fn test_result_is_fail() {
         let fail = TestResult::Fail {
             name: "test".to_string(),
+            id: None,
             formula: "=1".to_string(),
             expected: 1.0,
             actual: Some(2.0),
             error: None,
+            duration: None,
         };
         assert!(fail.is_fail());
         assert!(!fail.is_pass());
@@ -265,19 +1767,26 @@ assumptions:
     fn test_result_name() {
         let pass = TestResult::Pass {
             name: "pass_test".to_string(),
+            id: None,
             formula: "=1".to_string(),
             expected: 1.0,
             actual: 1.0,
+            heuristic: false,
+            forge: None,
+            duration: None,
         };
         let fail = TestResult::Fail {
             name: "fail_test".to_string(),
+            id: None,
             formula: "=1".to_string(),
             expected: 1.0,
             actual: None,
             error: Some("error".to_string()),
+            duration: None,
         };
         let skip = TestResult::Skip {
             name: "skip_test".to_string(),
+            id: None,
             reason: "not implemented".to_string(),
         };
 
@@ -328,6 +1837,55 @@ assumptions:
         assert!(cases[0].name.contains("test_real"));
     }
 
+    #[test]
+    fn top_level_tests_join_the_default_section() {
+        let yaml = r#"_forge_version: "1.0.0"
+_default_section: math
+test_abs:
+  formula: "=ABS(-1)"
+  expected: 1
+test_skipped:
+  formula: "=NOW()"
+  skip: volatile
+rates:
+  test_rate:
+    formula: "=5/100"
+    expected: 0.05
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        let mut names: Vec<_> = extract_test_cases(&spec)
+            .into_iter()
+            .map(|case| case.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["math.test_abs", "rates.test_rate"]);
+        let skips = extract_skip_cases(&spec);
+        assert_eq!(skips[0].name, "math.test_skipped");
+
+        assert_eq!(source_line(yaml, "math.test_abs"), Some(3));
+        assert_eq!(source_line(yaml, "rates.test_rate"), Some(10));
+        assert_eq!(source_line(yaml, "other.test_abs"), None);
+    }
+
+    #[test]
+    fn top_level_tests_need_a_default_section() {
+        let yaml = r#"_forge_version: "1.0.0"
+test_abs:
+  formula: "=ABS(-1)"
+  expected: 1
+math:
+  test_sqrt:
+    formula: "=SQRT(4)"
+    expected: 2
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+        assert_eq!(
+            spec_errors(&spec),
+            ["test_abs: written at the top level, but the spec has no _default_section"]
+        );
+        assert_eq!(source_line(yaml, "math.test_abs"), None);
+    }
+
     #[test]
     fn extract_requires_both_formula_and_expected() {
         let yaml = r#"
@@ -349,4 +1907,30 @@ assumptions:
         assert_eq!(cases.len(), 1);
         assert!(cases[0].name.contains("complete"));
     }
+
+    #[test]
+    fn extract_xlsx_expectations_separates_reference_tests() {
+        let yaml = r#"
+_forge_version: "1.0.0"
+assumptions:
+  test_literal:
+    value: null
+    formula: "=1"
+    expected: 1
+  test_oracle:
+    value: null
+    formula: "=B2*2"
+    expected_from_xlsx: "model.xlsx!Sheet1!B2"
+"#;
+        let spec: TestSpec = serde_yaml_ng::from_str(yaml).unwrap();
+
+        let cases = extract_test_cases(&spec);
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].name, "assumptions.test_literal");
+
+        let refs = extract_xlsx_expectations(&spec);
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name, "assumptions.test_oracle");
+        assert_eq!(refs[0].cell_ref, "model.xlsx!Sheet1!B2");
+    }
 }